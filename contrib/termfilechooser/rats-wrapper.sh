@@ -0,0 +1,17 @@
+#!/bin/sh
+# xdg-desktop-portal-termfilechooser wrapper that opens rats in a terminal.
+#
+# The portal calls this with: multiple directory save path out [debug]
+# Set TERMCMD to the terminal to run rats in; it must not return until rats
+# exits, e.g. "foot", "kitty --title termfilechooser" or "alacritty -e".
+
+multiple="$1"
+directory="$2"
+save="$3"
+path="$4"
+out="$5"
+
+termcmd="${TERMCMD:-foot}"
+
+# shellcheck disable=SC2086 # TERMCMD may carry its own arguments
+exec $termcmd rats portal "$multiple" "$directory" "$save" "$path" "$out"
@@ -0,0 +1,95 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "fs")]
+use std::fs;
+
+#[cfg(feature = "fs")]
+use crate::walk;
+
+/// Where `fuzzy::search` gets its candidates: the file system, or where
+/// there is none to read (a browser, a web editor) the paths the host
+/// hands over.
+pub trait Backend {
+    /// The entries of `dir`, or with `recursive` every file below it. With
+    /// `gitignore`, paths ignored by git are left out if the backend knows
+    /// which they are.
+    fn list(&self, dir: &Path, recursive: bool, gitignore: bool) -> io::Result<Vec<PathBuf>>;
+
+    fn is_dir(&self, path: &Path) -> bool;
+}
+
+/// The file system, read through `walk`.
+#[cfg(feature = "fs")]
+pub struct FsBackend;
+
+#[cfg(feature = "fs")]
+impl Backend for FsBackend {
+    fn list(&self, dir: &Path, recursive: bool, gitignore: bool) -> io::Result<Vec<PathBuf>> {
+        // Fail on an unreadable directory rather than list nothing
+        fs::read_dir(dir)?;
+        Ok(if recursive {
+            walk::files(dir.to_path_buf(), gitignore).collect()
+        } else if gitignore {
+            walk::unignored_entries(dir).filter_map(|entry| entry.ok().map(|(path, _)| path)).collect()
+        } else {
+            fs::read_dir(dir)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect()
+        })
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+}
+
+/// Paths supplied by the host instead of read from disk. A path given with
+/// a trailing `/` is a directory, as is every parent of another path.
+#[derive(Debug, Clone, Default)]
+pub struct Items {
+    files: Vec<PathBuf>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl Items {
+    pub fn new<S: AsRef<str>>(items: impl IntoIterator<Item = S>) -> Items {
+        let mut files = Vec::new();
+        let mut dirs = HashSet::new();
+        for item in items {
+            let item = item.as_ref();
+            let path = PathBuf::from(item.trim_end_matches('/'));
+            dirs.extend(path.ancestors().skip(1).filter(|dir| !dir.as_os_str().is_empty()).map(Path::to_path_buf));
+            if item.ends_with('/') {
+                dirs.insert(path);
+            } else {
+                files.push(path);
+            }
+        }
+        Items { files, dirs }
+    }
+}
+
+impl Backend for Items {
+    fn list(&self, dir: &Path, recursive: bool, _gitignore: bool) -> io::Result<Vec<PathBuf>> {
+        // The empty path is the top level, whose entries have no parent dir
+        if !dir.as_os_str().is_empty() && !self.dirs.contains(dir) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} is not a directory", dir.display())));
+        }
+        Ok(if recursive {
+            self.files.iter().filter(|path| path.starts_with(dir)).cloned().collect()
+        } else {
+            self.files
+                .iter()
+                .chain(&self.dirs)
+                .filter(|path| path.parent() == Some(dir))
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.contains(path)
+    }
+}
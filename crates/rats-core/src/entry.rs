@@ -0,0 +1,82 @@
+use std::{fs::FileType, path::PathBuf};
+
+use crate::names::{safe_filename_for_matching, safe_filename_to_string};
+
+/// One row of the result list. Directory listings produce plain path
+/// entries; other sources (compiler diagnostics, ...) point at a line and
+/// carry their own label.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub label: Option<String>,
+    /// Text the filter matches in place of the label, when it differs
+    pub search: Option<String>,
+    /// Whether the path is a directory, when a directory listing already
+    /// said so; otherwise `is_dir` asks the filesystem
+    pub dir: Option<bool>,
+}
+
+impl Entry {
+    pub fn from_path(path: PathBuf) -> Entry {
+        Entry {
+            path,
+            line: None,
+            label: None,
+            search: None,
+            dir: None,
+        }
+    }
+
+    /// An entry read from a directory, whose type came with it. Symlinks
+    /// are left to `is_dir` to follow.
+    pub fn listed(path: PathBuf, file_type: Option<FileType>) -> Entry {
+        Entry {
+            dir: file_type.filter(|t| !t.is_symlink()).map(|t| t.is_dir()),
+            ..Entry::from_path(path)
+        }
+    }
+
+    pub fn labeled(path: PathBuf, label: String) -> Entry {
+        Entry {
+            path,
+            line: None,
+            label: Some(label),
+            search: None,
+            dir: None,
+        }
+    }
+
+    pub fn at_line(path: PathBuf, line: usize, label: String) -> Entry {
+        Entry {
+            path,
+            line: Some(line),
+            label: Some(label),
+            search: None,
+            dir: None,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.dir.unwrap_or_else(|| self.path.is_dir())
+    }
+
+    /// Text shown in the list.
+    pub fn display_name(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => safe_filename_to_string(&self.path),
+        }
+    }
+
+    /// Text the filter is matched against.
+    pub fn match_text(&self) -> Option<String> {
+        if let Some(search) = &self.search {
+            return Some(search.clone());
+        }
+        match &self.label {
+            Some(label) => Some(label.clone()),
+            None => safe_filename_for_matching(&self.path),
+        }
+    }
+}
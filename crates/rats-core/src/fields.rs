@@ -0,0 +1,141 @@
+//! Fields of structured lines, such as `path<TAB>description`, picked out
+//! by index expressions like `1`, `-1`, `2..` or `1,3..4`.
+
+/// How a line is split into fields.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Delimiter {
+    /// Runs of whitespace, ignoring any at either end, as awk splits
+    #[default]
+    Whitespace,
+    /// Every occurrence of this string
+    Text(String),
+}
+
+impl Delimiter {
+    /// `None` (or an empty string) splits on whitespace.
+    pub fn new(text: Option<&str>) -> Delimiter {
+        match text {
+            Some(text) if !text.is_empty() => Delimiter::Text(text.to_string()),
+            _ => Delimiter::Whitespace,
+        }
+    }
+
+    pub fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        match self {
+            Delimiter::Whitespace => line.split_whitespace().collect(),
+            Delimiter::Text(text) => line.split(text.as_str()).collect(),
+        }
+    }
+
+    /// What picked fields are joined with again.
+    fn joiner(&self) -> &str {
+        match self {
+            Delimiter::Whitespace => " ",
+            Delimiter::Text(text) => text,
+        }
+    }
+}
+
+/// One field index, 1 for the first field and -1 for the last.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Index(i64);
+
+impl Index {
+    /// Zero-based position among `count` fields, which may lie outside
+    /// them.
+    fn resolve(self, count: usize) -> i64 {
+        if self.0 > 0 { self.0 - 1 } else { count as i64 + self.0 }
+    }
+}
+
+/// A field or an inclusive range of them; an open end runs to the first or
+/// last field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Range {
+    start: Option<Index>,
+    end: Option<Index>,
+}
+
+/// Comma-separated field ranges, e.g. `1`, `-1`, `2..`, `..-2` or `1,3..4`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fields {
+    ranges: Vec<Range>,
+}
+
+impl Fields {
+    pub fn parse(spec: &str) -> Result<Fields, String> {
+        let index = |text: &str| match text.trim().parse::<i64>() {
+            Ok(0) | Err(_) => Err(format!("invalid field index: {:?} (fields count from 1, or -1 for the last)", text)),
+            Ok(index) => Ok(Index(index)),
+        };
+        let ranges = spec
+            .split(',')
+            .map(|range| match range.split_once("..") {
+                Some((start, end)) => Ok(Range {
+                    start: (!start.trim().is_empty()).then(|| index(start)).transpose()?,
+                    end: (!end.trim().is_empty()).then(|| index(end)).transpose()?,
+                }),
+                None => {
+                    let index = Some(index(range)?);
+                    Ok(Range { start: index, end: index })
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Fields { ranges })
+    }
+
+    /// The fields of `line` these ranges pick, in the order they are given,
+    /// joined by the delimiter (a space when splitting on whitespace).
+    pub fn pick(&self, line: &str, delimiter: &Delimiter) -> String {
+        let fields = delimiter.split(line);
+        let mut picked: Vec<&str> = Vec::new();
+        for range in &self.ranges {
+            let count = fields.len() as i64;
+            let start = range.start.map_or(0, |start| start.resolve(fields.len())).max(0);
+            let end = range.end.map_or(count, |end| end.resolve(fields.len()) + 1).min(count);
+            if start < end {
+                picked.extend(&fields[start as usize..end as usize]);
+            }
+        }
+        picked.join(delimiter.joiner())
+    }
+}
+
+/// `template` with its placeholders filled in from `line`, each quoted for
+/// `sh`: `{}` is the whole line, `{q}` the query, and a field expression
+/// such as `{1}` or `{2..}` the fields it picks. Braces holding anything
+/// else are left as they are, as are those for the line without one (as
+/// in `find -exec`, run to list the lines).
+pub fn fill(template: &str, line: Option<&str>, delimiter: &Delimiter, query: &str) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        filled.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let placeholder = after.find('}').and_then(|close| {
+            let text = match &after[..close] {
+                "q" => query.to_string(),
+                "" => line?.to_string(),
+                spec => Fields::parse(spec).ok()?.pick(line?, delimiter),
+            };
+            Some((text, close))
+        });
+        match placeholder {
+            Some((text, close)) => {
+                filled.push_str(&shell_quote(&text));
+                rest = &after[close + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// `text` as a single `sh` word, in single quotes.
+pub fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
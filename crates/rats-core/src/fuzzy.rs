@@ -0,0 +1,128 @@
+use std::{io, path::Path};
+
+use serde::Serialize;
+
+use crate::backend::Backend;
+
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Char indices into the (lowercased) text of the matched characters,
+    /// ascending
+    pub matched_indices: Vec<usize>,
+}
+
+/// One result of `search`, as `--json` prints it.
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub path: String,
+    pub score: i32,
+    /// What was matched: the file name, or with `recursive` the path
+    /// relative to the searched directory
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Match `pattern` against the entries of `dir`, or with `recursive` every
+/// file below it by relative path, as listed by `backend`. With
+/// `gitignore`, paths ignored by git are left out. Best match first, then
+/// by name.
+pub fn search(
+    backend: &dyn Backend,
+    dir: &Path,
+    pattern: &str,
+    recursive: bool,
+    gitignore: bool,
+) -> io::Result<Vec<SearchResult>> {
+    let paths = backend.list(dir, recursive, gitignore)?;
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| match recursive {
+            true => path.strip_prefix(dir).unwrap_or(path).to_string_lossy().into_owned(),
+            false => path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned(),
+        })
+        .collect();
+    Ok(rank(pattern, names.iter().map(String::as_str))
+        .into_iter()
+        .map(|(index, score)| SearchResult {
+            path: paths[index].to_string_lossy().into_owned(),
+            score,
+            name: names[index].clone(),
+            is_dir: backend.is_dir(&paths[index]),
+        })
+        .collect())
+}
+
+/// Match `pattern` against each of `names`, returning the index and score
+/// of every match: best first, then by name. Everything that ranks results
+/// (`search`, the picker, the C ABI) goes through here so they agree.
+pub fn rank<'a>(pattern: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<(usize, i32)> {
+    let mut matches: Vec<(usize, i32, &str)> = names
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, name)| Some((index, fuzzy_match(pattern, name)?.score, name)))
+        .collect();
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.2.cmp(b.2)));
+    matches.into_iter().map(|(index, score, _)| (index, score)).collect()
+}
+
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            matched_indices: Vec::new(),
+        });
+    }
+
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    let text_chars: Vec<char> = text.chars().collect();
+    
+    let mut score = 0i32;
+    let mut matched_indices = Vec::new();
+    let mut pattern_idx = 0;
+    let mut last_match_idx = None;
+    
+    for (text_idx, &text_char) in text_chars.iter().enumerate() {
+        if pattern_idx < pattern_chars.len() && text_char == pattern_chars[pattern_idx] {
+            matched_indices.push(text_idx);
+            
+            // Base score for each match
+            score += 10;
+            
+            // Bonus for consecutive matches
+            if let Some(last_idx) = last_match_idx
+                && text_idx == last_idx + 1
+            {
+                score += 5;
+            }
+            
+            // Bonus for matches at the beginning
+            if text_idx == 0 {
+                score += 15;
+            }
+            
+            // Bonus for matches after separators
+            if text_idx > 0 && (text_chars[text_idx - 1] == '/' || text_chars[text_idx - 1] == '_' || text_chars[text_idx - 1] == '-' || text_chars[text_idx - 1] == '.') {
+                score += 10;
+            }
+            
+            last_match_idx = Some(text_idx);
+            pattern_idx += 1;
+        }
+    }
+    
+    // All pattern characters must be matched
+    if pattern_idx == pattern_chars.len() {
+        // Penalty for longer text (prefer shorter matches)
+        score -= text_chars.len() as i32;
+        
+        Some(FuzzyMatch {
+            score,
+            matched_indices,
+        })
+    } else {
+        None
+    }
+}
\ No newline at end of file
@@ -0,0 +1,14 @@
+//! The parts of rats that don't need a terminal: walking directories,
+//! fuzzy matching names, parsing queries and the entries they produce.
+//! Without the default `fs` feature nothing touches the file system, so the
+//! crate builds for wasm32 with the host handing over paths as `Items`.
+
+pub mod backend;
+pub mod entry;
+pub mod fields;
+pub mod fuzzy;
+pub mod names;
+pub mod pool;
+pub mod query;
+#[cfg(feature = "fs")]
+pub mod walk;
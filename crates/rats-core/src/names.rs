@@ -0,0 +1,59 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+/// The file name of `path` for display, `..` for a parent entry.
+pub fn safe_filename_to_string(path: &Path) -> String {
+    if let Some(name) = path.file_name() {
+        if let Some(name_str) = name.to_str() {
+            // Valid UTF-8
+            name_str.to_string()
+        } else {
+            // Invalid UTF-8 - use lossy conversion
+            name.to_string_lossy().to_string()
+        }
+    } else {
+        // Special case: if the path ends with "..", return ".."
+        if path.to_string_lossy().ends_with("..") {
+            "..".to_string()
+        } else {
+            // No filename (shouldn't happen for our use case)
+            "Unknown".to_string()
+        }
+    }
+}
+
+/// The raw bytes of `path`. Only Unix paths can hold arbitrary bytes;
+/// elsewhere this is the lossy UTF-8 form.
+pub fn path_to_bytes(path: &Path) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Cow::Borrowed(path.as_os_str().as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        match path.to_string_lossy() {
+            Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+        }
+    }
+}
+
+/// Inverse of `path_to_bytes`.
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+pub fn safe_filename_for_matching(path: &Path) -> Option<String> {
+    path.file_name().map(|name| name.to_string_lossy().to_string())
+}
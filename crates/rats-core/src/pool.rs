@@ -0,0 +1,184 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{self, AtomicU64, AtomicUsize},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+/// Upper bound on worker threads, whatever the core count.
+const MAX_WORKERS: usize = 4;
+
+/// Order in which queued tasks are picked up. Work the user is looking at
+/// (the current preview) goes before speculative or bulk work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    High,
+}
+
+/// Hands out [`CancelToken`]s and cancels all of them at once, e.g. when
+/// the selection moves on and work for the old preview is no longer needed.
+#[derive(Debug, Clone, Default)]
+pub struct CancelSource {
+    generation: Arc<AtomicU64>,
+}
+
+impl CancelSource {
+    /// A token that stays valid until the next `renew`.
+    pub fn current(&self) -> CancelToken {
+        CancelToken {
+            generation: self.generation.clone(),
+            expected: self.generation.load(atomic::Ordering::Relaxed),
+        }
+    }
+
+    /// Cancel every token issued so far and return a fresh one.
+    pub fn renew(&self) -> CancelToken {
+        let generation = self.generation.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+        CancelToken {
+            generation: self.generation.clone(),
+            expected: generation,
+        }
+    }
+}
+
+/// Checked by tasks (and by the pool before starting them) to stop work
+/// whose result would be thrown away.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    generation: Arc<AtomicU64>,
+    expected: u64,
+}
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.generation.load(atomic::Ordering::Relaxed) != self.expected
+    }
+}
+
+type Job = Box<dyn FnOnce(&CancelToken) + Send>;
+
+struct Task {
+    priority: Priority,
+    /// Submission order, so tasks of equal priority run first-in first-out
+    sequence: u64,
+    token: CancelToken,
+    job: Job,
+}
+
+impl PartialEq for Task {
+    fn eq(&self, other: &Task) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Task {}
+
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Task) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Task {
+    fn cmp(&self, other: &Task) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: BinaryHeap<Task>,
+    next_sequence: u64,
+}
+
+/// A fixed set of threads running all background work, so the thread
+/// count stays bounded however many tasks are queued.
+pub struct WorkerPool {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    /// Tasks queued or running
+    pending: Arc<AtomicUsize>,
+}
+
+impl Default for WorkerPool {
+    fn default() -> WorkerPool {
+        WorkerPool::new()
+    }
+}
+
+impl WorkerPool {
+    pub fn new() -> WorkerPool {
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_WORKERS);
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        let pending = Arc::new(AtomicUsize::new(0));
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let pending = pending.clone();
+            thread::spawn(move || worker(&queue, &pending));
+        }
+        WorkerPool { queue, pending }
+    }
+
+    /// How many tasks are queued or running, cancelled ones included until
+    /// a worker drops them.
+    pub fn pending(&self) -> usize {
+        self.pending.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Queue `job`. It is dropped without running if `token` is cancelled
+    /// before a worker gets to it; long jobs should also check the token
+    /// themselves.
+    pub fn spawn(
+        &self,
+        priority: Priority,
+        token: CancelToken,
+        job: impl FnOnce(&CancelToken) + Send + 'static,
+    ) {
+        let (lock, available) = &*self.queue;
+        let mut queue = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.pending.fetch_add(1, atomic::Ordering::Relaxed);
+        let sequence = queue.next_sequence;
+        queue.next_sequence += 1;
+        queue.tasks.push(Task {
+            priority,
+            sequence,
+            token,
+            job: Box::new(job),
+        });
+        available.notify_one();
+    }
+}
+
+fn worker(queue: &(Mutex<Queue>, Condvar), pending: &AtomicUsize) {
+    let (lock, available) = queue;
+    loop {
+        let task = {
+            let mut queue = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            loop {
+                match queue.tasks.pop() {
+                    Some(task) => break task,
+                    None => {
+                        queue = available
+                            .wait(queue)
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                }
+            }
+        };
+        if !task.token.is_cancelled() {
+            // A decoder choking on a malformed file must not take the worker,
+            // or the count of pending tasks, down with it
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| (task.job)(&task.token)));
+        }
+        pending.fetch_sub(1, atomic::Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,123 @@
+/// A parsed filter string. Besides the fuzzy pattern itself, the filter may
+/// carry a `:123` line suffix (as in compiler error locations) which is
+/// applied to the accepted file rather than matched against names,
+/// `tag:name` words restricting the list to entries with those tags, and
+/// `taken:` words restricting it by photo capture date.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub pattern: String,
+    pub line: Option<usize>,
+    pub tags: Vec<String>,
+    pub taken: Vec<DateFilter>,
+}
+
+/// How a date is compared in a `taken:` predicate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Before,
+    AtOrBefore,
+    Equal,
+    AtOrAfter,
+    After,
+}
+
+/// A `taken:` predicate such as `taken:>2023-01` or `taken:2024-06-01`.
+/// Dates compare as prefixes, so `taken:2023` is the whole year and
+/// `taken:>2023-01` starts in February.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateFilter {
+    pub comparison: Comparison,
+    pub date: String,
+}
+
+impl DateFilter {
+    fn parse(value: &str) -> Option<DateFilter> {
+        let (comparison, date) = [
+            (">=", Comparison::AtOrAfter),
+            ("<=", Comparison::AtOrBefore),
+            (">", Comparison::After),
+            ("<", Comparison::Before),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(op, comparison)| Some((comparison, value.strip_prefix(op)?)))
+        .unwrap_or((Comparison::Equal, value));
+        (!date.is_empty()).then(|| DateFilter {
+            comparison,
+            date: date.to_string(),
+        })
+    }
+
+    /// Whether a `YYYY-MM-DD HH:MM:SS` date satisfies the predicate.
+    pub fn matches(&self, date: &str) -> bool {
+        let prefix = date.get(..self.date.len()).unwrap_or(date);
+        match self.comparison {
+            Comparison::Before => prefix < self.date.as_str(),
+            Comparison::AtOrBefore => prefix <= self.date.as_str(),
+            Comparison::Equal => prefix == self.date,
+            Comparison::AtOrAfter => prefix >= self.date.as_str(),
+            Comparison::After => prefix > self.date.as_str(),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Query {
+        let is_predicate = |word: &&str| word.starts_with("tag:") || word.starts_with("taken:");
+        let tags: Vec<String> = input
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix("tag:"))
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        let taken: Vec<DateFilter> = input
+            .split_whitespace()
+            .filter_map(|word| DateFilter::parse(word.strip_prefix("taken:")?))
+            .collect();
+        // Only rebuild the pattern when there was a predicate, so spaces in
+        // plain patterns are kept as typed
+        let without_predicates;
+        let input = if input.split_whitespace().any(|word| is_predicate(&word)) {
+            without_predicates = input
+                .split_whitespace()
+                .filter(|word| !is_predicate(word))
+                .collect::<Vec<_>>()
+                .join(" ");
+            without_predicates.as_str()
+        } else {
+            input
+        };
+
+        let (rest, last) = split_number_suffix(input);
+        let Some(last) = last else {
+            return Query {
+                pattern: input.to_string(),
+                line: None,
+                tags,
+                taken,
+            };
+        };
+
+        // `path:12:5` - the final number is a column, which is ignored
+        let (pattern, line) = match split_number_suffix(rest) {
+            (path, Some(line)) => (path, line),
+            _ => (rest, last),
+        };
+        Query {
+            pattern: pattern.to_string(),
+            line: Some(line),
+            tags,
+            taken,
+        }
+    }
+}
+
+fn split_number_suffix(input: &str) -> (&str, Option<usize>) {
+    match input.rsplit_once(':') {
+        Some((head, tail)) if !head.is_empty() => match tail.parse() {
+            Ok(n) => (head, Some(n)),
+            Err(_) => (input, None),
+        },
+        _ => (input, None),
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    fs::{self, FileType},
+    io,
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+
+use crate::pool::CancelToken;
+
+/// Directories that are never worth descending into when scanning a project.
+pub const SKIPPED_DIRS: [&str; 3] = ["target", "node_modules", "__pycache__"];
+
+/// Recursively collect the files under `root`, skipping hidden entries and
+/// common build/dependency directories. Unreadable directories are ignored.
+pub fn walk_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = files(root.to_path_buf(), false).collect();
+    files.sort();
+    files
+}
+
+/// The files `walk_files` finds, in no particular order, read one
+/// directory at a time as the iterator is advanced. With `gitignore`,
+/// paths ignored by git (`.gitignore`, `.git/info/exclude`, the global
+/// excludes file) or by `.ignore` files are skipped too.
+pub fn files(root: PathBuf, gitignore: bool) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(root)
+        .standard_filters(gitignore)
+        .hidden(true)
+        .filter_entry(|entry| {
+            !(entry.file_type().is_some_and(|file_type| file_type.is_dir())
+                && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        })
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+        .map(|entry| entry.into_path())
+}
+
+/// The entries of `dir` that ignore files (see `files`) don't exclude,
+/// dot-files included.
+pub fn unignored_entries(dir: &Path) -> impl Iterator<Item = io::Result<(PathBuf, Option<FileType>)>> + use<> {
+    WalkBuilder::new(dir)
+        .standard_filters(true)
+        .hidden(false)
+        .max_depth(Some(1))
+        .build()
+        .filter(|entry| entry.as_ref().map_or(true, |entry| entry.depth() > 0))
+        .map(|entry| {
+            let entry = entry.map_err(io::Error::other)?;
+            let file_type = entry.file_type();
+            Ok((entry.into_path(), file_type))
+        })
+}
+
+/// Total size in bytes of `path` and everything below it, hidden entries
+/// included, like `du --apparent-size`. Symlinks count as themselves and
+/// unreadable entries as nothing. `None` once `token` is cancelled.
+pub fn total_size(path: &Path, token: &CancelToken) -> Option<u64> {
+    let mut total = 0;
+    let mut stack = vec![path.to_path_buf()];
+
+    while let Some(path) = stack.pop() {
+        if token.is_cancelled() {
+            return None;
+        }
+        let Ok(meta) = path.symlink_metadata() else {
+            continue;
+        };
+        total += meta.len();
+        if meta.is_dir()
+            && let Ok(entries) = fs::read_dir(&path)
+        {
+            stack.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+
+    Some(total)
+}
@@ -0,0 +1,47 @@
+use rats_core::fields::{self, Delimiter, Fields};
+
+fn pick(spec: &str, line: &str, delimiter: &Delimiter) -> String {
+    Fields::parse(spec).unwrap().pick(line, delimiter)
+}
+
+#[test]
+fn picks_fields_and_ranges() {
+    let tab = Delimiter::new(Some("\t"));
+    let line = "src/main.rs\t42\tthe entry point";
+    assert_eq!(pick("1", line, &tab), "src/main.rs");
+    assert_eq!(pick("-1", line, &tab), "the entry point");
+    assert_eq!(pick("2..", line, &tab), "42\tthe entry point");
+    assert_eq!(pick("..-2", line, &tab), "src/main.rs\t42");
+    assert_eq!(pick("3,1", line, &tab), "the entry point\tsrc/main.rs");
+}
+
+#[test]
+fn whitespace_splits_like_awk() {
+    let line = "  a   b c ";
+    assert_eq!(pick("2..", line, &Delimiter::Whitespace), "b c");
+    assert_eq!(pick("-1", line, &Delimiter::new(None)), "c");
+}
+
+#[test]
+fn missing_fields_pick_nothing() {
+    assert_eq!(pick("5", "a b", &Delimiter::Whitespace), "");
+    assert_eq!(pick("-5", "a b", &Delimiter::Whitespace), "");
+    assert_eq!(pick("-5..1", "a b", &Delimiter::Whitespace), "a");
+}
+
+#[test]
+fn rejects_bad_indexes() {
+    assert!(Fields::parse("0").is_err());
+    assert!(Fields::parse("a..2").is_err());
+    assert!(Fields::parse("").is_err());
+}
+
+#[test]
+fn fills_preview_templates() {
+    let tab = Delimiter::new(Some("\t"));
+    let line = "src/main.rs\t42\tit's here";
+    assert_eq!(fields::fill("bat -H {2} {1}", Some(line), &tab, ""), "bat -H '42' 'src/main.rs'");
+    assert_eq!(fields::fill("echo {-1} {q}", Some(line), &tab, "ma"), "echo 'it'\\''s here' 'ma'");
+    assert_eq!(fields::fill("awk '{print $1}' {}", Some("a b"), &Delimiter::Whitespace, ""), "awk '{print $1}' 'a b'");
+    assert_eq!(fields::fill("find . -exec grep -l {q} {} +", None, &tab, "x y"), "find . -exec grep -l 'x y' {} +");
+}
@@ -0,0 +1,49 @@
+//! Golden ranking of a fixed corpus of paths, so a change to the matcher
+//! that reorders results shows up as a diff of `corpus/ranking.txt`. After
+//! an intended change, regenerate it with
+//! `UPDATE_GOLDEN=1 cargo test -p rats-core --test fuzzy_corpus`.
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+use rats_core::fuzzy::rank;
+
+const QUERIES: &[&str] = &[
+    "main", "mn", "src", "config", "cfg", "ui", "rs", "toml", "readme", "lua", "test", "fm", "ap", "win", "keys",
+    "s/m", "wf", "x",
+];
+
+/// How many of the best matches are recorded per query.
+const TOP: usize = 8;
+
+fn corpus_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+fn ranking(paths: &[&str]) -> String {
+    let mut out = String::new();
+    for query in QUERIES {
+        let ranked = rank(query, paths.iter().copied());
+        writeln!(out, "> {} ({} matches)", query, ranked.len()).unwrap();
+        for (index, score) in ranked.into_iter().take(TOP) {
+            writeln!(out, "{:>5}  {}", score, paths[index]).unwrap();
+        }
+    }
+    out
+}
+
+#[test]
+fn ranking_matches_golden_file() {
+    let paths = fs::read_to_string(corpus_dir().join("paths.txt")).unwrap();
+    let paths: Vec<&str> = paths.lines().filter(|line| !line.is_empty()).collect();
+    let actual = ranking(&paths);
+    let golden = corpus_dir().join("ranking.txt");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&golden).unwrap();
+    for (line, (expected, actual)) in expected.lines().zip(actual.lines()).enumerate() {
+        assert_eq!(expected, actual, "ranking.txt line {} differs", line + 1);
+    }
+    assert_eq!(expected.lines().count(), actual.lines().count(), "ranking.txt length differs");
+}
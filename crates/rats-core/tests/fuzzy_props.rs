@@ -0,0 +1,76 @@
+//! Invariants of `fuzzy_match` that any change to the scoring must keep.
+
+use proptest::prelude::*;
+use rats_core::fuzzy::{fuzzy_match, rank};
+
+/// File-name-like text, where lowercasing maps one char to one char so a
+/// prefix of a pattern stays a prefix once lowercased
+const NAME: &str = "[a-zA-Z0-9_./ -]{0,30}";
+const PATTERN: &str = "[a-zA-Z0-9_./-]{0,8}";
+
+proptest! {
+    #[test]
+    fn never_panics(pattern in any::<String>(), text in any::<String>()) {
+        let _ = fuzzy_match(&pattern, &text);
+    }
+
+    /// A match means the (lowercased) pattern is a subsequence of the
+    /// (lowercased) text, at the indices reported.
+    #[test]
+    fn match_is_subsequence(pattern in any::<String>(), text in any::<String>()) {
+        if let Some(found) = fuzzy_match(&pattern, &text) {
+            let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+            let text: Vec<char> = text.to_lowercase().chars().collect();
+            prop_assert_eq!(found.matched_indices.len(), pattern.len());
+            prop_assert!(found.matched_indices.windows(2).all(|pair| pair[0] < pair[1]));
+            for (&index, &expected) in found.matched_indices.iter().zip(&pattern) {
+                prop_assert_eq!(text[index], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn subsequence_matches(text in NAME, picks in proptest::collection::vec(any::<bool>(), 30)) {
+        let pattern: String = text.chars().zip(&picks).filter(|&(_, &pick)| pick).map(|(c, _)| c).collect();
+        prop_assert!(fuzzy_match(&pattern, &text).is_some());
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything(text in any::<String>()) {
+        prop_assert_eq!(fuzzy_match("", &text).map(|found| found.score), Some(0));
+    }
+
+    #[test]
+    fn case_is_ignored(pattern in PATTERN, text in NAME) {
+        let lower = fuzzy_match(&pattern.to_lowercase(), &text).map(|found| found.score);
+        prop_assert_eq!(fuzzy_match(&pattern.to_uppercase(), &text).map(|found| found.score), lower);
+    }
+
+    /// Typing one more character can only narrow the matches, and raises
+    /// the score of those still matching.
+    #[test]
+    fn growing_the_pattern_narrows_and_scores_higher(pattern in PATTERN, next in "[a-zA-Z0-9_./-]", text in NAME) {
+        let longer = format!("{}{}", pattern, next);
+        if let Some(longer) = fuzzy_match(&longer, &text) {
+            let shorter = fuzzy_match(&pattern, &text);
+            prop_assert!(shorter.is_some());
+            let shorter = shorter.unwrap();
+            // The empty pattern scores 0 without the length penalty
+            if !pattern.is_empty() {
+                prop_assert!(longer.score > shorter.score);
+            }
+        }
+    }
+
+    /// `rank` keeps exactly the matches, best first and then by name.
+    #[test]
+    fn rank_orders_matches(pattern in PATTERN, names in proptest::collection::vec(NAME, 0..20)) {
+        let ranked = rank(&pattern, names.iter().map(String::as_str));
+        let matching = names.iter().filter(|name| fuzzy_match(&pattern, name).is_some()).count();
+        prop_assert_eq!(ranked.len(), matching);
+        for pair in ranked.windows(2) {
+            let ((a, a_score), (b, b_score)) = (pair[0], pair[1]);
+            prop_assert!(a_score > b_score || (a_score == b_score && names[a] <= names[b]));
+        }
+    }
+}
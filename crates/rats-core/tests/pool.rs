@@ -0,0 +1,28 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rats_core::pool::{CancelSource, Priority, WorkerPool};
+
+#[test]
+fn survives_panicking_jobs() {
+    let pool = WorkerPool::new();
+    let cancel = CancelSource::default();
+    // More panics than there can be workers
+    for _ in 0..16 {
+        pool.spawn(Priority::High, cancel.current(), |_| panic!("bad file"));
+    }
+    let (sender, receiver) = mpsc::channel();
+    pool.spawn(Priority::Low, cancel.current(), move |_| {
+        let _ = sender.send(());
+    });
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.pending() > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(pool.pending(), 0);
+}
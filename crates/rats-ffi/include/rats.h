@@ -0,0 +1,33 @@
+/* C ABI for the rats fuzzy matcher. Link against librats_ffi (built by
+ * `cargo build -p rats-ffi --release`). Strings are NUL-terminated UTF-8. */
+#ifndef RATS_H
+#define RATS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RATS_ABI_VERSION 1
+
+/* RATS_ABI_VERSION of the loaded library. */
+uint32_t rats_abi_version(void);
+
+/* 1 and the score in *score (if score is not NULL) on a match, 0 when
+ * there is none, -1 when pattern or text is NULL. */
+int32_t rats_fuzzy_score(const char *pattern, const char *text, int32_t *score);
+
+/* Rank the len strings in names against pattern: best score first, then
+ * by name. Writes the index of each match to indices and its score to
+ * scores (if not NULL); both need room for len values. Returns the number
+ * of matches. */
+size_t rats_rank(const char *pattern, const char *const *names, size_t len,
+                 size_t *indices, int32_t *scores);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
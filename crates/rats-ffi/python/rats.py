@@ -0,0 +1,68 @@
+"""ctypes bindings for librats_ffi, ranking exactly like rats itself.
+
+    import rats
+    rats.rank("mn", ["main.rs", "Cargo.toml", "mod.rs"])  # [(0, score), ...]
+
+The library is looked up next to this file, then in RATS_FFI_LIB, then on
+the system library path.
+"""
+
+import ctypes
+import ctypes.util
+import os
+import sys
+
+ABI_VERSION = 1
+
+
+def _load():
+    name = {"darwin": "librats_ffi.dylib", "win32": "rats_ffi.dll"}.get(sys.platform, "librats_ffi.so")
+    candidates = [
+        os.path.join(os.path.dirname(os.path.abspath(__file__)), name),
+        os.environ.get("RATS_FFI_LIB"),
+        ctypes.util.find_library("rats_ffi"),
+    ]
+    for path in filter(None, candidates):
+        if os.path.exists(path) or not os.path.isabs(path):
+            try:
+                return ctypes.CDLL(path)
+            except OSError:
+                continue
+    raise OSError("librats_ffi not found; build it with `cargo build -p rats-ffi --release`")
+
+
+_lib = _load()
+_lib.rats_abi_version.restype = ctypes.c_uint32
+_lib.rats_fuzzy_score.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.POINTER(ctypes.c_int32)]
+_lib.rats_fuzzy_score.restype = ctypes.c_int32
+_lib.rats_rank.argtypes = [
+    ctypes.c_char_p,
+    ctypes.POINTER(ctypes.c_char_p),
+    ctypes.c_size_t,
+    ctypes.POINTER(ctypes.c_size_t),
+    ctypes.POINTER(ctypes.c_int32),
+]
+_lib.rats_rank.restype = ctypes.c_size_t
+
+if _lib.rats_abi_version() != ABI_VERSION:
+    raise ImportError("librats_ffi has ABI version %d, expected %d" % (_lib.rats_abi_version(), ABI_VERSION))
+
+
+def score(pattern, text):
+    """The score of text against pattern, or None when it does not match."""
+    result = ctypes.c_int32()
+    if _lib.rats_fuzzy_score(pattern.encode(), text.encode(), ctypes.byref(result)) == 1:
+        return result.value
+    return None
+
+
+def rank(pattern, names):
+    """(index, score) of every name matching pattern, best first, then by name."""
+    names = list(names)
+    if not names:
+        return []
+    array = (ctypes.c_char_p * len(names))(*(name.encode() for name in names))
+    indices = (ctypes.c_size_t * len(names))()
+    scores = (ctypes.c_int32 * len(names))()
+    count = _lib.rats_rank(pattern.encode(), array, len(names), indices, scores)
+    return [(indices[i], scores[i]) for i in range(count)]
@@ -0,0 +1,97 @@
+//! The fuzzy matcher and ranking of rats behind a small C ABI, so plugins
+//! in other languages rank exactly like the interactive browser. The
+//! declarations are in `include/rats.h`; `python/rats.py` wraps them.
+//!
+//! Strings are NUL-terminated UTF-8; invalid bytes are replaced the same
+//! way rats replaces them in file names before matching.
+
+use std::{
+    borrow::Cow,
+    ffi::{CStr, c_char},
+    slice,
+};
+
+use rats_core::fuzzy;
+
+/// Bumped whenever a signature below changes.
+pub const RATS_ABI_VERSION: u32 = 1;
+
+unsafe fn string<'a>(text: *const c_char) -> Option<Cow<'a, str>> {
+    match text.is_null() {
+        true => None,
+        false => Some(unsafe { CStr::from_ptr(text) }.to_string_lossy()),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn rats_abi_version() -> u32 {
+    RATS_ABI_VERSION
+}
+
+/// Match `pattern` against `text`. Returns 1 and stores the score in
+/// `score` (when not null) on a match, 0 when there is none and -1 when
+/// `pattern` or `text` is null.
+///
+/// # Safety
+///
+/// `pattern` and `text` must be null or point to NUL-terminated strings,
+/// and `score` must be null or valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rats_fuzzy_score(pattern: *const c_char, text: *const c_char, score: *mut i32) -> i32 {
+    let (Some(pattern), Some(text)) = (unsafe { string(pattern) }, unsafe { string(text) }) else {
+        return -1;
+    };
+    match fuzzy::fuzzy_match(&pattern, &text) {
+        Some(found) => {
+            if !score.is_null() {
+                unsafe { *score = found.score };
+            }
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Rank the `len` strings in `names` against `pattern` the way rats orders
+/// its results: best score first, then by name. Writes the index into
+/// `names` of each match to `indices` and, when not null, its score to
+/// `scores`, and returns how many matched. Null names never match; a null
+/// `pattern` or `names` matches nothing.
+///
+/// # Safety
+///
+/// `pattern` must be null or a NUL-terminated string. `names` must be null
+/// or point to `len` pointers, each null or a NUL-terminated string.
+/// `indices`, and `scores` unless null, must have room for `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rats_rank(
+    pattern: *const c_char,
+    names: *const *const c_char,
+    len: usize,
+    indices: *mut usize,
+    scores: *mut i32,
+) -> usize {
+    let Some(pattern) = (unsafe { string(pattern) }) else {
+        return 0;
+    };
+    if names.is_null() || indices.is_null() || len == 0 {
+        return 0;
+    }
+    let names: Vec<Option<Cow<str>>> = unsafe { slice::from_raw_parts(names, len) }
+        .iter()
+        .map(|&name| unsafe { string(name) })
+        .collect();
+    // Null names stand in as "" only to keep the indices; they are dropped below
+    let ranked = fuzzy::rank(&pattern, names.iter().map(|name| name.as_deref().unwrap_or("")));
+    let ranked = ranked.into_iter().filter(|&(index, _)| names[index].is_some());
+    let indices = unsafe { slice::from_raw_parts_mut(indices, len) };
+    let mut count = 0;
+    for (index, score) in ranked {
+        indices[count] = index;
+        if !scores.is_null() {
+            unsafe { *scores.add(count) = score };
+        }
+        count += 1;
+    }
+    count
+}
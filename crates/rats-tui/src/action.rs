@@ -0,0 +1,315 @@
+use std::io;
+
+use crate::{
+    app::{App, Selection},
+    config::Source,
+    crash,
+    mode::Mode,
+};
+
+/// Something the user asked for, whatever asked for it: a key, the mouse,
+/// or anything else driving the app. `App::dispatch` carries it out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    SetMode(Mode),
+    /// Move the selection down one entry
+    Next,
+    /// Move the selection up one entry
+    Previous,
+    Top,
+    Bottom,
+    /// Select the entry at this index of the filtered list
+    Select(usize),
+    /// Move the selection, or the viewport, by a number of entries
+    ScrollList(isize),
+    /// Move through the gallery by a number of cells
+    MoveGallery(isize),
+    /// Scroll the preview by a number of lines
+    ScrollPreview(isize),
+    TogglePreview,
+    /// Show or hide dot-files
+    ToggleHidden,
+    /// Show or hide paths ignored by git
+    ToggleIgnored,
+    TogglePreviewFocus,
+    /// Move the preview cursor by a number of visible lines
+    MovePreviewCursor(isize),
+    ToggleFold,
+    /// Decrypt the selected gpg or age file into the preview
+    Decrypt,
+    /// Read the previewed file again
+    ReloadPreview,
+    ToggleAllFolds,
+    /// Open the `path:line` under the preview cursor
+    FollowPath,
+    /// Open the selected directory, or accept the selected file
+    Accept,
+    /// Answer a chooser request with the marked entries or current directory
+    Choose,
+    ToggleMark,
+    TogglePin,
+    Dismiss,
+    UndismissAll,
+    /// Put the marked or selected entries aside for `Paste`
+    Yank,
+    /// Like `Yank`, but `Paste` moves them
+    Cut,
+    /// Copy or move the yanked or cut entries into the current directory
+    Paste,
+    Annotate,
+    ExportTree,
+    ToggleHistory,
+    /// Bookmark the browsed directory under a letter
+    SetBookmark(char),
+    /// Browse the directory bookmarked under a letter
+    JumpToBookmark(char),
+    /// Swap the list for the bookmarks, or back
+    ToggleBookmarks,
+    /// Swap the list for the frecent directories, or back
+    ToggleJump,
+    /// Go back to the directory browsed before
+    Back,
+    /// Go forward again after `Back`
+    Forward,
+    /// Switch between browsing names and searching file contents
+    ToggleGrep,
+    /// Move the marked or selected entries to the system trash
+    MoveToTrash,
+    /// Review permanently deleting the marked or selected entries
+    Delete,
+    /// Edit the name of the selected entry
+    Rename,
+    /// Type the path of a new file (false) or directory (true) to create
+    Create(bool),
+    /// Browse the directory holding the selected search result
+    OpenContainingDir,
+    /// Collapse or expand the group of the selected recursive result
+    ToggleGroup,
+    ToggleAllGroups,
+    Restore,
+    Purge,
+    /// Mount (true) or unmount (false) the selected entry
+    Mount(bool),
+    CancelLoading,
+    ClearFilter,
+    /// Add a character to the input of the current mode
+    Type(char),
+    /// Remove the last character from the input of the current mode
+    Backspace,
+    /// Enter in a text-entry mode
+    Submit,
+    /// Run a command as if typed after `:`
+    RunCommand(String),
+    /// Run the listing command again, or the given one in its place
+    Reload(Option<String>),
+    /// Run a shell command in the background, its placeholders filled in
+    /// from the selected entry
+    Execute(String),
+    /// Esc in a text-entry mode, dropping what was typed
+    CancelInput,
+    ApplyPlan,
+    ExportPlan,
+    CancelPlan,
+    ScrollPlan(isize),
+}
+
+/// Whether the event loop carries on after an action.
+pub enum Flow {
+    Continue,
+    Exit(Option<Selection>),
+}
+
+impl App {
+    /// Carry out `action`. Errors are only returned for failures to answer
+    /// a chooser request; everything else reports through the status line.
+    pub fn dispatch(&mut self, action: Action) -> io::Result<Flow> {
+        crash::record(&action);
+        match action {
+            Action::Quit => return Ok(Flow::Exit(None)),
+            Action::SetMode(mode) => self.set_mode(mode),
+            Action::Next => self.next(),
+            Action::Previous => self.previous(),
+            Action::Top => self.go_to_top(),
+            Action::Bottom => self.go_to_bottom(),
+            Action::Select(index) => {
+                if index < self.filtered_items.len() {
+                    self.list_state.select(Some(index));
+                    self.load_preview();
+                }
+            }
+            Action::ScrollList(delta) => self.scroll_list(delta),
+            Action::MoveGallery(delta) => self.move_gallery(delta),
+            Action::ScrollPreview(delta) => self.scroll_preview(delta),
+            Action::TogglePreview => self.toggle_preview(),
+            Action::ToggleHidden => self.toggle_hidden(),
+            Action::ToggleIgnored => self.toggle_ignored(),
+            Action::TogglePreviewFocus => self.toggle_preview_focus(),
+            Action::MovePreviewCursor(delta) => self.move_preview_cursor(delta),
+            Action::ToggleFold => self.toggle_fold(),
+            Action::Decrypt => self.decrypt_selected(),
+            Action::ReloadPreview => self.reload_preview(),
+            Action::ToggleAllFolds => self.toggle_all_folds(),
+            Action::FollowPath => {
+                if let Err(err) = self.follow_path_under_cursor() {
+                    self.status_message = Some(format!("Cannot open: {}", err));
+                }
+            }
+            // Accepting a clip puts it back on the clipboard instead of
+            // printing anything
+            Action::Accept if self.config.source == Source::Clipboard => {
+                if self.restore_clip() {
+                    return Ok(Flow::Exit(None));
+                }
+            }
+            Action::Accept => {
+                if let Ok(Some(selection)) = self.enter_selected() {
+                    return Ok(Flow::Exit(Some(selection)));
+                }
+            }
+            Action::Choose => {
+                if self.answer_chooser(None)? {
+                    return Ok(Flow::Exit(None));
+                }
+            }
+            Action::ToggleMark => self.toggle_mark_selected(),
+            Action::TogglePin => self.toggle_pin_selected(),
+            Action::Dismiss => self.dismiss_selected(),
+            Action::UndismissAll => self.undismiss_all(),
+            Action::Yank => self.yank(false),
+            Action::Cut => self.yank(true),
+            Action::Paste => self.paste(),
+            Action::Annotate => self.start_annotating(),
+            Action::ExportTree => self.export_tree(),
+            Action::ToggleHistory => self.toggle_history(),
+            Action::SetBookmark(letter) => self.set_bookmark(letter),
+            Action::JumpToBookmark(letter) => self.jump_to_bookmark(letter),
+            Action::ToggleBookmarks => self.toggle_overlay(Source::Bookmarks),
+            Action::ToggleJump => self.toggle_overlay(Source::Jump),
+            Action::Back => self.step_dir_history(false),
+            Action::Forward => self.step_dir_history(true),
+            Action::ToggleGrep => self.toggle_grep(),
+            Action::OpenContainingDir => self.open_containing_dir(),
+            Action::MoveToTrash => self.trash_selected(),
+            Action::Delete => self.plan_delete(),
+            Action::Rename => self.start_renaming(),
+            Action::Create(dir) => self.start_creating(dir),
+            Action::ToggleGroup => self.toggle_group(),
+            Action::ToggleAllGroups => self.toggle_all_groups(),
+            Action::Restore => self.restore_selected(),
+            Action::Purge => self.purge_selected(),
+            Action::Mount(mount) => self.toggle_mount_selected(mount),
+            Action::CancelLoading => self.cancel_loading(),
+            Action::ClearFilter => self.clear_filter(),
+            Action::Type(c) => self.type_char(c),
+            Action::Backspace => self.backspace(),
+            Action::Submit => return self.submit(),
+            Action::Reload(command) => self.reload_command(command),
+            Action::Execute(command) => self.execute_silent(&command),
+            Action::RunCommand(command) => {
+                self.command_input = command;
+                self.run_command();
+            }
+            Action::CancelInput => self.cancel_input(),
+            Action::ApplyPlan => self.apply_plan(),
+            Action::ExportPlan => self.export_plan(),
+            Action::CancelPlan => self.cancel_plan(),
+            Action::ScrollPlan(delta) => self.scroll_plan(delta),
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn type_char(&mut self, c: char) {
+        match self.mode {
+            Mode::Insert => self.add_char_to_filter(c),
+            Mode::Annotate => self.note_input.push(c),
+            Mode::Command => self.command_input.push(c),
+            Mode::Confirm => self.confirm_input.push(c),
+            Mode::SaveAs if c != '/' => self.save_name.push(c),
+            Mode::Rename if c != '/' => self.rename_input.push(c),
+            Mode::Create => self.create_input.push(c),
+            // Tags are single words so they can be used in `tag:` queries
+            Mode::Tag if !c.is_whitespace() => self.tag_input.push(c),
+            _ => {}
+        }
+    }
+
+    fn backspace(&mut self) {
+        match self.mode {
+            Mode::Insert => self.remove_char_from_filter(),
+            Mode::Annotate => {
+                self.note_input.pop();
+            }
+            Mode::Command => {
+                self.command_input.pop();
+            }
+            Mode::Confirm => {
+                self.confirm_input.pop();
+            }
+            Mode::SaveAs => {
+                self.save_name.pop();
+            }
+            Mode::Rename => {
+                self.rename_input.pop();
+            }
+            Mode::Create => {
+                self.create_input.pop();
+            }
+            Mode::Tag => {
+                self.tag_input.pop();
+            }
+            Mode::Normal | Mode::Plan => {}
+        }
+    }
+
+    fn submit(&mut self) -> io::Result<Flow> {
+        match self.mode {
+            Mode::Insert => return self.dispatch(Action::Accept),
+            Mode::Annotate => self.save_note(),
+            Mode::Command => self.run_command(),
+            Mode::Confirm => self.submit_confirmation(),
+            Mode::SaveAs => {
+                if self.save_as()? {
+                    return Ok(Flow::Exit(None));
+                }
+            }
+            Mode::Tag => self.toggle_tag_selected(),
+            Mode::Rename => self.rename_selected(),
+            Mode::Create => self.create_entry(),
+            Mode::Normal | Mode::Plan => {}
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn cancel_input(&mut self) {
+        match self.mode {
+            Mode::Annotate | Mode::Insert => self.set_mode(Mode::Normal),
+            Mode::Command => {
+                self.command_input.clear();
+                self.set_mode(Mode::Normal);
+            }
+            Mode::SaveAs => {
+                self.save_name.clear();
+                self.set_mode(Mode::Normal);
+            }
+            Mode::Rename => {
+                self.rename_input.clear();
+                self.set_mode(Mode::Normal);
+            }
+            Mode::Create => {
+                self.create_input.clear();
+                self.set_mode(Mode::Normal);
+            }
+            Mode::Tag => {
+                self.tag_input.clear();
+                self.set_mode(Mode::Normal);
+            }
+            // Cancelling is answering anything but `yes`
+            Mode::Confirm => {
+                self.confirm_input.clear();
+                self.submit_confirmation();
+            }
+            Mode::Normal | Mode::Plan => {}
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, SystemTime};
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Default `--age-colors` thresholds: today, this week, this month.
+pub const DEFAULT_THRESHOLDS: &str = "1d,7d,30d";
+
+/// Ages at which entry names get dimmer, youngest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeScale {
+    pub thresholds: Vec<Duration>,
+}
+
+impl AgeScale {
+    /// Parse a comma-separated list of ages such as `1d,7d,30d`, with units
+    /// `s`, `m`, `h`, `d` and `w`.
+    pub fn parse(spec: &str) -> Option<AgeScale> {
+        let mut thresholds = spec
+            .split(',')
+            .map(|age| parse_age(age.trim()))
+            .collect::<Option<Vec<_>>>()?;
+        if thresholds.is_empty() {
+            return None;
+        }
+        thresholds.sort();
+        Some(AgeScale { thresholds })
+    }
+
+    /// Tint for an entry last modified at `modified`: bright when younger
+    /// than the first threshold, dark grey past the last one.
+    pub fn style(&self, modified: SystemTime) -> Style {
+        let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+        let level = self.thresholds.iter().take_while(|&&threshold| age >= threshold).count();
+        let last = self.thresholds.len();
+        match level {
+            0 => Style::default().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            level if level == last => Style::default().fg(Color::DarkGray),
+            level if level * 2 <= last => Style::default().fg(Color::White),
+            _ => Style::default().fg(Color::Gray),
+        }
+    }
+}
+
+fn parse_age(age: &str) -> Option<Duration> {
+    let split = age.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = age.split_at(split);
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count.parse::<u64>().ok()? * seconds))
+}
@@ -0,0 +1,180 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+/// Convert text containing ANSI escape sequences (as produced by
+/// `bat --color=always`, `git diff --color`, ...) into styled ratatui text.
+/// SGR sequences become styles; any other escape sequence is dropped.
+pub fn to_text(content: &str) -> Text<'static> {
+    let mut style = Style::default();
+    let lines: Vec<Line> = content
+        .lines()
+        .map(|line| parse_line(line, &mut style))
+        .collect();
+    Text::from(lines)
+}
+
+/// Remove all escape sequences, keeping only the printable text.
+pub fn strip(content: &str) -> String {
+    let mut style = Style::default();
+    content
+        .lines()
+        .map(|line| {
+            parse_line(line, &mut style)
+                .spans
+                .into_iter()
+                .map(|span| span.content)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_line(line: &str, style: &mut Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            current.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            // CSI: ESC [ params final-byte
+            Some('[') => {
+                chars.next();
+                let mut params = String::new();
+                let mut final_byte = None;
+                for p in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&p) {
+                        final_byte = Some(p);
+                        break;
+                    }
+                    params.push(p);
+                }
+                if final_byte == Some('m') {
+                    if !current.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut current), *style));
+                    }
+                    *style = apply_sgr(*style, &params);
+                }
+            }
+            // OSC: ESC ] ... terminated by BEL or ESC \
+            Some(']') => {
+                chars.next();
+                while let Some(p) = chars.next() {
+                    if p == '\x07' {
+                        break;
+                    }
+                    if p == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Two-character escape such as ESC ( B
+            Some(_) => {
+                chars.next();
+            }
+            None => {}
+        }
+    }
+
+    if !current.is_empty() {
+        spans.push(Span::styled(current, *style));
+    }
+    Line::from(spans)
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            n @ 30..=37 => style = style.fg(basic_color(n - 30)),
+            n @ 40..=47 => style = style.bg(basic_color(n - 40)),
+            n @ 90..=97 => style = style.fg(bright_color(n - 90)),
+            n @ 100..=107 => style = style.bg(bright_color(n - 100)),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            38 | 48 => {
+                let (color, consumed) = extended_color(&codes[i + 1..]);
+                if let Some(color) = color {
+                    style = if codes[i] == 38 { style.fg(color) } else { style.bg(color) };
+                }
+                i += consumed;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+/// Parse the tail of a `38;5;n` / `38;2;r;g;b` sequence, returning the color
+/// and how many codes were consumed.
+fn extended_color(codes: &[u16]) -> (Option<Color>, usize) {
+    match codes.first() {
+        Some(5) => match codes.get(1) {
+            Some(&n) => (Some(Color::Indexed(n as u8)), 2),
+            None => (None, 1),
+        },
+        Some(2) if codes.len() >= 4 => (
+            Some(Color::Rgb(codes[1] as u8, codes[2] as u8, codes[3] as u8)),
+            4,
+        ),
+        Some(_) => (None, 1),
+        None => (None, 0),
+    }
+}
+
+fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(n: u16) -> Color {
+    match n {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
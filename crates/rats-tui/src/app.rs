@@ -0,0 +1,3284 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use ratatui::{layout::Rect, widgets::ListState};
+use crate::{
+    ansi,
+    audit,
+    bookmarks::Bookmarks,
+    cargo,
+    clipboard,
+    config::{Config, NetworkMode, SortOrder, Source},
+    crypt::{self, Cipher},
+    diagnostics,
+    dirdiff::DirDiff,
+    dirhistory::DirHistory,
+    entry::Entry,
+    expand::expand,
+    fields,
+    fold,
+    frecency,
+    gallery::{self, Grid},
+    history,
+    imageinfo,
+    journal,
+    listing::{self, Listing},
+    manpages,
+    media,
+    fuzzy::fuzzy_match,
+    keymap::Event,
+    mode::Mode,
+    names::{path_to_bytes, safe_filename_to_string},
+    preview::{self, DirectorySample, DIRECTORY_SAMPLE_LIMIT, HEAD_TAIL_LINES, LARGE_FILE_BYTES},
+    mounts,
+    mouse::{LastClick, PaneAreas},
+    notes::Notes,
+    pathref,
+    pool::{CancelSource, Priority, WorkerPool},
+    pins::{self, Pins},
+    plan::{FileOp, Plan},
+    privilege,
+    query::Query,
+    subtrees,
+    tags::Tags,
+    todos,
+    trash,
+    tools::{Tool, Tools},
+    thumbnail::{self, Graphics, Placement},
+    timing::Timings,
+    tree,
+    walk,
+    watch::DirWatcher,
+};
+
+/// How many entries above and below the selection have their previews
+/// read ahead.
+const PREFETCH_ENTRIES: usize = 3;
+
+/// How long the browsed directory has to stay quiet after entries come or
+/// go before it is read again, so a burst of changes costs one read.
+const REFRESH_DELAY: Duration = Duration::from_millis(200);
+
+/// How long the filter has to stay unchanged before `--live` runs the
+/// command for it, so typing a word costs one run.
+const LIVE_DELAY: Duration = Duration::from_millis(150);
+
+/// How long after the session state changes it is journaled, so moving
+/// through the list costs one write rather than one per entry.
+const JOURNAL_DELAY: Duration = Duration::from_millis(500);
+
+/// How often the previewed file is checked for changes on disk.
+const CHANGE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The entry accepted by the user, printed on exit for the calling tool.
+pub struct Selection {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.path.display(), line),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
+/// Path and line an excerpt was read for.
+pub type ExcerptKey = (PathBuf, Option<usize>);
+
+impl Selection {
+    /// Write the selection as raw path bytes (plus `:line`), followed by
+    /// `terminator`. Unlike `Display`, this keeps paths that aren't valid
+    /// UTF-8 intact for the consuming tool.
+    pub fn write_to(&self, out: &mut impl io::Write, terminator: u8) -> io::Result<()> {
+        out.write_all(&path_to_bytes(&self.path))?;
+        if let Some(line) = self.line {
+            write!(out, ":{}", line)?;
+        }
+        out.write_all(&[terminator])
+    }
+}
+
+/// A list saved while another one temporarily replaces it.
+struct Stash {
+    source: Source,
+    items: Vec<Entry>,
+    filter: String,
+    selected: Option<PathBuf>,
+}
+
+/// What `y` or `x` put aside for `p`.
+pub struct Register {
+    pub paths: Vec<PathBuf>,
+    /// Moved rather than copied when pasted
+    pub cut: bool,
+}
+
+/// Progress of a paste running in the background.
+enum Pasting {
+    /// Bytes to copy in all
+    Total(u64),
+    /// Bytes of a file just copied
+    Copied(u64),
+    /// Where the pasted entries went, or why the paste stopped
+    Done(Result<Vec<PathBuf>, String>),
+}
+
+pub struct App {
+    pub current_path: PathBuf,
+    pub items: Vec<Entry>,
+    /// Lines kept out of the list by `--header-lines`
+    pub header_lines: Vec<String>,
+    pub list_state: ListState,
+    pub filter: String,
+    pub query: Query,
+    pub filtered_items: Vec<(usize, i32, Vec<usize>)>, // (index, score, matched chars)
+    pub config: Config,
+    pub preview_content: Option<String>,
+    pub preview_scroll: usize,
+    /// Line of interest in the previewed file, highlighted and centered
+    pub preview_focus: Option<usize>,
+    /// Set when `preview_focus` changed and the pane should scroll to it
+    pub center_preview: bool,
+    /// Whether the preview pane is shown; when hidden the list takes the
+    /// full width
+    pub show_preview: bool,
+    /// Excerpts shown under list entries, cached by path and line
+    pub excerpts: HashMap<ExcerptKey, Option<String>>,
+    /// Draw image thumbnails in the list with kitty graphics
+    pub show_thumbnails: bool,
+    /// How the previewed image is drawn, if the terminal can
+    pub graphics: Option<Graphics>,
+    /// Thumbnail file for each image, `None` while it is being made (or if
+    /// that failed)
+    pub thumbnails: HashMap<PathBuf, Option<PathBuf>>,
+    /// Where the UI put thumbnails in the last frame
+    pub placements: Vec<Placement>,
+    /// Show entries as a grid of thumbnails instead of the list and preview
+    pub gallery: bool,
+    /// Gallery layout from the last frame
+    pub grid: Grid,
+    /// Whether `current_path` is browsed in network-filesystem mode
+    pub network: bool,
+    /// When the directory listing still being read was started
+    pub loading: Option<Instant>,
+    /// Events for `--bind 'event:...'` not acted on yet
+    events: Vec<Event>,
+    /// Entry the last `focus` event was for
+    focused: Option<PathBuf>,
+    /// How many entries matched when the list last settled
+    matched: Option<usize>,
+    /// Last directory listed in full, where cancelling a load goes back to
+    listed_path: Option<PathBuf>,
+    /// Entries to select once the listing being read reaches them, best
+    /// first
+    pending_select: Vec<PathBuf>,
+    /// Groups of a grouped recursive listing showing only their best entry,
+    /// by directory relative to `current_path`
+    pub collapsed: HashSet<PathBuf>,
+    /// How many filtered entries each group has, collapsed or not
+    pub group_sizes: HashMap<PathBuf, usize>,
+    /// How long the last load and filter pass took
+    pub timings: Timings,
+    /// Active subtrees the last recursive scan was limited to, 0 for all of
+    /// `current_path`
+    pub scanned_subtrees: usize,
+    /// Modification time of entries, read when first drawn with
+    /// `--age-colors`
+    pub mtimes: HashMap<PathBuf, Option<SystemTime>>,
+    /// Pixel size of images (and thumbnails), read once
+    pub dimensions: HashMap<PathBuf, Option<(u32, u32)>>,
+    /// EXIF capture date of photos, `None` while it is being read (or if
+    /// there is none)
+    pub capture_dates: HashMap<PathBuf, Option<String>>,
+    /// Whether j/k move the preview cursor instead of the selection
+    pub preview_focused: bool,
+    /// Line under the cursor in the focused preview
+    pub preview_cursor: usize,
+    /// Indentation of each preview line, for folding
+    pub preview_levels: Vec<Option<usize>>,
+    /// Start lines of the folded blocks in the preview
+    pub folds: BTreeSet<usize>,
+    pub mode: Mode,
+    pub tools: Tools,
+    pub status_message: Option<String>,
+    /// Pane positions from the last draw, for mouse hit testing
+    pub pane_areas: PaneAreas,
+    pub last_click: Option<LastClick>,
+    pub pins: Pins,
+    pub bookmarks: Bookmarks,
+    /// Directories browsed this session, for `Ctrl-o` and `L`
+    pub dir_history: DirHistory,
+    /// Indices into `items` of the pinned entries, listed before the rest
+    pub pinned: HashSet<usize>,
+    pub notes: Notes,
+    /// Note being typed in annotate mode
+    pub note_input: String,
+    pub tags: Tags,
+    /// Tag being typed in tag mode
+    pub tag_input: String,
+    /// New name being typed in rename mode, and the entry it is for
+    pub rename_input: String,
+    renaming: Option<PathBuf>,
+    /// Path being typed in create mode, relative to `current_path`
+    pub create_input: String,
+    /// Whether create mode makes a directory rather than a file
+    pub creating_dir: bool,
+    /// What an overlay (history, operation log) replaced, restored when it
+    /// is closed
+    overlay_stash: Option<Stash>,
+    /// Command being typed after `:`
+    pub command_input: String,
+    /// Entries marked for a batch operation, kept across directories
+    pub marked: BTreeSet<PathBuf>,
+    /// Size of each marked entry (directories with everything in them),
+    /// `None` while it is being added up
+    pub marked_sizes: HashMap<PathBuf, Option<u64>>,
+    /// The directories compared by `:diff` and how they differ
+    pub diff: Option<DirDiff>,
+    /// Clipboard mode's clips, newest first; entries point at them by index
+    pub clips: Vec<String>,
+    /// Entries left out of the list for the rest of the session
+    pub dismissed: HashSet<PathBuf>,
+    /// Batch operation shown on the plan screen, awaiting confirmation
+    pub plan: Option<Plan>,
+    /// Why each operation in `plan` would fail, if it would
+    pub plan_conflicts: Vec<Option<String>>,
+    pub plan_scroll: usize,
+    /// File name being typed to answer a save dialog
+    pub save_name: String,
+    /// Trash entry awaiting a second keypress to confirm purging
+    pub pending_purge: Option<PathBuf>,
+    /// File over the `--warn-size` limit awaiting a second Enter to accept it
+    pub pending_accept: Option<PathBuf>,
+    /// Confirmation being typed in confirm mode
+    pub confirm_input: String,
+    /// Running as root: shown in the header, and destructive operations
+    /// must be confirmed by typing `yes`
+    pub is_root: bool,
+    /// The current directory can't be modified by this user
+    pub read_only: bool,
+    preview_path: Option<PathBuf>,
+    /// Modification time and size of the previewed file as it was read
+    preview_stamp: Option<(SystemTime, u64)>,
+    /// Checksum of the previewed file as it was read, once computed
+    preview_checksum: Option<u64>,
+    /// The previewed file's contents changed on disk since it was read
+    pub preview_changed: bool,
+    change_checked: Instant,
+    checksum_tx: Sender<(PathBuf, u64)>,
+    checksum_rx: Receiver<(PathBuf, u64)>,
+    directory_sample: Option<DirectorySample>,
+    /// Runs all background work
+    pool: WorkerPool,
+    /// Cancels background work for a preview once it is replaced
+    preview_cancel: CancelSource,
+    count_tx: Sender<(PathBuf, usize)>,
+    count_rx: Receiver<(PathBuf, usize)>,
+    command_preview_tx: Sender<(PathBuf, String)>,
+    command_preview_rx: Receiver<(PathBuf, String)>,
+    decrypt_tx: Sender<(PathBuf, Result<String, String>)>,
+    decrypt_rx: Receiver<(PathBuf, Result<String, String>)>,
+    /// Previews read ahead for the entries around the selection, by path and
+    /// whether they were read for a focus line
+    prefetched: HashMap<(PathBuf, bool), Prefetched>,
+    /// Cancels reading ahead once the selection moves on
+    prefetch_cancel: CancelSource,
+    prefetch_tx: Sender<((PathBuf, bool), Prefetched)>,
+    prefetch_rx: Receiver<((PathBuf, bool), Prefetched)>,
+    /// Cancels per-entry work (excerpts, thumbnails) for a directory once
+    /// it is left
+    listing_cancel: CancelSource,
+    listing_rx: Receiver<Listing>,
+    /// Watches the browsed directory for entries made or removed elsewhere
+    watcher: Option<DirWatcher>,
+    /// When the watched directory last changed, while the refresh waits
+    /// for it to settle
+    dir_changed: Option<Instant>,
+    /// A fresh read of the browsed directory, swapped in whole once done so
+    /// the list doesn't empty and refill
+    refresh_rx: Option<Receiver<Listing>>,
+    refreshed: Vec<Entry>,
+    /// When the filter last changed, while `--live` waits for typing to
+    /// pause before running the command for it
+    live_changed: Option<Instant>,
+    /// When the directory, query or selection first changed since they
+    /// were last journaled
+    journal_changed: Option<Instant>,
+    /// What `y` or `x` put aside for `p`
+    pub register: Option<Register>,
+    /// Bytes copied so far and in all while a paste runs
+    pub paste_progress: Option<(u64, u64)>,
+    paste_tx: Sender<Pasting>,
+    paste_rx: Receiver<Pasting>,
+    /// Stops a running paste
+    paste_cancel: CancelSource,
+    /// Cancels adding up the sizes of marked entries once marks are cleared
+    marks_cancel: CancelSource,
+    size_tx: Sender<(PathBuf, u64)>,
+    size_rx: Receiver<(PathBuf, u64)>,
+    excerpt_tx: Sender<(ExcerptKey, String)>,
+    excerpt_rx: Receiver<(ExcerptKey, String)>,
+    thumbnail_tx: Sender<(PathBuf, PathBuf)>,
+    thumbnail_rx: Receiver<(PathBuf, PathBuf)>,
+    capture_date_tx: Sender<(PathBuf, String)>,
+    capture_date_rx: Receiver<(PathBuf, String)>,
+}
+
+impl App {
+    pub fn new(config: Config) -> Result<App, Box<dyn Error>> {
+        let current_path = config.directory.clone();
+        let (count_tx, count_rx) = mpsc::channel();
+        let (command_preview_tx, command_preview_rx) = mpsc::channel();
+        let (decrypt_tx, decrypt_rx) = mpsc::channel();
+        let (prefetch_tx, prefetch_rx) = mpsc::channel();
+        let (checksum_tx, checksum_rx) = mpsc::channel();
+        let (excerpt_tx, excerpt_rx) = mpsc::channel();
+        let (thumbnail_tx, thumbnail_rx) = mpsc::channel();
+        let (capture_date_tx, capture_date_rx) = mpsc::channel();
+        let (size_tx, size_rx) = mpsc::channel();
+        let (paste_tx, paste_rx) = mpsc::channel();
+        let tools = Tools::detect();
+        let graphics = Graphics::detect()
+            .filter(|_| config.thumbnails && (tools.is_available(Tool::Magick) || tools.is_available(Tool::Convert)));
+        // Anything but kitty's images would need the screen redrawn in full
+        // whenever the list scrolls
+        let show_thumbnails = graphics == Some(Graphics::Kitty);
+        
+        let gallery = config.gallery;
+        let mut app = App {
+            current_path: current_path.clone(),
+            items: Vec::new(),
+            header_lines: Vec::new(),
+            list_state: ListState::default(),
+            filter: config.query.clone(),
+            query: Query::parse(&config.query),
+            filtered_items: Vec::new(),
+            config,
+            preview_content: None,
+            preview_scroll: 0,
+            preview_focus: None,
+            center_preview: false,
+            show_preview: true,
+            excerpts: HashMap::new(),
+            show_thumbnails,
+            graphics,
+            thumbnails: HashMap::new(),
+            placements: Vec::new(),
+            gallery,
+            grid: Grid::default(),
+            network: false,
+            loading: None,
+            events: Vec::new(),
+            focused: None,
+            matched: None,
+            listed_path: None,
+            pending_select: Vec::new(),
+            collapsed: HashSet::new(),
+            group_sizes: HashMap::new(),
+            timings: Timings::default(),
+            scanned_subtrees: 0,
+            mtimes: HashMap::new(),
+            dimensions: HashMap::new(),
+            capture_dates: HashMap::new(),
+            preview_focused: false,
+            preview_cursor: 0,
+            preview_levels: Vec::new(),
+            folds: BTreeSet::new(),
+            mode: Mode::Normal,
+            tools,
+            status_message: None,
+            pending_purge: None,
+            pending_accept: None,
+            confirm_input: String::new(),
+            is_root: privilege::is_root(),
+            read_only: false,
+            overlay_stash: None,
+            command_input: String::new(),
+            marked: BTreeSet::new(),
+            dismissed: HashSet::new(),
+            marked_sizes: HashMap::new(),
+            diff: None,
+            clips: Vec::new(),
+            plan: None,
+            plan_conflicts: Vec::new(),
+            plan_scroll: 0,
+            save_name: String::new(),
+            pane_areas: PaneAreas::default(),
+            last_click: None,
+            pins: Pins::load(),
+            bookmarks: Bookmarks::load(),
+            dir_history: DirHistory::default(),
+            pinned: HashSet::new(),
+            notes: Notes::load(),
+            note_input: String::new(),
+            tags: Tags::load(),
+            tag_input: String::new(),
+            rename_input: String::new(),
+            renaming: None,
+            create_input: String::new(),
+            creating_dir: false,
+            preview_path: None,
+            preview_stamp: None,
+            preview_checksum: None,
+            preview_changed: false,
+            change_checked: Instant::now(),
+            checksum_tx,
+            checksum_rx,
+            directory_sample: None,
+            pool: WorkerPool::new(),
+            preview_cancel: CancelSource::default(),
+            count_tx,
+            count_rx,
+            command_preview_tx,
+            command_preview_rx,
+            decrypt_tx,
+            decrypt_rx,
+            prefetched: HashMap::new(),
+            prefetch_cancel: CancelSource::default(),
+            prefetch_tx,
+            prefetch_rx,
+            listing_cancel: CancelSource::default(),
+            listing_rx: mpsc::channel().1,
+            watcher: None,
+            dir_changed: None,
+            refresh_rx: None,
+            refreshed: Vec::new(),
+            live_changed: None,
+            journal_changed: None,
+            register: None,
+            paste_progress: None,
+            paste_tx,
+            paste_rx,
+            paste_cancel: CancelSource::default(),
+            marks_cancel: CancelSource::default(),
+            size_tx,
+            size_rx,
+            excerpt_tx,
+            excerpt_rx,
+            thumbnail_tx,
+            thumbnail_rx,
+            capture_date_tx,
+            capture_date_rx,
+        };
+        if app.config.use_bat && !app.tools.is_available(Tool::Bat) {
+            app.status_message = Some("bat not found, using built-in preview".to_string());
+        }
+        if !app.config.resume_crashed && journal::preserve_crashed() {
+            app.status_message = Some("Previous session crashed, restart with --resume-crashed to restore it".to_string());
+        }
+        match app.config.source {
+            Source::Browse => {
+                if !app.current_path.exists() {
+                    app.move_to_existing_ancestor("doesn't exist");
+                }
+                // Fail before the terminal is taken over, as an unreadable
+                // directory would otherwise just show an empty list
+                fs::read_dir(&app.current_path)?;
+                app.load_directory();
+            }
+            Source::Diagnostics => app.load_diagnostics()?,
+            Source::Todos => app.load_todos(),
+            Source::Cargo => app.load_cargo()?,
+            Source::Man => app.load_man_pages()?,
+            Source::Clipboard => app.load_clipboard()?,
+            Source::Trash => app.load_trash(),
+            Source::Mounts => app.load_mounts(),
+            Source::History => app.load_history(),
+            Source::Bookmarks => app.load_bookmarks(),
+            Source::Jump => app.load_jump(),
+            Source::Oplog => app.load_oplog(),
+            Source::Diff => app.load_diff(),
+            Source::Grep => {
+                fs::read_dir(&app.current_path)?;
+                app.load_grep();
+            }
+            Source::Stdin => app.load_stdin(),
+            Source::Command => {
+                if app.config.default_command.is_none() {
+                    return Err("RATS_DEFAULT_COMMAND is not set".into());
+                }
+                fs::read_dir(&app.current_path)?;
+                app.load_command();
+            }
+        }
+        if let Some(path) = app.config.select.clone() {
+            app.select_path(&path);
+        }
+        app.load_preview(); // Load preview for initial selection
+        Ok(app)
+    }
+
+    /// Populate the list from compiler output piped on stdin.
+    pub fn load_diagnostics(&mut self) -> Result<(), Box<dyn Error>> {
+        let output = io::read_to_string(io::stdin())?;
+        self.items = diagnostics::parse(&output)
+            .into_iter()
+            .map(|d| Entry::at_line(self.current_path.join(&d.path), d.line, d.label()))
+            .collect();
+        self.update_filter();
+        Ok(())
+    }
+
+    /// The repository `current_path` is in, or `current_path` itself in
+    /// network-filesystem mode, where looking for `.git` in every ancestor
+    /// is too slow.
+    fn project_root(&self) -> PathBuf {
+        if self.network {
+            self.current_path.clone()
+        } else {
+            pins::project_root(&self.current_path)
+        }
+    }
+
+    /// Directories a recursive scan of `current_path` covers: its active
+    /// monorepo subtrees unless widened to the whole repository.
+    pub fn scan_roots(&mut self) -> Vec<PathBuf> {
+        let subtrees = if self.network {
+            Vec::new()
+        } else {
+            subtrees::within(&self.current_path)
+        };
+        if self.config.whole_repo || subtrees.is_empty() {
+            self.scanned_subtrees = 0;
+            vec![self.current_path.clone()]
+        } else {
+            self.scanned_subtrees = subtrees.len();
+            subtrees
+        }
+    }
+
+    /// Switch recursive scans between the active subtrees and the whole
+    /// repository.
+    pub fn toggle_whole_repo(&mut self) {
+        self.config.whole_repo = !self.config.whole_repo;
+        let subtrees = subtrees::within(&self.current_path).len();
+        self.status_message = Some(if self.config.whole_repo {
+            "Searching the whole repository".to_string()
+        } else if subtrees == 0 {
+            "No active subtrees below this directory".to_string()
+        } else {
+            format!("Searching {} active subtrees", subtrees)
+        });
+        if self.config.source == Source::Todos {
+            self.load_todos();
+        }
+    }
+
+    /// Populate the list with TODO-style markers found under `current_path`.
+    /// Markers are listed as they are found (see `poll_background`).
+    pub fn load_todos(&mut self) {
+        self.items.clear();
+        let token = self.listing_cancel.renew();
+        self.listing_rx = listing::spawn_todos(self.scan_roots(), self.config.todo_tags.clone(), token);
+        self.loading = Some(Instant::now());
+        self.update_filter();
+    }
+
+    /// Populate the list with the workspace's packages, each followed by
+    /// its targets and files.
+    pub fn load_cargo(&mut self) -> Result<(), Box<dyn Error>> {
+        self.items = cargo::workspace(&self.current_path)?
+            .into_iter()
+            .map(|item| Entry::labeled(item.path, item.label))
+            .collect();
+        self.update_filter();
+        Ok(())
+    }
+
+    /// Populate the list with the man pages on the system, named by their
+    /// `man:` reference.
+    pub fn load_man_pages(&mut self) -> Result<(), Box<dyn Error>> {
+        self.items = manpages::list()?
+            .into_iter()
+            .map(|page| Entry::labeled(PathBuf::from(page.reference()), page.label()))
+            .collect();
+        self.update_filter();
+        Ok(())
+    }
+
+    /// Populate the list with the clipboard history, newest first.
+    pub fn load_clipboard(&mut self) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .config
+            .clipboard_history
+            .clone()
+            .or_else(clipboard::default_history)
+            .ok_or("no clipboard history file (see --clipboard-history)")?;
+        self.clips = clipboard::history(&path)?;
+        self.items = self
+            .clips
+            .iter()
+            .enumerate()
+            .map(|(i, clip)| {
+                let path = PathBuf::from(format!("{}{}", clipboard::SCHEME, i));
+                Entry::labeled(path, clipboard::label(clip))
+            })
+            .collect();
+        self.update_filter();
+        Ok(())
+    }
+
+    /// The clip the selected clipboard mode entry points at.
+    fn selected_clip(&self) -> Option<&String> {
+        let path = self.selected_entry()?.path.to_str()?;
+        self.clips.get(path.strip_prefix(clipboard::SCHEME)?.parse::<usize>().ok()?)
+    }
+
+    /// Put the selected clip back on the clipboard. Returns whether it was.
+    pub fn restore_clip(&mut self) -> bool {
+        let Some(clip) = self.selected_clip().cloned() else {
+            return false;
+        };
+        match clipboard::copy(&self.tools, &clip) {
+            Ok(_) => true,
+            Err(err) => {
+                self.status_message = Some(format!("Cannot copy: {}", err));
+                false
+            }
+        }
+    }
+
+    /// Populate the list with the contents of the trash.
+    pub fn load_trash(&mut self) {
+        self.items = trash::list()
+            .into_iter()
+            .map(|item| {
+                let label = item.label();
+                Entry::labeled(item.path, label)
+            })
+            .collect();
+        self.update_filter();
+    }
+
+    /// Restore the selected trash entry to where it was deleted from.
+    pub fn restore_selected(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        self.status_message = Some(match trash::restore(&entry.path) {
+            Ok(original) => {
+                let _ = audit::record("restore", &entry.path, Some(&original));
+                format!("Restored {}", original.display())
+            }
+            Err(err) => format!("Restore failed: {}", err),
+        });
+        self.keep_selection(App::load_trash);
+    }
+
+    /// Permanently delete the selected trash entry. The first call only asks
+    /// for confirmation; a second call on the same entry purges it.
+    pub fn purge_selected(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let path = entry.path.clone();
+        if self.is_root {
+            self.pending_purge = Some(path);
+            self.confirm_input.clear();
+            self.mode = Mode::Confirm;
+            return;
+        }
+        if self.pending_purge.as_ref() != Some(&path) {
+            self.status_message = Some(format!(
+                "Press D again to permanently delete {}",
+                entry.display_name()
+            ));
+            self.pending_purge = Some(path);
+            return;
+        }
+
+        self.pending_purge = None;
+        self.purge(&path);
+    }
+
+    /// Act on what was typed in confirm mode: anything but `yes` cancels,
+    /// going back to the plan if it was one being confirmed.
+    pub fn submit_confirmation(&mut self) {
+        self.mode = Mode::Normal;
+        let confirmed = std::mem::take(&mut self.confirm_input) == "yes";
+        if let Some(path) = self.pending_purge.take() {
+            if confirmed {
+                self.purge(&path);
+            } else {
+                self.status_message = Some("Cancelled".to_string());
+            }
+        } else if self.plan.is_some() {
+            if confirmed {
+                self.execute_plan();
+            } else {
+                self.mode = Mode::Plan;
+                self.status_message = Some("Cancelled".to_string());
+            }
+        }
+    }
+
+    fn purge(&mut self, path: &Path) {
+        self.status_message = Some(match trash::purge(path) {
+            Ok(()) => {
+                let _ = audit::record("purge", path, None);
+                "Purged from trash".to_string()
+            }
+            Err(err) => format!("Purge failed: {}", err),
+        });
+        self.keep_selection(App::load_trash);
+    }
+
+    /// Populate the list with mounted filesystems and removable devices.
+    pub fn load_mounts(&mut self) {
+        self.items = mounts::list()
+            .into_iter()
+            .map(|mount| Entry::labeled(mount.path(), mount.label()))
+            .collect();
+        self.update_filter();
+    }
+
+    /// Mount (if it is an unmounted device) or unmount the selected entry
+    /// through udisks.
+    pub fn toggle_mount_selected(&mut self, mount: bool) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if !self.tools.is_available(Tool::Udisksctl) {
+            self.status_message = Some("udisksctl not found, cannot (un)mount".to_string());
+            return;
+        }
+        let result = if mount {
+            mounts::mount(&entry.path.to_string_lossy())
+        } else {
+            mounts::unmount(&entry.path)
+        };
+        self.status_message = Some(match result {
+            Ok(message) => {
+                let action = if mount { "mount" } else { "unmount" };
+                let _ = audit::record(action, &entry.path, None);
+                message
+            }
+            Err(err) => format!("Failed: {}", err),
+        });
+        self.keep_selection(App::load_mounts);
+    }
+
+    /// Populate the list with previously accepted selections.
+    pub fn load_history(&mut self) {
+        self.items = history::load()
+            .into_iter()
+            .map(|entry| Entry {
+                label: Some(entry.label()),
+                path: entry.path,
+                line: entry.line,
+                search: None,
+                dir: None,
+            })
+            .collect();
+        self.update_filter();
+    }
+
+    /// Populate the list with the bookmarked directories, by letter.
+    pub fn load_bookmarks(&mut self) {
+        self.items = self
+            .bookmarks
+            .iter()
+            .map(|(letter, dir)| Entry::labeled(dir.to_path_buf(), format!("{}  {}", letter, dir.display())))
+            .collect();
+        self.update_filter();
+    }
+
+    /// Populate the list with the directories browsed before, most frecent
+    /// first.
+    pub fn load_jump(&mut self) {
+        self.items = frecency::load()
+            .into_iter()
+            .map(|visited| Entry::labeled(visited.dir.clone(), visited.label()))
+            .collect();
+        self.update_filter();
+    }
+
+    /// Bookmark the browsed directory as `letter`.
+    pub fn set_bookmark(&mut self, letter: char) {
+        let dir = self.current_path.clone();
+        self.status_message = Some(match self.bookmarks.set(letter, dir.clone()) {
+            Ok(()) => format!("Bookmarked {} as '{}", dir.display(), letter),
+            Err(err) => format!("Cannot save bookmark: {}", err),
+        });
+    }
+
+    /// Browse the directory bookmarked as `letter`, closing any overlay.
+    pub fn jump_to_bookmark(&mut self, letter: char) {
+        match self.bookmarks.get(letter).map(Path::to_path_buf) {
+            Some(dir) => self.browse(dir),
+            None => self.status_message = Some(format!("No bookmark '{}", letter)),
+        }
+    }
+
+    /// Go back (or with `forward`, forward again) to a directory browsed
+    /// before, selecting what was selected when it was left.
+    pub fn step_dir_history(&mut self, forward: bool) {
+        let selected = match self.config.source {
+            Source::Browse => self.selected_entry().map(|entry| entry.path.clone()),
+            _ => None,
+        };
+        let visit = match forward {
+            true => self.dir_history.forward(selected),
+            false => self.dir_history.back(selected),
+        };
+        let Some(visit) = visit else {
+            self.status_message = Some(match forward {
+                true => "No later directory".to_string(),
+                false => "No earlier directory".to_string(),
+            });
+            return;
+        };
+        self.browse(visit.dir);
+        if let Some(selected) = visit.selected {
+            self.select_path(&selected);
+        }
+    }
+
+    /// Leave whatever is listed to browse `dir`.
+    fn browse(&mut self, dir: PathBuf) {
+        if !dir.is_dir() {
+            self.status_message = Some(format!("Not a directory: {}", dir.display()));
+            return;
+        }
+        self.overlay_stash = None;
+        self.config.source = Source::Browse;
+        self.current_path = dir;
+        self.filter.clear();
+        self.load_directory();
+    }
+
+    /// Populate the list with the audit log of file operations.
+    pub fn load_oplog(&mut self) {
+        self.items = audit::load()
+            .into_iter()
+            .map(|operation| {
+                let label = operation.label();
+                Entry::labeled(operation.new_path.unwrap_or(operation.path), label)
+            })
+            .collect();
+        self.update_filter();
+    }
+
+    /// Populate the list with the differences between the compared
+    /// directories, comparing them again.
+    pub fn load_diff(&mut self) {
+        self.items.clear();
+        let token = self.listing_cancel.renew();
+        match &mut self.diff {
+            Some(diff) => {
+                diff.differences.clear();
+                self.listing_rx = listing::spawn_diff(diff.left.clone(), diff.right.clone(), token);
+                self.loading = Some(Instant::now());
+            }
+            None => {
+                self.listing_rx = mpsc::channel().1;
+                self.loading = None;
+            }
+        }
+        self.update_filter();
+    }
+
+    /// Compare the two marked directories, or the current directory with
+    /// `dir`, showing the differences in place of the list. Without either,
+    /// closes the comparison.
+    fn compare_directories(&mut self, dir: &str) {
+        if self.config.source == Source::Diff && dir.is_empty() {
+            self.toggle_overlay(Source::Diff);
+            return;
+        }
+        let dirs: Vec<PathBuf> = if dir.is_empty() {
+            self.marked.iter().filter(|path| path.is_dir()).cloned().collect()
+        } else {
+            vec![self.current_path.clone(), self.current_path.join(expand(dir))]
+        };
+        let [left, right] = dirs.as_slice() else {
+            self.status_message = Some("Mark two directories, or use :diff <dir>".to_string());
+            return;
+        };
+        if !right.is_dir() {
+            self.status_message = Some(format!("Not a directory: {}", right.display()));
+            return;
+        }
+        self.diff = Some(DirDiff {
+            left: left.clone(),
+            right: right.clone(),
+            differences: Vec::new(),
+        });
+        self.clear_marks();
+        if self.config.source == Source::Diff {
+            self.load_diff();
+        } else {
+            self.toggle_overlay(Source::Diff);
+        }
+    }
+
+    /// Plan copying the files missing on either side of the comparison
+    /// across.
+    fn plan_copy_missing(&mut self) {
+        match &self.diff {
+            Some(_) if self.config.source == Source::Diff && self.loading.is_some() => {
+                self.status_message = Some("Still comparing, wait for it to finish (or Esc to stop)".to_string());
+            }
+            Some(diff) if self.config.source == Source::Diff => {
+                let plan = diff.copy_missing();
+                if plan.operations.is_empty() {
+                    self.status_message = Some("No files missing on either side".to_string());
+                } else {
+                    self.review_plan(plan);
+                }
+            }
+            _ => self.status_message = Some("Compare two directories with :diff first".to_string()),
+        }
+    }
+
+    /// Swap the list for the selection history, or back to what was shown
+    /// before it.
+    pub fn toggle_history(&mut self) {
+        self.toggle_overlay(Source::History);
+    }
+
+    /// Swap the list for `overlay` (history, the operation log), or back to
+    /// what was shown before it. Opening one overlay over another replaces
+    /// it.
+    pub fn toggle_overlay(&mut self, overlay: Source) {
+        if let Some(stash) = self.overlay_stash.take() {
+            let closing = self.config.source;
+            // A comparison still going would list into what comes back
+            if closing == Source::Diff && self.loading.take().is_some() {
+                self.listing_cancel.renew();
+                self.listing_rx = mpsc::channel().1;
+            }
+            self.config.source = stash.source;
+            self.items = stash.items;
+            self.filter = stash.filter;
+            self.update_filter();
+            if let Some(path) = stash.selected {
+                self.select_path(&path);
+                self.load_preview();
+            }
+            if closing == overlay {
+                return;
+            }
+        }
+        if self.config.source == overlay {
+            return;
+        }
+        self.overlay_stash = Some(Stash {
+            source: self.config.source,
+            items: std::mem::take(&mut self.items),
+            filter: std::mem::take(&mut self.filter),
+            selected: self.selected_entry().map(|entry| entry.path.clone()),
+        });
+        self.config.source = overlay;
+        match overlay {
+            Source::Bookmarks => self.load_bookmarks(),
+            Source::Jump => self.load_jump(),
+            Source::Oplog => self.load_oplog(),
+            Source::Diff => self.load_diff(),
+            _ => self.load_history(),
+        }
+    }
+
+    /// Run a command typed after `:`.
+    pub fn run_command(&mut self) {
+        self.mode = Mode::Normal;
+        let command = std::mem::take(&mut self.command_input);
+        let (name, arg) = match command.trim().split_once(' ') {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (command.trim(), ""),
+        };
+        match (name, arg) {
+            ("", _) => {}
+            ("oplog", _) => self.toggle_overlay(Source::Oplog),
+            ("diff", dir) => self.compare_directories(dir),
+            ("copy-missing", _) => self.plan_copy_missing(),
+            ("history", _) => self.toggle_overlay(Source::History),
+            ("marks", _) => self.toggle_overlay(Source::Bookmarks),
+            ("jump", _) => self.toggle_overlay(Source::Jump),
+            ("gallery", _) => self.toggle_gallery(),
+            ("widen", _) => self.toggle_whole_repo(),
+            ("recursive", _) => self.toggle_recursive(),
+            ("group", _) => {
+                self.config.group = !self.config.group;
+                self.refilter();
+            }
+            ("ignored", _) => self.toggle_ignored(),
+            ("unhide", _) => self.undismiss_all(),
+            ("age", _) => self.toggle_age_colors(),
+            ("sort", order) => match SortOrder::from_name(order) {
+                Some(order) => {
+                    self.config.sort = order;
+                    self.refilter();
+                }
+                None => self.status_message = Some("Usage: :sort name|taken".to_string()),
+            },
+            ("trash", _) => self.trash_selected(),
+            ("delete", _) => self.plan_delete(),
+            ("move", dir) if !dir.is_empty() => self.plan_transfer(dir, true),
+            ("copy", dir) if !dir.is_empty() => self.plan_transfer(dir, false),
+            ("move" | "copy", _) => {
+                self.status_message = Some(format!("Usage: :{} <directory>", name));
+            }
+            _ => self.status_message = Some(format!("Unknown command: {}", command.trim())),
+        }
+    }
+
+    /// Mark or unmark the selected entry for a batch operation, then move
+    /// to the next one.
+    pub fn toggle_mark_selected(&mut self) {
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        if path.file_name().is_none_or(|name| name == "..") {
+            return;
+        }
+        if !self.marked.remove(&path) {
+            self.request_size(&path);
+            self.marked.insert(path);
+        }
+        self.next();
+    }
+
+    /// Start adding up the size of a marked entry unless it is known.
+    fn request_size(&mut self, path: &Path) {
+        if self.marked_sizes.contains_key(path) {
+            return;
+        }
+        self.marked_sizes.insert(path.to_path_buf(), None);
+        let tx = self.size_tx.clone();
+        let path = path.to_path_buf();
+        self.pool.spawn(Priority::Low, self.marks_cancel.current(), move |token| {
+            if let Some(size) = walk::total_size(&path, token) {
+                let _ = tx.send((path, size));
+            }
+        });
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.marked_sizes.clear();
+        self.marks_cancel.renew();
+    }
+
+    /// Count and total size of the marked entries for the status bar, or
+    /// `None` when nothing is marked.
+    pub fn marked_summary(&self) -> Option<String> {
+        if self.marked.is_empty() {
+            return None;
+        }
+        let sizes: Vec<Option<u64>> = self
+            .marked
+            .iter()
+            .map(|path| self.marked_sizes.get(path).copied().flatten())
+            .collect();
+        let total = preview::human_size(sizes.iter().flatten().sum());
+        Some(if sizes.contains(&None) {
+            format!("{} marked, {}+ (counting)", self.marked.len(), total)
+        } else {
+            format!("{} marked, {}", self.marked.len(), total)
+        })
+    }
+
+    /// What a batch operation applies to: the marked entries, or the
+    /// selected one when nothing is marked.
+    fn batch_targets(&self) -> Vec<PathBuf> {
+        if !self.marked.is_empty() {
+            return self.marked.iter().cloned().collect();
+        }
+        self.selected_entry()
+            .map(|entry| entry.path.clone())
+            .filter(|path| path.file_name().is_some_and(|name| name != ".."))
+            .into_iter()
+            .collect()
+    }
+
+    /// Move the marked entries, or the selected one, to the system trash,
+    /// where `--mode trash` can restore them. Stops at the first failure.
+    pub fn trash_selected(&mut self) {
+        let targets = self.batch_targets();
+        if targets.is_empty() {
+            self.status_message = Some("Nothing selected or marked".to_string());
+            return;
+        }
+        let mut trashed = 0;
+        let mut failure = None;
+        for path in &targets {
+            if let Err(err) = trash::send(path) {
+                failure = Some(format!("Cannot trash {}: {}", safe_filename_to_string(path), err));
+                break;
+            }
+            let _ = audit::record("trash", path, None);
+            trashed += 1;
+        }
+        self.status_message = Some(match failure {
+            Some(failure) if trashed > 0 => format!("{} ({} of {} trashed)", failure, trashed, targets.len()),
+            Some(failure) => failure,
+            None if trashed == 1 => "Moved 1 entry to the trash".to_string(),
+            None => format!("Moved {} entries to the trash", trashed),
+        });
+        self.clear_marks();
+        if self.config.source == Source::Browse {
+            let candidates = self.selection_candidates();
+            self.load_directory();
+            self.pending_select = candidates;
+            self.load_preview();
+        }
+    }
+
+    /// Review permanently deleting the marked entries, or the selected one.
+    pub fn plan_delete(&mut self) {
+        let operations = self
+            .batch_targets()
+            .into_iter()
+            .map(|path| FileOp::Delete { path })
+            .collect();
+        self.review_plan(Plan { operations });
+    }
+
+    /// Plan moving or copying the batch into `dir`, relative to the current
+    /// directory, creating it first if needed.
+    fn plan_transfer(&mut self, dir: &str, moving: bool) {
+        let dir = self.current_path.join(expand(dir));
+        let mut operations = Vec::new();
+        if !dir.exists() {
+            operations.push(FileOp::Mkdir { path: dir.clone() });
+        }
+        for from in self.batch_targets() {
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            let to = dir.join(name);
+            operations.push(if moving {
+                FileOp::Move { from, to }
+            } else {
+                FileOp::Copy { from, to }
+            });
+        }
+        self.review_plan(Plan { operations });
+    }
+
+    /// Put the marked entries, or the selected one, aside for `paste`; with
+    /// `cut` they are moved rather than copied.
+    pub fn yank(&mut self, cut: bool) {
+        let paths = self.batch_targets();
+        if paths.is_empty() {
+            self.status_message = Some("Nothing selected or marked".to_string());
+            return;
+        }
+        self.status_message = Some(format!(
+            "{} {} {}, p pastes {} here",
+            if cut { "Cut" } else { "Yanked" },
+            paths.len(),
+            if paths.len() == 1 { "entry" } else { "entries" },
+            if cut { "them" } else { "copies" },
+        ));
+        self.register = Some(Register { paths, cut });
+        self.clear_marks();
+    }
+
+    /// Copy or move what was yanked or cut into the current directory, in
+    /// the background. A paste that would overwrite something goes to the
+    /// plan screen instead, with the conflicts flagged.
+    pub fn paste(&mut self) {
+        if self.paste_progress.is_some() {
+            self.status_message = Some("Still pasting".to_string());
+            return;
+        }
+        let Some(register) = &self.register else {
+            self.status_message = Some("Nothing yanked (y) or cut (x)".to_string());
+            return;
+        };
+        if let Some(path) = register.paths.iter().find(|path| self.current_path.starts_with(path)) {
+            self.status_message = Some(format!("Cannot paste {} into itself", safe_filename_to_string(path)));
+            return;
+        }
+        let operations: Vec<FileOp> = register
+            .paths
+            .iter()
+            .filter_map(|from| {
+                let to = self.current_path.join(from.file_name()?);
+                let from = from.clone();
+                Some(if register.cut { FileOp::Move { from, to } } else { FileOp::Copy { from, to } })
+            })
+            .collect();
+        let plan = Plan { operations };
+        if plan.conflicts().iter().any(Option::is_some) {
+            self.review_plan(plan);
+            return;
+        }
+
+        self.forget_cut(&plan);
+        self.paste_progress = Some((0, 0));
+        let tx = self.paste_tx.clone();
+        let token = self.paste_cancel.renew();
+        thread::spawn(move || {
+            let total = plan
+                .operations
+                .iter()
+                .filter_map(|op| match op {
+                    FileOp::Copy { from, .. } | FileOp::Move { from, .. } => walk::total_size(from, &token),
+                    _ => None,
+                })
+                .sum();
+            let _ = tx.send(Pasting::Total(total));
+            let result = plan.execute_with_progress(&token, &mut |bytes| {
+                let _ = tx.send(Pasting::Copied(bytes));
+            });
+            let pasted = plan
+                .operations
+                .iter()
+                .filter_map(|op| match op {
+                    FileOp::Copy { to, .. } | FileOp::Move { to, .. } => Some(to.clone()),
+                    _ => None,
+                })
+                .collect();
+            let _ = tx.send(Pasting::Done(result.map(|()| pasted).map_err(|err| err.to_string())));
+        });
+    }
+
+    /// Drop a cut register once `plan` moves its entries away: cut entries
+    /// can only be pasted once.
+    fn forget_cut(&mut self, plan: &Plan) {
+        let moved = |path: &PathBuf| {
+            plan.operations
+                .iter()
+                .any(|op| matches!(op, FileOp::Move { from, .. } if from == path))
+        };
+        if self.register.as_ref().is_some_and(|register| register.cut && register.paths.iter().any(moved)) {
+            self.register = None;
+        }
+    }
+
+    /// Show `plan` on the plan screen for confirmation.
+    fn review_plan(&mut self, plan: Plan) {
+        if plan.operations.iter().all(|op| matches!(op, FileOp::Mkdir { .. })) {
+            self.status_message = Some("Nothing selected or marked".to_string());
+            return;
+        }
+        self.plan_conflicts = plan.conflicts();
+        self.plan = Some(plan);
+        self.plan_scroll = 0;
+        self.mode = Mode::Plan;
+    }
+
+    /// Carry out the plan being reviewed. Refused while any operation
+    /// conflicts, so a batch never stops halfway on a foreseeable failure.
+    /// As root, a plan deleting or moving anything first needs `yes` typed.
+    pub fn apply_plan(&mut self) {
+        let conflicts = self.plan_conflicts.iter().flatten().count();
+        if conflicts > 0 {
+            self.status_message = Some(format!(
+                "{} conflicting operations; export the plan to edit it, or Esc to cancel",
+                conflicts
+            ));
+            return;
+        }
+        let destructive = self.plan.as_ref().is_some_and(|plan| {
+            plan.operations.iter().any(|op| matches!(op, FileOp::Delete { .. } | FileOp::Move { .. }))
+        });
+        if self.is_root && destructive {
+            self.confirm_input.clear();
+            self.mode = Mode::Confirm;
+            return;
+        }
+        self.execute_plan();
+    }
+
+    fn execute_plan(&mut self) {
+        let Some(plan) = self.plan.take() else {
+            return;
+        };
+        self.mode = Mode::Normal;
+        self.forget_cut(&plan);
+        self.status_message = Some(match plan.execute() {
+            Ok(()) => format!("Applied {} operations", plan.operations.len()),
+            Err(err) => format!("Batch stopped: {}", err),
+        });
+        self.clear_marks();
+        if self.config.source == Source::Diff {
+            // Restored as the differences come in
+            let candidates = self.selection_candidates();
+            self.load_diff();
+            self.pending_select = candidates;
+        }
+        if self.config.source == Source::Browse {
+            // Restored as the listing comes in
+            let candidates = self.selection_candidates();
+            self.load_directory();
+            self.pending_select = candidates;
+            self.load_preview();
+        }
+    }
+
+    /// Write the plan being reviewed to `plan.json` in the state directory,
+    /// for `rats apply`.
+    pub fn export_plan(&mut self) {
+        let Some(plan) = &self.plan else {
+            return;
+        };
+        let Some(file) = history::state_dir().map(|dir| dir.join("plan.json")) else {
+            self.status_message = Some("Export failed: no state directory".to_string());
+            return;
+        };
+        let written = serde_json::to_string_pretty(plan)
+            .map_err(io::Error::other)
+            .and_then(|json| {
+                fs::create_dir_all(file.parent().unwrap_or(&file))?;
+                fs::write(&file, json)
+            });
+        self.status_message = Some(match written {
+            Ok(()) => format!("Wrote plan to {} (run with rats apply)", file.display()),
+            Err(err) => format!("Export failed: {}", err),
+        });
+    }
+
+    pub fn cancel_plan(&mut self) {
+        self.plan = None;
+        self.plan_conflicts.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn scroll_plan(&mut self, delta: isize) {
+        let len = self.plan.as_ref().map_or(0, |plan| plan.operations.len());
+        self.plan_scroll = self
+            .plan_scroll
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    /// Start listing `current_path`, or with `--recursive` every file below
+    /// it. Entries show up as they are read (see `poll_background`), so a
+    /// slow disk or a huge tree never blocks the UI.
+    pub fn load_directory(&mut self) {
+        // Before the list is cleared, so the history knows what was selected
+        let selected = self.selected_entry().map(|entry| entry.path.clone());
+        if self.dir_history.visit(&self.current_path, selected) {
+            // Failing to count the visit shouldn't stop browsing
+            let _ = frecency::record(&self.current_path);
+        }
+        self.items.clear();
+        self.network = match self.config.network {
+            NetworkMode::Auto => mounts::is_network(&self.current_path),
+            NetworkMode::On => true,
+            NetworkMode::Off => false,
+        };
+        self.read_only = !privilege::is_writable(&self.current_path);
+        // Walks and network mounts (which rarely send change events) aren't
+        // watched
+        if self.config.recursive || self.network {
+            self.watcher = None;
+        } else if self.watcher.as_ref().is_none_or(|watcher| watcher.dir != self.current_path) {
+            self.watcher = DirWatcher::new(&self.current_path);
+        }
+        self.dir_changed = None;
+        self.refresh_rx = None;
+        // Excerpts, thumbnails and capture dates still queued for the old
+        // directory won't be shown, and a read still going stops
+        let token = self.listing_cancel.renew();
+        if self.listed_path.as_ref() != Some(&self.current_path) {
+            self.collapsed.clear();
+        }
+        self.excerpts.retain(|_, excerpt| excerpt.is_some());
+        self.thumbnails.retain(|_, thumbnail| thumbnail.is_some());
+        self.capture_dates.retain(|_, date| date.is_some());
+        // Files may have changed since the directory was last shown
+        self.mtimes.clear();
+        
+        // Add parent directory entry if not at root
+        if self.current_path.parent().is_some() {
+            self.items.push(Entry::from_path(self.current_path.join("..")));
+        }
+        
+        self.listing_rx = if self.config.recursive {
+            listing::spawn_walk(self.current_path.clone(), !self.config.no_ignore, token)
+        } else {
+            listing::spawn(self.current_path.clone(), !self.config.no_ignore, token)
+        };
+        self.loading = Some(Instant::now());
+        self.update_filter();
+    }
+
+    /// Show or hide dot-files, keeping the selection where it can.
+    pub fn toggle_hidden(&mut self) {
+        self.config.hidden = !self.config.hidden;
+        self.refilter();
+    }
+
+    /// Show or hide paths ignored by git, listing or searching again.
+    pub fn toggle_ignored(&mut self) {
+        self.config.no_ignore = !self.config.no_ignore;
+        self.status_message = Some(match self.config.no_ignore {
+            true => "Showing git-ignored files".to_string(),
+            false => "Hiding git-ignored files".to_string(),
+        });
+        match self.config.source {
+            Source::Browse => self.load_directory(),
+            Source::Grep => self.load_grep(),
+            _ => {}
+        }
+    }
+
+    /// Switch between listing the directory and every file below it.
+    pub fn toggle_recursive(&mut self) {
+        if self.config.source != Source::Browse {
+            self.status_message = Some("Recursive listing only applies when browsing".to_string());
+            return;
+        }
+        self.config.recursive = !self.config.recursive;
+        self.load_directory();
+    }
+
+    /// Make the nearest existing ancestor of `current_path` current, saying
+    /// why the path was left (`what` happened to it).
+    fn move_to_existing_ancestor(&mut self, what: &str) {
+        let gone = std::path::absolute(&self.current_path).unwrap_or_else(|_| self.current_path.clone());
+        let ancestor = gone.ancestors().skip(1).find(|dir| dir.is_dir()).unwrap_or(Path::new("/"));
+        self.status_message = Some(format!("{} {}, showing {}", gone.display(), what, ancestor.display()));
+        self.current_path = ancestor.to_path_buf();
+    }
+
+    /// Start running `RATS_DEFAULT_COMMAND` in `current_path`, listing the
+    /// paths it prints as they come (see `poll_background`), in its order.
+    /// `{q}` in the command stands for the filter text, quoted.
+    pub fn load_command(&mut self) {
+        let Some(command) = self.config.default_command.clone() else {
+            return;
+        };
+        let command = fields::fill(&command, None, &self.config.delimiter, &self.filter);
+        self.items.clear();
+        self.header_lines.clear();
+        let token = self.listing_cancel.renew();
+        self.listing_rx = listing::spawn_command(command, self.current_path.clone(), self.read_delimiter(), token);
+        self.loading = Some(Instant::now());
+        self.update_filter();
+    }
+
+    /// Start reading the entries piped on stdin, listing them as they come
+    /// (see `poll_background`), in their order.
+    pub fn load_stdin(&mut self) {
+        self.items.clear();
+        self.header_lines.clear();
+        let token = self.listing_cancel.renew();
+        self.listing_rx = listing::spawn_stdin(self.read_delimiter(), token);
+        self.loading = Some(Instant::now());
+        self.update_filter();
+    }
+
+    /// The lines shown above the list: `--header`, then those taken by
+    /// `--header-lines`.
+    pub fn header(&self) -> impl Iterator<Item = &str> {
+        self.config.header.iter().flat_map(|header| header.lines()).chain(self.header_lines.iter().map(String::as_str))
+    }
+
+    /// What separates the entries of a command's output or stdin.
+    fn read_delimiter(&self) -> u8 {
+        if self.config.read0 { b'\0' } else { b'\n' }
+    }
+
+    /// An entry for what the listing command printed, or a line from
+    /// stdin. Those lines, and any with `--nth` or `--with-nth`, are
+    /// records rather than paths: they are kept as they were read, to be
+    /// printed again when accepted, and show and match the fields picked.
+    fn printed_entry(&self, line: PathBuf) -> Entry {
+        let text = line.to_string_lossy();
+        let (nth, with_nth) = (&self.config.nth, &self.config.with_nth);
+        if nth.is_none() && with_nth.is_none() && self.config.source != Source::Stdin {
+            // Newlines (from `--read0`) would break the entry's row
+            let label = text.replace('\n', "␤");
+            return Entry::labeled(self.current_path.join(line), label);
+        }
+        let delimiter = &self.config.delimiter;
+        let label = match with_nth {
+            Some(fields) => fields.pick(&text, delimiter),
+            None => text.to_string(),
+        };
+        let search = nth.as_ref().map(|fields| fields.pick(&text, delimiter));
+        // Tabs don't line up in the list
+        let label = label.replace('\n', "␤").replace('\t', " ");
+        Entry {
+            search,
+            ..Entry::labeled(line.clone(), label)
+        }
+    }
+
+    /// The line a command or stdin printed for the entry at `path`, as it
+    /// was read.
+    fn printed_line(&self, path: &Path) -> Option<String> {
+        match self.config.source {
+            Source::Command => Some(path.strip_prefix(&self.current_path).unwrap_or(path).to_string_lossy().into_owned()),
+            Source::Stdin => Some(path.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    }
+
+    /// Run `command` with `sh -c` in the browsed directory, without waiting
+    /// for it or showing its output. Its placeholders are filled in from
+    /// the selected entry as for `--preview-cmd`.
+    pub fn execute_silent(&mut self, command: &str) {
+        let line = match self.selected_entry() {
+            Some(entry) => self.printed_line(&entry.path).unwrap_or_else(|| entry.path.to_string_lossy().into_owned()),
+            None => String::new(),
+        };
+        let command = fields::fill(command, Some(&line), &self.config.delimiter, &self.filter);
+        let spawned = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&self.current_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            // Reaped on its own thread so it doesn't linger as a zombie
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(err) => self.status_message = Some(format!("Cannot run {}: {}", command, err)),
+        }
+    }
+
+    /// Events that happened since this was last called: `load` as each
+    /// listing is read in full, `focus` when another entry is selected, and
+    /// `zero` or `one` when the number of matches, not counting `..`,
+    /// becomes that once the list has settled.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        let focused = self.selected_entry().map(|entry| entry.path.clone());
+        if focused != self.focused {
+            if focused.is_some() {
+                self.events.push(Event::Focus);
+            }
+            self.focused = focused;
+        }
+        // The parent entry is how to leave, not a match
+        let matched = self
+            .filtered_items
+            .iter()
+            .filter(|(index, ..)| self.items[*index].path.file_name().is_some_and(|name| name != ".."))
+            .count();
+        if self.loading.is_none() && self.matched != Some(matched) {
+            match matched {
+                0 => self.events.push(Event::Zero),
+                1 => self.events.push(Event::One),
+                _ => {}
+            }
+            self.matched = Some(matched);
+        }
+        std::mem::take(&mut self.events)
+    }
+
+    /// Run the command listing the entries again, or `command` in its place,
+    /// keeping the filter and (once it is listed again) the selection.
+    pub fn reload_command(&mut self, command: Option<String>) {
+        if let Some(command) = command {
+            self.config.default_command = Some(command);
+        } else if self.config.default_command.is_none() {
+            self.status_message = Some("No command to reload, use reload(<command>)".to_string());
+            return;
+        }
+        if self.config.source == Source::Command {
+            self.pending_select = self.selection_candidates();
+        } else {
+            self.overlay_stash = None;
+            self.config.source = Source::Command;
+            self.pending_select.clear();
+        }
+        self.load_command();
+    }
+
+    /// Start searching the files under `current_path` for the filter text,
+    /// listing the matches as they are found (see `poll_background`). A
+    /// search still going for the previous text is stopped.
+    pub fn load_grep(&mut self) {
+        self.items.clear();
+        let token = self.listing_cancel.renew();
+        if self.filter.is_empty() {
+            self.listing_rx = mpsc::channel().1;
+            self.loading = None;
+        } else {
+            let use_rg = self.tools.is_available(Tool::Rg);
+            let gitignore = !self.config.no_ignore;
+            self.listing_rx =
+                listing::spawn_grep(self.filter.clone(), self.current_path.clone(), use_rg, gitignore, token);
+            self.loading = Some(Instant::now());
+        }
+        self.update_filter();
+    }
+
+    /// Switch between browsing names and searching file contents, keeping
+    /// the filter text.
+    pub fn toggle_grep(&mut self) {
+        match self.config.source {
+            Source::Grep => {
+                self.config.source = Source::Browse;
+                self.load_directory();
+            }
+            Source::Browse => {
+                self.config.source = Source::Grep;
+                self.load_grep();
+            }
+            _ => self.status_message = Some("Content search only applies when browsing".to_string()),
+        }
+    }
+
+    /// Leave a recursive listing or content search for browsing the
+    /// directory holding the selected result, with the result selected.
+    pub fn open_containing_dir(&mut self) {
+        let searching = self.config.source == Source::Grep
+            || (self.config.source == Source::Browse && self.config.recursive);
+        if !searching {
+            self.status_message = Some("Only recursive listings and content search have results to open".to_string());
+            return;
+        }
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        self.config.source = Source::Browse;
+        self.config.recursive = false;
+        self.current_path = dir.to_path_buf();
+        self.filter.clear();
+        self.load_directory();
+        self.select_path(&path);
+    }
+
+    /// Add entries read by a directory listing or command, keeping the
+    /// selection if the user has moved it and otherwise staying on the top
+    /// entry (or the one waiting to be selected).
+    fn add_listed(&mut self, entries: Vec<Entry>) {
+        let moved = self.list_state.selected().is_some_and(|selected| selected > 0);
+        let wanted = match self.selected_entry() {
+            Some(entry) if moved => vec![entry.path.clone()],
+            _ => self.pending_select.clone(),
+        };
+        self.items.extend(entries);
+        // A walk is left in the order it goes; resorting a huge tree after
+        // every batch would cost more than reading it
+        if self.config.source == Source::Browse && !self.config.recursive {
+            sort_listing(&mut self.items);
+        }
+        if self.config.source == Source::Todos {
+            sort_todos(&mut self.items, &self.config.todo_tags);
+        }
+        self.update_filter();
+        if self.restore_selection(&wanted) {
+            self.load_preview();
+        }
+    }
+
+    /// What is still going on in the background, or None once everything
+    /// has settled.
+    pub fn activity(&self) -> Option<String> {
+        let reading = format!("reading, {} entries", self.items.len());
+        match (self.loading.is_some(), self.pool.pending()) {
+            (false, 0) => None,
+            (true, 0) => Some(reading),
+            (false, tasks) => Some(format!("{} tasks", tasks)),
+            (true, tasks) => Some(format!("{}, {} tasks", reading, tasks)),
+        }
+    }
+
+    /// Stop a directory listing that hasn't finished, going back to the
+    /// directory listed before it, and a running paste.
+    pub fn cancel_loading(&mut self) {
+        if self.paste_progress.is_some() {
+            // The paste reports where it stopped once it has
+            self.paste_cancel.renew();
+        }
+        if self.loading.take().is_none() {
+            return;
+        }
+        self.listing_cancel.renew();
+        self.pending_select.clear();
+        let abandoned = self.current_path.clone();
+        match self.listed_path.clone() {
+            Some(previous) if previous != abandoned => {
+                self.current_path = previous;
+                self.filter.clear();
+                self.load_directory();
+                self.select_path(&abandoned);
+            }
+            // Nothing to go back to: keep what was read so far
+            _ => {}
+        }
+        self.status_message = Some(format!("Stopped reading {}", abandoned.display()));
+    }
+
+    fn update_filter(&mut self) {
+        self.filtered_items.clear();
+        self.query = Query::parse(&self.filter);
+        let root = self.project_root();
+        self.pinned = (0..self.items.len())
+            .filter(|&i| self.pins.is_pinned(&root, &self.items[i].path))
+            .collect();
+        
+        let scoring = Instant::now();
+        let by_date = self.config.sort == SortOrder::Taken || !self.query.taken.is_empty();
+        if by_date {
+            self.request_capture_dates();
+        }
+        
+        let browsing_without_hidden = self.config.source == Source::Browse && !self.config.hidden;
+        for (i, entry) in self.items.iter().enumerate() {
+            if self.dismissed.contains(&entry.path) {
+                continue;
+            }
+            if browsing_without_hidden && is_hidden(&entry.path) {
+                continue;
+            }
+            // Content search results already contain the filter text, and
+            // a live command's output is what it made of it
+            if self.config.source == Source::Grep || self.live() {
+                self.filtered_items.push((i, 0, Vec::new()));
+                continue;
+            }
+            if !self.query.taken.is_empty() {
+                // Photos whose date isn't known (yet) are left out
+                let date = self.capture_dates.get(&entry.path).cloned().flatten();
+                if !date.is_some_and(|date| self.query.taken.iter().all(|filter| filter.matches(&date))) {
+                    continue;
+                }
+            }
+            if !self.query.tags.is_empty() {
+                let tags = self.tags.get(&entry.path);
+                if !self.query.tags.iter().all(|tag| tags.contains(tag)) {
+                    continue;
+                }
+            }
+            // Notes are searchable along with the name
+            let text = match (entry.match_text(), self.notes.get(&entry.path)) {
+                (Some(text), Some(note)) => Some(format!("{} {}", text, note)),
+                (text, _) => text,
+            };
+            if let Some(text) = text
+                && let Some(fuzzy_match) = fuzzy_match(&self.query.pattern, &text)
+            {
+                // Frecent directories stay in frecency order, as in zoxide
+                let score = match self.config.source {
+                    Source::Jump => 0,
+                    _ => fuzzy_match.score,
+                };
+                // Matches in `--nth` fields have no place in the label
+                let matched = if entry.search.is_some() { Vec::new() } else { fuzzy_match.matched_indices };
+                self.filtered_items.push((i, score, matched));
+            }
+        }
+        
+        self.timings.scoring = scoring.elapsed();
+        self.timings.entries = self.items.len();
+        
+        // Pinned entries first, then by score (higher is better), or by
+        // capture date with undated entries last
+        let sorting = Instant::now();
+        if self.config.sort == SortOrder::Taken {
+            self.filtered_items.sort_by_key(|&(i, score, _)| {
+                let date = self.capture_dates.get(&self.items[i].path).and_then(Option::as_deref);
+                (!self.pinned.contains(&i), date.is_none(), date, std::cmp::Reverse(score))
+            });
+        } else {
+            self.filtered_items
+                .sort_by_key(|&(i, score, _)| (!self.pinned.contains(&i), std::cmp::Reverse(score)));
+        }
+        if self.grouping() {
+            self.group_results();
+        }
+        self.timings.sorting = sorting.elapsed();
+        
+        // Reset selection to first item
+        if self.filtered_items.is_empty() {
+            self.list_state.select(None);
+        } else {
+            self.list_state.select(Some(0));
+        }
+        self.load_preview();
+    }
+
+    /// Whether recursive results are clustered under directory headers.
+    pub fn grouping(&self) -> bool {
+        self.config.group && self.config.recursive && self.config.source == Source::Browse
+    }
+
+    /// The directory `entry` is grouped under, relative to `current_path`.
+    pub fn group_of(&self, entry: &Entry) -> PathBuf {
+        entry
+            .path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&self.current_path).ok())
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    /// The group whose header is drawn above the entry at `position` of the
+    /// filtered list, if it is the first of its group.
+    pub fn group_header(&self, position: usize) -> Option<PathBuf> {
+        if !self.grouping() {
+            return None;
+        }
+        let group = self.group_of(&self.items[self.filtered_items.get(position)?.0]);
+        let previous = position
+            .checked_sub(1)
+            .and_then(|previous| self.filtered_items.get(previous))
+            .map(|&(i, _, _)| self.group_of(&self.items[i]));
+        (previous.as_ref() != Some(&group)).then_some(group)
+    }
+
+    /// Cluster the sorted results by directory, groups in the order of
+    /// their best entry, and drop all but the best entry of collapsed ones.
+    fn group_results(&mut self) {
+        let groups: Vec<PathBuf> =
+            self.filtered_items.iter().map(|&(i, _, _)| self.group_of(&self.items[i])).collect();
+        let mut order = HashMap::new();
+        self.group_sizes.clear();
+        for group in &groups {
+            let next = order.len();
+            order.entry(group.clone()).or_insert(next);
+            *self.group_sizes.entry(group.clone()).or_insert(0) += 1;
+        }
+        let mut grouped: Vec<_> =
+            groups.iter().map(|group| order[group]).zip(self.filtered_items.drain(..)).collect();
+        grouped.sort_by_key(|&(group, _)| group);
+        let mut shown = HashSet::new();
+        self.filtered_items = grouped
+            .into_iter()
+            .filter(|&(group, (i, _, _))| !self.collapsed.contains(&self.group_of(&self.items[i])) || shown.insert(group))
+            .map(|(_, item)| item)
+            .collect();
+    }
+
+    /// Collapse the group of the selected entry to its best entry, or
+    /// expand it again.
+    pub fn toggle_group(&mut self) {
+        if !self.grouping() {
+            self.status_message = Some("Groups only apply to recursive listings (--group)".to_string());
+            return;
+        }
+        let Some(group) = self.selected_entry().map(|entry| self.group_of(entry)) else {
+            return;
+        };
+        if !self.collapsed.remove(&group) {
+            self.collapsed.insert(group.clone());
+        }
+        self.update_filter();
+        let position = self
+            .filtered_items
+            .iter()
+            .position(|&(i, _, _)| self.group_of(&self.items[i]) == group);
+        if let Some(position) = position {
+            self.list_state.select(Some(position));
+            self.load_preview();
+        }
+    }
+
+    /// Collapse every group, or expand them all if any is collapsed.
+    pub fn toggle_all_groups(&mut self) {
+        if !self.grouping() {
+            self.status_message = Some("Groups only apply to recursive listings (--group)".to_string());
+            return;
+        }
+        if self.collapsed.is_empty() {
+            self.collapsed = self.group_sizes.keys().cloned().collect();
+        } else {
+            self.collapsed.clear();
+        }
+        self.refilter();
+    }
+
+    pub fn next(&mut self) {
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i >= self.filtered_items.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        if !self.filtered_items.is_empty() {
+            self.list_state.select(Some(i));
+            self.load_preview();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.filtered_items.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        if !self.filtered_items.is_empty() {
+            self.list_state.select(Some(i));
+            self.load_preview();
+        }
+    }
+
+    /// Scroll the preview by `delta` lines, down when positive.
+    pub fn scroll_preview(&mut self, delta: isize) {
+        if self.preview_content.is_some() {
+            self.preview_scroll = self.preview_scroll.saturating_add_signed(delta);
+        }
+    }
+
+    pub fn enter_selected(&mut self) -> Result<Option<Selection>, Box<dyn Error>> {
+        if let Some(entry) = self.selected_entry().cloned() {
+            let path = entry.path;
+            if matches!(self.config.source, Source::Bookmarks | Source::Jump) {
+                self.browse(path);
+                return Ok(None);
+            }
+            // Entering a mountpoint switches to browsing it
+            if self.config.source == Source::Mounts {
+                if path.is_dir() {
+                    self.config.source = Source::Browse;
+                    self.current_path = path;
+                    self.filter.clear();
+                    self.load_directory();
+                } else {
+                    self.status_message = Some("Not mounted, press m to mount".to_string());
+                }
+                return Ok(None);
+            }
+            // Removed since it was listed: reread what is left
+            if entry.label.is_none() && self.config.source == Source::Browse && !path.exists() {
+                if self.current_path.exists() {
+                    self.status_message = Some(format!("{} is gone", safe_filename_to_string(&path)));
+                } else {
+                    self.move_to_existing_ancestor("is gone");
+                }
+                self.load_directory();
+                return Ok(None);
+            }
+            // Labeled entries (diagnostics, trash, ...) are always accepted as-is
+            if path.is_dir() && entry.label.is_none() {
+                // Navigate to directory
+                if safe_filename_to_string(&path) == ".." {
+                    // Go to parent directory
+                    if let Some(parent) = self.current_path.parent() {
+                        self.current_path = parent.to_path_buf();
+                    }
+                } else {
+                    // Go to subdirectory
+                    self.current_path = path;
+                }
+                self.filter.clear(); // Clear filter when navigating
+                self.load_directory();
+                return Ok(None);
+            } else {
+                if !self.confirm_size(&path) {
+                    return Ok(None);
+                }
+                // Return the selected file
+                return Ok(Some(Selection {
+                    path,
+                    line: entry.line.or(self.query.line),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether `path` may be accepted: it is within `--warn-size`, or this
+    /// is the second try in a row. The first try only warns.
+    fn confirm_size(&mut self, path: &Path) -> bool {
+        let size = fs::metadata(path).ok().filter(|meta| meta.is_file()).map(|meta| meta.len());
+        let Some(size) = size.filter(|&size| self.config.warn_size.is_some_and(|limit| size > limit)) else {
+            return true;
+        };
+        if self.pending_accept.as_deref() == Some(path) {
+            self.pending_accept = None;
+            return true;
+        }
+        self.status_message = Some(format!(
+            "{} is {}, press Enter again to open it anyway",
+            safe_filename_to_string(path),
+            preview::human_size(size)
+        ));
+        self.pending_accept = Some(path.to_path_buf());
+        false
+    }
+
+    /// Answer the file chooser request with the marked entries (if several
+    /// may be chosen) or `accepted`, falling back to the selected entry, or
+    /// for a directory chooser, the current directory. Entries of the wrong
+    /// kind are refused. Saving asks for a name unless an existing file was
+    /// accepted. Returns whether the request was answered.
+    pub fn answer_chooser(&mut self, accepted: Option<PathBuf>) -> io::Result<bool> {
+        let Some(request) = self.config.chooser.clone() else {
+            return Ok(false);
+        };
+        if request.save {
+            let Some(path) = accepted else {
+                self.save_name = request.suggested_name();
+                self.mode = Mode::SaveAs;
+                return Ok(false);
+            };
+            request.respond(&[path])?;
+            return Ok(true);
+        }
+
+        let explicit = accepted.is_some();
+        let mut paths: Vec<PathBuf> = if request.multiple && !self.marked.is_empty() {
+            self.marked.iter().cloned().collect()
+        } else {
+            accepted
+                .or_else(|| self.selected_entry().map(|entry| entry.path.clone()))
+                .filter(|path| path.file_name().is_some_and(|name| name != ".."))
+                .into_iter()
+                .collect()
+        };
+        paths.retain(|path| request.accepts(path));
+        if paths.is_empty() && request.directory && !explicit {
+            paths.push(self.current_path.clone());
+        }
+        if paths.is_empty() {
+            self.status_message = Some(if request.directory {
+                "Choose a directory: c picks the selected or current one".to_string()
+            } else {
+                "Choose a file".to_string()
+            });
+            return Ok(false);
+        }
+        if !request.multiple {
+            paths.truncate(1);
+        }
+        request.respond(&paths)?;
+        Ok(true)
+    }
+
+    /// Answer a save dialog with the typed name in the current directory.
+    pub fn save_as(&mut self) -> io::Result<bool> {
+        self.mode = Mode::Normal;
+        let name = std::mem::take(&mut self.save_name);
+        let Some(request) = self.config.chooser.clone() else {
+            return Ok(false);
+        };
+        if name.trim().is_empty() {
+            return Ok(false);
+        }
+        request.respond(&[self.current_path.join(name.trim())])?;
+        Ok(true)
+    }
+
+    /// Leave the selected entry out of the list for the rest of the
+    /// session, selecting its neighbour. Nothing on disk changes.
+    pub fn dismiss_selected(&mut self) {
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        self.dismissed.insert(path);
+        self.refilter();
+    }
+
+    /// Bring back every dismissed entry.
+    pub fn undismiss_all(&mut self) {
+        if self.dismissed.is_empty() {
+            return;
+        }
+        self.status_message = Some(format!("Showing {} hidden entries again", self.dismissed.len()));
+        self.dismissed.clear();
+        self.refilter();
+    }
+
+    /// Pin or unpin the selected entry for the current project, keeping it
+    /// selected as it moves.
+    pub fn toggle_pin_selected(&mut self) {
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        let root = self.project_root();
+        self.status_message = Some(match self.pins.toggle(&root, &path) {
+            Ok(true) => format!("Pinned {}", safe_filename_to_string(&path)),
+            Ok(false) => format!("Unpinned {}", safe_filename_to_string(&path)),
+            Err(err) => format!("Pin failed: {}", err),
+        });
+        self.refilter();
+    }
+
+    /// Start editing the note on the selected entry.
+    pub fn start_annotating(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        self.note_input = self.notes.get(&entry.path).unwrap_or_default().to_string();
+        self.mode = Mode::Annotate;
+    }
+
+    /// Start editing the name of the selected entry.
+    pub fn start_renaming(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let name = entry.path.file_name().map(|name| name.to_string_lossy().into_owned());
+        match name {
+            Some(name) if entry.label.is_none() && name != ".." => {
+                self.renaming = Some(entry.path.clone());
+                self.rename_input = name;
+                self.mode = Mode::Rename;
+            }
+            _ => self.status_message = Some("Only files and directories can be renamed".to_string()),
+        }
+    }
+
+    /// Rename the entry being renamed to the typed name, in the same
+    /// directory, and select it under its new name.
+    pub fn rename_selected(&mut self) {
+        self.mode = Mode::Normal;
+        let name = std::mem::take(&mut self.rename_input);
+        let Some(from) = self.renaming.take() else {
+            return;
+        };
+        if name.is_empty() || from.file_name().is_some_and(|old| old == name.as_str()) {
+            return;
+        }
+        let to = from.with_file_name(&name);
+        if to.symlink_metadata().is_ok() {
+            self.status_message = Some(format!("{} already exists", name));
+            return;
+        }
+        if let Err(err) = fs::rename(&from, &to) {
+            self.status_message = Some(format!("Rename failed: {}", err));
+            return;
+        }
+        let _ = audit::record("rename", &from, Some(&to));
+        self.status_message = Some(format!("Renamed {} to {}", safe_filename_to_string(&from), name));
+        self.load_directory();
+        self.select_path(&to);
+    }
+
+    /// Start typing the path of a new file, or with `dir` a new directory.
+    pub fn start_creating(&mut self, dir: bool) {
+        self.creating_dir = dir;
+        self.create_input.clear();
+        self.mode = Mode::Create;
+    }
+
+    /// Create the typed file or directory below `current_path`, with any
+    /// directories leading to it, and select it (or the directory holding
+    /// it here).
+    pub fn create_entry(&mut self) {
+        self.mode = Mode::Normal;
+        let typed = std::mem::take(&mut self.create_input);
+        let name = typed.trim_end_matches('/');
+        if name.is_empty() {
+            return;
+        }
+        let path = self.current_path.join(name);
+        if path.symlink_metadata().is_ok() {
+            self.status_message = Some(format!("{} already exists", name));
+            return;
+        }
+        let created = if self.creating_dir {
+            fs::create_dir_all(&path)
+        } else {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::OpenOptions::new().write(true).create_new(true).open(&path).map(drop))
+        };
+        if let Err(err) = created {
+            self.status_message = Some(format!("Cannot create {}: {}", name, err));
+            return;
+        }
+        let action = if self.creating_dir { "mkdir" } else { "create" };
+        let _ = audit::record(action, &path, None);
+        self.status_message = Some(format!("Created {}", name));
+        // Outside a recursive listing only the first component is listed
+        let select = match path.strip_prefix(&self.current_path).ok().and_then(|rest| rest.components().next()) {
+            Some(first) if !self.config.recursive => self.current_path.join(first),
+            _ => path,
+        };
+        self.filter.clear();
+        self.load_directory();
+        self.select_path(&select);
+    }
+
+    /// Save the note being edited; an empty note removes it.
+    pub fn save_note(&mut self) {
+        self.mode = Mode::Normal;
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        let note = std::mem::take(&mut self.note_input);
+        if let Err(err) = self.notes.set(&path, &note) {
+            self.status_message = Some(format!("Saving note failed: {}", err));
+        }
+        self.refilter();
+    }
+
+    /// Add the typed tag to the selected entry, or remove it if the entry
+    /// already has it.
+    pub fn toggle_tag_selected(&mut self) {
+        self.mode = Mode::Normal;
+        let tag = std::mem::take(&mut self.tag_input);
+        let Some(path) = self.selected_entry().map(|entry| entry.path.clone()) else {
+            return;
+        };
+        if tag.is_empty() {
+            return;
+        }
+        self.status_message = Some(match self.tags.toggle(&path, &tag) {
+            Ok(true) => format!("Tagged {} with {}", safe_filename_to_string(&path), tag),
+            Ok(false) => format!("Removed tag {} from {}", tag, safe_filename_to_string(&path)),
+            Err(err) => format!("Tagging failed: {}", err),
+        });
+        self.refilter();
+    }
+
+    /// Copy the tree under the current directory to the clipboard, limited
+    /// to names matching the filter. Without a clipboard program the tree is
+    /// written to `tree.txt` in the state directory instead.
+    pub fn export_tree(&mut self) {
+        let pattern = self.query.pattern.clone();
+        let text = tree::render(&self.current_path, &|name| fuzzy_match(&pattern, name).is_some());
+        let entries = text.lines().count() - 1;
+        self.status_message = Some(match clipboard::copy(&self.tools, &text) {
+            Ok(program) => format!("Copied tree of {} entries ({})", entries, program),
+            Err(_) => match history::state_dir().map(|dir| dir.join("tree.txt")) {
+                Some(file) => match fs::create_dir_all(file.parent().unwrap_or(&file))
+                    .and_then(|_| fs::write(&file, &text))
+                {
+                    Ok(()) => format!("No clipboard, wrote tree to {}", file.display()),
+                    Err(err) => format!("Export failed: {}", err),
+                },
+                None => "Export failed: no clipboard or state directory".to_string(),
+            },
+        });
+    }
+
+    /// Select the entry for `path`. While the directory is still being
+    /// read, an entry not listed yet is selected once it arrives.
+    pub fn select_path(&mut self, path: &Path) {
+        let position = self
+            .filtered_items
+            .iter()
+            .position(|&(i, _, _)| self.items[i].path == path);
+        if let Some(position) = position {
+            self.list_state.select(Some(position));
+            self.pending_select.clear();
+        } else if self.loading.is_some() {
+            self.pending_select = vec![path.to_path_buf()];
+        }
+    }
+
+    /// The currently highlighted entry, if any.
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        let selected = self.list_state.selected()?;
+        let &(item_index, _, _) = self.filtered_items.get(selected)?;
+        self.items.get(item_index)
+    }
+
+    pub fn add_char_to_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.filter_changed();
+    }
+
+    pub fn remove_char_from_filter(&mut self) {
+        self.filter.pop();
+        self.filter_changed();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filter_changed();
+    }
+
+    /// Whether the listing command is run for the filter text (`--live`)
+    /// rather than its output matched against it.
+    pub fn live(&self) -> bool {
+        self.config.live && self.config.source == Source::Command
+    }
+
+    /// Journal the session state once it has been changed for a while.
+    fn check_journal_changed(&mut self) {
+        if self.journal_changed.is_some_and(|changed| changed.elapsed() >= JOURNAL_DELAY) {
+            self.journal_changed = None;
+            let _ = journal::append(
+                self.current_path.clone(),
+                self.filter.clone(),
+                self.selected_entry().map(|entry| entry.path.clone()),
+            );
+        }
+    }
+
+    /// Run the `--live` command for the filter once typing has paused.
+    fn check_live_changed(&mut self) -> bool {
+        match self.live_changed {
+            Some(changed) if changed.elapsed() >= LIVE_DELAY => {
+                self.live_changed = None;
+                self.reload_command(None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Apply an edited filter: matched against the list, searched for anew
+    /// in content search, or with `--live` run through the command.
+    fn filter_changed(&mut self) {
+        if self.live() {
+            self.live_changed = Some(Instant::now());
+        } else if self.config.source == Source::Grep {
+            self.load_grep();
+        } else {
+            self.update_filter();
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    pub fn go_to_top(&mut self) {
+        if !self.filtered_items.is_empty() {
+            self.list_state.select(Some(0));
+            self.load_preview();
+        }
+    }
+
+    pub fn go_to_bottom(&mut self) {
+        if !self.filtered_items.is_empty() {
+            self.list_state.select(Some(self.filtered_items.len() - 1));
+            self.load_preview();
+        }
+    }
+
+    pub fn load_preview(&mut self) {
+        // Every selection, filter and directory change ends up here, which
+        // makes it the one place to note the session state has to be
+        // journaled
+        self.journal_changed.get_or_insert_with(Instant::now);
+
+        self.preview_scroll = 0;
+        self.preview_levels.clear();
+        self.folds.clear();
+        self.directory_sample = None;
+        self.preview_stamp = None;
+        self.preview_checksum = None;
+        self.preview_changed = false;
+        // Invalidate any background work started for the previous preview
+        let token = self.preview_cancel.renew();
+        let entry = self.selected_entry().cloned();
+        let is_dir = entry.as_ref().is_some_and(Entry::is_dir);
+        self.preview_path = entry.as_ref().map(|entry| entry.path.clone());
+        self.preview_focus = entry.and_then(|entry| entry.line).or(self.query.line);
+        self.preview_cursor = self.preview_focus.unwrap_or(1).saturating_sub(1);
+
+        let path = match &self.preview_path {
+            Some(path) if path.file_name().is_some_and(|name| name != "..") => path.clone(),
+            _ => {
+                self.preview_content = None;
+                self.preview_focus = None;
+                self.preview_focused = false;
+                return;
+            }
+        };
+
+        if self.config.source == Source::Clipboard {
+            self.preview_content = self.selected_clip().cloned();
+            return;
+        }
+        if self.config.source == Source::Man {
+            let width = match self.pane_areas.preview.width {
+                0 => 80,
+                width => width,
+            };
+            // `man` takes a while, so the page arrives with command previews
+            self.preview_content = Some("Loading…".to_string());
+            manpages::spawn_render(&self.pool, path, width, token, self.command_preview_tx.clone());
+            return;
+        }
+        if is_dir && self.network {
+            self.preview_content = Some("Directory previews are off on network mounts (--network off)".to_string());
+            return;
+        }
+        // The built-in preview shows until the command's output replaces
+        // it, and stays if the command fails
+        let line = self.printed_line(&path);
+        if let Some(command) = &self.config.preview_command
+            && Cipher::of(&path).is_none()
+        {
+            let line = line.clone().unwrap_or_else(|| path.to_string_lossy().into_owned());
+            let command = fields::fill(command, Some(&line), &self.config.delimiter, &self.filter);
+            let (dir, limit) = (self.current_path.clone(), self.config.preview_limit);
+            preview::spawn_command(command, dir, path.clone(), limit, token.clone(), self.command_preview_tx.clone());
+        }
+        // A line that names no file previews as itself
+        if let Some(line) = line
+            && fs::symlink_metadata(&path).is_err()
+        {
+            // Tabs would leave the rest of the row unpainted
+            self.preview_content = Some(line.replace('\t', "    "));
+            return;
+        }
+        if is_dir {
+            self.preview_content = None;
+            if let Some(sample) = DirectorySample::read(&path, DIRECTORY_SAMPLE_LIMIT) {
+                if sample.truncated {
+                    preview::spawn_entry_count(&self.pool, path, token, self.count_tx.clone());
+                }
+                self.preview_content = Some(sample.render(None));
+                self.directory_sample = Some(sample);
+            }
+            return;
+        }
+
+        if let Some(cipher) = Cipher::of(&path) {
+            self.preview_content = Some(format!("Encrypted with {}: press U to decrypt", cipher.name()));
+            return;
+        }
+        if self.graphics.is_some() && thumbnail::is_image(&path) {
+            self.request_thumbnail(&path);
+        }
+        // Only both ends of a huge file are read, where the line to jump to
+        // is nowhere to be found
+        if let Some(line) = self.preview_focus
+            && !self.uses_bat()
+            && shows_both_ends(&path, self.config.preview_limit, true)
+        {
+            self.preview_focus = None;
+            self.preview_cursor = 0;
+            self.status_message =
+                Some(format!("{} is too big to show line {}", safe_filename_to_string(&path), line));
+        }
+        let focused = self.preview_focus.is_some();
+        if !self.network {
+            self.preview_stamp = stamp(&path);
+            preview::spawn_checksum(&self.pool, path.clone(), self.config.preview_limit, token, self.checksum_tx.clone());
+        }
+        self.preview_content = match self.prefetched.remove(&(path.clone(), focused)) {
+            Some(prefetched) if !self.uses_bat() && prefetched.is_current(&path) => Some(prefetched.content),
+            _ => self
+                .read_with_bat(&path)
+                .or_else(|| read_file_content(&path, self.config.preview_limit, focused)),
+        };
+        if let Some(content) = &self.preview_content {
+            self.preview_levels = fold::indent_levels(content);
+        }
+        // The pane height is only known at draw time, so centering on the
+        // line of interest happens there
+        self.center_preview = self.preview_focus.is_some();
+        self.prefetch_previews();
+    }
+
+    /// Read the previews of the files just above and below the selection
+    /// on the worker pool, so moving through the list doesn't wait on disk.
+    /// Only built-in file previews are read ahead, and never on network
+    /// mounts.
+    fn prefetch_previews(&mut self) {
+        let token = self.prefetch_cancel.renew();
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        if self.network || self.uses_bat() || matches!(self.config.source, Source::Man | Source::Clipboard) {
+            self.prefetched.clear();
+            return;
+        }
+        let first = selected.saturating_sub(PREFETCH_ENTRIES);
+        let last = (selected + PREFETCH_ENTRIES).min(self.filtered_items.len().saturating_sub(1));
+        let wanted: Vec<(PathBuf, bool)> = (first..=last)
+            .filter(|&i| i != selected)
+            .filter_map(|i| self.items.get(self.filtered_items.get(i)?.0))
+            .filter(|entry| !entry.is_dir() && Cipher::of(&entry.path).is_none())
+            .map(|entry| (entry.path.clone(), entry.line.or(self.query.line).is_some()))
+            .collect();
+        self.prefetched.retain(|key, _| wanted.contains(key));
+        let limit = self.config.preview_limit;
+        for key in wanted {
+            if self.prefetched.contains_key(&key) {
+                continue;
+            }
+            let tx = self.prefetch_tx.clone();
+            self.pool.spawn(Priority::Low, token.clone(), move |token| {
+                let (path, focused) = &key;
+                let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+                let content = read_file_content(path, limit, *focused);
+                if let Some(content) = content
+                    && !token.is_cancelled()
+                {
+                    let _ = tx.send((key, Prefetched { modified, content }));
+                }
+            });
+        }
+    }
+
+    /// Read the previewed file again after it changed on disk, keeping the
+    /// scroll position.
+    pub fn reload_preview(&mut self) {
+        let scroll = self.preview_scroll;
+        self.load_preview();
+        self.preview_scroll = scroll;
+        self.center_preview = false;
+    }
+
+    /// Read the browsed directory again once the watcher has seen entries
+    /// come or go and things have been quiet for `REFRESH_DELAY`.
+    fn check_dir_changed(&mut self) {
+        if self.watcher.as_ref().is_some_and(DirWatcher::changed) {
+            self.dir_changed = Some(Instant::now());
+        }
+        // Content search and other lists keep the watcher for coming back
+        if self.config.source != Source::Browse || self.config.recursive {
+            return;
+        }
+        let Some(changed) = self.dir_changed else {
+            return;
+        };
+        if changed.elapsed() < REFRESH_DELAY || self.loading.is_some() || self.refresh_rx.is_some() {
+            return;
+        }
+        self.dir_changed = None;
+        self.refreshed.clear();
+        let token = self.listing_cancel.current();
+        self.refresh_rx = Some(listing::spawn(self.current_path.clone(), !self.config.no_ignore, token));
+    }
+
+    /// Swap in the entries of a finished refresh, keeping the filter and
+    /// the selection.
+    fn apply_refresh(&mut self) {
+        let mut items = std::mem::take(&mut self.refreshed);
+        if self.current_path.parent().is_some() {
+            items.push(Entry::from_path(self.current_path.join("..")));
+        }
+        sort_listing(&mut items);
+        self.mtimes.clear();
+        self.keep_selection(|app| {
+            app.items = items;
+            app.update_filter();
+        });
+    }
+
+    /// Notice the previewed file changing on disk: a cheap `stat` every
+    /// `CHANGE_CHECK_INTERVAL`, and a checksum once that looks different,
+    /// so touching a file doesn't count as a change.
+    fn check_preview_changed(&mut self) {
+        if self.change_checked.elapsed() < CHANGE_CHECK_INTERVAL || self.preview_changed {
+            return;
+        }
+        self.change_checked = Instant::now();
+        let (Some(path), Some(read)) = (&self.preview_path, self.preview_stamp) else {
+            return;
+        };
+        let Some(now) = stamp(path) else {
+            return;
+        };
+        if now != read {
+            self.preview_stamp = Some(now);
+            let token = self.preview_cancel.current();
+            preview::spawn_checksum(&self.pool, path.clone(), self.config.preview_limit, token, self.checksum_tx.clone());
+        }
+    }
+
+    /// Decrypt the selected gpg or age file into the preview. The plaintext
+    /// only ever lives in memory, and is gone once the preview changes.
+    pub fn decrypt_selected(&mut self) {
+        let Some(path) = self.preview_path.clone() else {
+            return;
+        };
+        let Some(cipher) = Cipher::of(&path) else {
+            self.status_message = Some("Not an encrypted file (.gpg or .age)".to_string());
+            return;
+        };
+        if !self.config.decrypt {
+            self.status_message = Some("Decrypting is off, start with --decrypt to allow it".to_string());
+            return;
+        }
+        self.preview_content = Some(format!("Decrypting with {}...", cipher.name()));
+        let token = self.preview_cancel.renew();
+        let identity = self.config.age_identity.clone();
+        crypt::spawn_decrypt(&self.pool, path, cipher, identity, token, self.decrypt_tx.clone());
+    }
+
+    /// Show or hide the preview pane.
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+        self.preview_focused = false;
+    }
+
+    /// Start reading the excerpt shown under `entry` in the list, unless it
+    /// has been read already. It arrives through `poll_background`.
+    pub fn request_excerpt(&mut self, entry: &Entry) {
+        let key = (entry.path.clone(), entry.line);
+        if self.excerpts.contains_key(&key) {
+            return;
+        }
+        self.excerpts.insert(key.clone(), None);
+        if self.network || entry.is_dir() || has_binary_extension(&entry.path) {
+            return;
+        }
+        let tx = self.excerpt_tx.clone();
+        // Excerpts are speculative, so they wait behind preview work
+        self.pool.spawn(Priority::Low, self.listing_cancel.current(), move |_| {
+            if let Some(excerpt) = preview::excerpt(&key.0, key.1) {
+                let _ = tx.send((key, excerpt));
+            }
+        });
+    }
+
+    /// ImageMagick's command, `magick` or (version 6) `convert`.
+    pub fn magick(&self) -> &'static str {
+        match self.tools.is_available(Tool::Magick) {
+            true => Tool::Magick.binary(),
+            false => Tool::Convert.binary(),
+        }
+    }
+
+    /// Where the previewed image goes in the preview pane's `area`, below
+    /// the lines describing it, once its thumbnail is ready.
+    pub fn preview_placement(&mut self, area: Rect) -> Option<Placement> {
+        let path = self.preview_path.clone().filter(|path| thumbnail::is_image(path))?;
+        if self.graphics.is_none() || self.plan.is_some() || self.preview_scroll > 0 {
+            return None;
+        }
+        let thumbnail = self.thumbnails.get(&path).cloned().flatten()?;
+        let pixels = self.image_dimensions(&thumbnail)?;
+        let text_rows = self.preview_content.as_deref().map_or(0, |content| content.lines().count() as u16 + 1);
+        let height = area.height.checked_sub(text_rows).filter(|&height| height > 0)?;
+        let (width, height) = gallery::fit(pixels, (area.width, height));
+        Some(Placement {
+            column: area.x,
+            row: area.y + text_rows,
+            width,
+            height,
+            thumbnail,
+        })
+    }
+
+    /// Start making a thumbnail for `image` unless one is ready or pending.
+    pub fn request_thumbnail(&mut self, image: &Path) {
+        if self.network || self.thumbnails.contains_key(image) {
+            return;
+        }
+        self.thumbnails.insert(image.to_path_buf(), None);
+        let program = self.magick();
+        let tx = self.thumbnail_tx.clone();
+        let image = image.to_path_buf();
+        self.pool.spawn(Priority::Low, self.listing_cancel.current(), move |_| {
+            if let Some(thumbnail) = thumbnail::generate(program, &image) {
+                let _ = tx.send((image, thumbnail));
+            }
+        });
+    }
+
+    /// Start reading the capture date of every photo in the listing that
+    /// hasn't been read yet.
+    fn request_capture_dates(&mut self) {
+        if self.network {
+            return;
+        }
+        for entry in &self.items {
+            if self.capture_dates.contains_key(&entry.path) || !imageinfo::has_exif(&entry.path) {
+                continue;
+            }
+            self.capture_dates.insert(entry.path.clone(), None);
+            let tx = self.capture_date_tx.clone();
+            let path = entry.path.clone();
+            self.pool.spawn(Priority::Low, self.listing_cancel.current(), move |_| {
+                if let Some(date) = imageinfo::capture_date(&path) {
+                    let _ = tx.send((path, date));
+                }
+            });
+        }
+    }
+
+    /// Filter and sort again, keeping the selected entry selected.
+    fn refilter(&mut self) {
+        self.keep_selection(App::update_filter);
+    }
+
+    /// Rebuild the list with `rebuild`, keeping the selected entry selected
+    /// if it is still listed, or else the nearest one that is.
+    fn keep_selection(&mut self, rebuild: impl FnOnce(&mut App)) {
+        let candidates = self.selection_candidates();
+        rebuild(self);
+        if self.restore_selection(&candidates) {
+            self.load_preview();
+        }
+    }
+
+    /// The selected entry followed by its neighbours, nearest first (the
+    /// one below before the one above), to restore the selection from after
+    /// the list changes.
+    fn selection_candidates(&self) -> Vec<PathBuf> {
+        let Some(selected) = self.list_state.selected() else {
+            return Vec::new();
+        };
+        let mut positions: Vec<usize> = (0..self.filtered_items.len()).collect();
+        positions.sort_by_key(|&position| (position.abs_diff(selected), position < selected));
+        positions
+            .into_iter()
+            .map(|position| self.items[self.filtered_items[position].0].path.clone())
+            .collect()
+    }
+
+    /// Select the first of `candidates` that is listed. Returns false if
+    /// none is, leaving the selection alone.
+    fn restore_selection(&mut self, candidates: &[PathBuf]) -> bool {
+        if candidates.is_empty() {
+            return false;
+        }
+        let positions: HashMap<&Path, usize> = self
+            .filtered_items
+            .iter()
+            .enumerate()
+            .map(|(position, &(i, _, _))| (self.items[i].path.as_path(), position))
+            .collect();
+        let Some(position) = candidates.iter().find_map(|path| positions.get(path.as_path()).copied()) else {
+            return false;
+        };
+        self.list_state.select(Some(position));
+        true
+    }
+
+    /// Switch between the list and the gallery grid.
+    pub fn toggle_gallery(&mut self) {
+        self.gallery = !self.gallery;
+        self.preview_focused = false;
+    }
+
+    /// Move the gallery selection by `delta` entries; a row is
+    /// `grid.columns` entries.
+    pub fn move_gallery(&mut self, delta: isize) {
+        if self.filtered_items.is_empty() {
+            return;
+        }
+        let selected = self.list_state.selected().unwrap_or(0);
+        let Some(target) = selected.checked_add_signed(delta) else {
+            return;
+        };
+        if target < self.filtered_items.len() {
+            self.list_state.select(Some(target));
+            self.load_preview();
+        }
+    }
+
+    /// When `path` was last modified, cached until the directory is
+    /// loaded again.
+    pub fn modified(&mut self, path: &Path) -> Option<SystemTime> {
+        *self
+            .mtimes
+            .entry(path.to_path_buf())
+            .or_insert_with(|| path.symlink_metadata().and_then(|meta| meta.modified()).ok())
+    }
+
+    /// Show or hide the modification age tint.
+    pub fn toggle_age_colors(&mut self) {
+        self.config.age_colors = !self.config.age_colors;
+    }
+
+    /// Pixel size of the image at `path`, cached.
+    pub fn image_dimensions(&mut self, path: &Path) -> Option<(u32, u32)> {
+        if self.network {
+            return None;
+        }
+        *self
+            .dimensions
+            .entry(path.to_path_buf())
+            .or_insert_with(|| imageinfo::dimensions(path))
+    }
+
+    /// Move keyboard focus between the list and the preview.
+    pub fn toggle_preview_focus(&mut self) {
+        self.preview_focused =
+            !self.preview_focused && self.show_preview && self.preview_content.is_some();
+    }
+
+    /// Move the preview cursor by `delta` visible lines, skipping folded
+    /// blocks.
+    pub fn move_preview_cursor(&mut self, delta: isize) {
+        let visible = fold::visible_lines(&self.preview_levels, &self.folds);
+        if visible.is_empty() {
+            return;
+        }
+        let row = self.cursor_row(&visible);
+        let row = row.saturating_add_signed(delta).min(visible.len() - 1);
+        self.preview_cursor = visible[row].0;
+        self.scroll_to_preview_row(row);
+    }
+
+    /// Fold the block at the cursor (or the one containing it), or unfold it
+    /// if it is folded.
+    pub fn toggle_fold(&mut self) {
+        if !self.folds.remove(&self.preview_cursor)
+            && let Some(start) = fold::enclosing_fold(&self.preview_levels, self.preview_cursor)
+        {
+            self.folds.insert(start);
+            self.preview_cursor = start;
+        }
+        self.move_preview_cursor(0);
+    }
+
+    /// Fold every block, for skimming the structure of a file, or unfold
+    /// everything if anything is folded.
+    pub fn toggle_all_folds(&mut self) {
+        if self.folds.is_empty() {
+            self.folds = fold::all_folds(&self.preview_levels);
+        } else {
+            self.folds.clear();
+        }
+        self.move_preview_cursor(0);
+    }
+
+    /// Navigate to the first existing path mentioned on the preview cursor's
+    /// line, resolved against the previewed file's directory, the current
+    /// directory and the project root. Files are selected in their
+    /// directory, at the mentioned line if there is one.
+    pub fn follow_path_under_cursor(&mut self) -> Result<(), Box<dyn Error>> {
+        let (Some(content), Some(previewed)) = (&self.preview_content, &self.preview_path) else {
+            return Ok(());
+        };
+        let line = ansi::strip(content)
+            .lines()
+            .nth(self.preview_cursor)
+            .unwrap_or_default()
+            .to_string();
+        let file_dir = previewed.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let root = self.project_root();
+        let bases = [file_dir.as_path(), self.current_path.as_path(), root.as_path()];
+        let Some((target, target_line)) = pathref::extract(&line)
+            .into_iter()
+            .find_map(|(path, line)| Some((pathref::resolve(&path, &bases)?, line)))
+        else {
+            self.status_message = Some("No existing path on this line".to_string());
+            return Ok(());
+        };
+
+        self.config.source = Source::Browse;
+        self.overlay_stash = None;
+        self.preview_focused = false;
+        self.filter.clear();
+        if target.is_dir() {
+            self.current_path = target;
+            self.load_directory();
+            return Ok(());
+        }
+        self.current_path = target.parent().unwrap_or(Path::new("/")).to_path_buf();
+        self.load_directory();
+        self.select_path(&target);
+        self.load_preview();
+        if let Some(line) = target_line {
+            self.preview_focus = Some(line);
+            self.preview_cursor = line.saturating_sub(1);
+            self.center_preview = true;
+        }
+        Ok(())
+    }
+
+    /// Row of the cursor among the visible preview lines. A cursor hidden
+    /// inside a fold moves to the fold's first line.
+    fn cursor_row(&mut self, visible: &[(usize, usize)]) -> usize {
+        let row = visible
+            .iter()
+            .rposition(|&(line, _)| line <= self.preview_cursor)
+            .unwrap_or(0);
+        self.preview_cursor = visible[row].0;
+        row
+    }
+
+    fn scroll_to_preview_row(&mut self, row: usize) {
+        let height = (self.pane_areas.preview.height as usize).max(1);
+        if row < self.preview_scroll {
+            self.preview_scroll = row;
+        } else if row >= self.preview_scroll + height {
+            self.preview_scroll = row + 1 - height;
+        }
+    }
+
+    /// Apply results from background work. Returns true if anything visible
+    /// changed and the UI should be redrawn.
+    pub fn poll_background(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((key, excerpt)) = self.excerpt_rx.try_recv() {
+            self.excerpts.insert(key, Some(excerpt));
+            changed = true;
+        }
+        while let Ok((image, thumbnail)) = self.thumbnail_rx.try_recv() {
+            self.thumbnails.insert(image, Some(thumbnail));
+            changed = true;
+        }
+        // Dates can change the order, so the list is rebuilt once per batch
+        let mut dated = false;
+        while let Ok(pasting) = self.paste_rx.try_recv() {
+            match pasting {
+                Pasting::Total(total) => self.paste_progress = Some((0, total)),
+                Pasting::Copied(bytes) => {
+                    if let Some((copied, _)) = &mut self.paste_progress {
+                        *copied += bytes;
+                    }
+                }
+                Pasting::Done(result) => {
+                    self.paste_progress = None;
+                    match result {
+                        Ok(pasted) => {
+                            self.status_message = Some(match pasted.len() {
+                                1 => "Pasted 1 entry".to_string(),
+                                count => format!("Pasted {} entries", count),
+                            });
+                            let here = pasted.first().and_then(|path| path.parent()) == Some(self.current_path.as_path());
+                            if self.config.source == Source::Browse && here {
+                                self.load_directory();
+                                self.select_path(&pasted[0]);
+                            }
+                        }
+                        Err(err) => {
+                            self.status_message = Some(format!("Paste stopped: {}", err));
+                            // Show whatever made it
+                            if self.config.source == Source::Browse {
+                                self.load_directory();
+                            }
+                        }
+                    }
+                }
+            }
+            changed = true;
+        }
+        while let Ok((path, size)) = self.size_rx.try_recv() {
+            self.marked_sizes.insert(path, Some(size));
+            changed = true;
+        }
+        while let Ok((photo, date)) = self.capture_date_rx.try_recv() {
+            self.capture_dates.insert(photo, Some(date));
+            dated = true;
+        }
+        if dated {
+            self.refilter();
+            changed = true;
+        }
+        let mut listed = Vec::new();
+        let mut finished = None;
+        while let Ok(listing) = self.listing_rx.try_recv() {
+            match listing {
+                Listing::Entries(paths) => listed
+                    .extend(paths.into_iter().map(|(path, file_type)| Entry::listed(path, file_type))),
+                Listing::Lines(lines) => listed.extend(
+                    lines
+                        .into_iter()
+                        .map(|line| Entry::labeled(self.current_path.join(&line), line)),
+                ),
+                Listing::Printed(paths) => {
+                    let mut paths = paths.into_iter();
+                    while self.header_lines.len() < self.config.header_lines
+                        && let Some(line) = paths.next()
+                    {
+                        self.header_lines.push(line.to_string_lossy().replace('\t', " "));
+                    }
+                    listed.extend(paths.map(|path| self.printed_entry(path)));
+                }
+                Listing::Matches(matches) => listed.extend(matches.into_iter().map(|found| {
+                    let label = found.label();
+                    Entry::at_line(self.current_path.join(&found.path), found.line, label)
+                })),
+                Listing::Todos(todos) => listed.extend(todos.into_iter().map(|todo| {
+                    let label = todo.label(&self.current_path);
+                    Entry::at_line(todo.path, todo.line, label)
+                })),
+                Listing::Differences(differences) => {
+                    if let Some(diff) = &mut self.diff {
+                        listed.extend(
+                            differences
+                                .iter()
+                                .map(|difference| Entry::labeled(diff.path(difference), diff.label(difference))),
+                        );
+                        diff.differences.extend(differences);
+                    }
+                }
+                Listing::Done => finished = Some(Ok(())),
+                Listing::Failed(err) => finished = Some(Err(err)),
+            }
+            changed = true;
+        }
+        if !listed.is_empty() {
+            self.add_listed(listed);
+        }
+        match finished {
+            Some(Ok(())) => {
+                if let Some(started) = self.loading.take() {
+                    self.timings.load = started.elapsed();
+                    self.events.push(Event::Load);
+                }
+                self.pending_select.clear();
+                self.listed_path = Some(self.current_path.clone());
+            }
+            // Deleted or unmounted while browsing
+            Some(Err(_)) if self.config.source == Source::Browse && !self.current_path.exists() => {
+                self.loading = None;
+                self.pending_select.clear();
+                self.move_to_existing_ancestor("is gone");
+                self.filter.clear();
+                self.load_directory();
+            }
+            Some(Err(err)) => {
+                self.loading = None;
+                self.pending_select.clear();
+                self.status_message = Some(format!("Cannot read {}: {}", self.current_path.display(), err));
+            }
+            None => {}
+        }
+        while let Ok((path, result)) = self.decrypt_rx.try_recv() {
+            if self.preview_path.as_ref() != Some(&path) {
+                continue;
+            }
+            match result {
+                Ok(plaintext) => {
+                    self.preview_levels = fold::indent_levels(&plaintext);
+                    self.preview_content = Some(plaintext);
+                }
+                Err(err) => {
+                    self.preview_content = Some(format!("Cannot decrypt: {}", err));
+                    self.status_message = Some(format!("Cannot decrypt {}: {}", path.display(), err));
+                }
+            }
+            changed = true;
+        }
+        self.check_dir_changed();
+        self.check_journal_changed();
+        if self.check_live_changed() {
+            changed = true;
+        }
+        if let Some(rx) = &self.refresh_rx {
+            let mut finished = None;
+            while let Ok(listing) = rx.try_recv() {
+                match listing {
+                    Listing::Entries(paths) => self
+                        .refreshed
+                        .extend(paths.into_iter().map(|(path, file_type)| Entry::listed(path, file_type))),
+                    Listing::Done => finished = Some(true),
+                    Listing::Failed(_) => finished = Some(false),
+                    Listing::Lines(_) | Listing::Printed(_) | Listing::Matches(_) | Listing::Differences(_) | Listing::Todos(_) => {}
+                }
+            }
+            match finished {
+                Some(true) => {
+                    self.refresh_rx = None;
+                    self.apply_refresh();
+                    changed = true;
+                }
+                // Deleted while browsing
+                Some(false) if !self.current_path.exists() => {
+                    self.move_to_existing_ancestor("is gone");
+                    self.filter.clear();
+                    self.load_directory();
+                    changed = true;
+                }
+                Some(false) => {
+                    self.refresh_rx = None;
+                    self.refreshed.clear();
+                }
+                None => {}
+            }
+        }
+        self.check_preview_changed();
+        while let Ok((path, sum)) = self.checksum_rx.try_recv() {
+            if self.preview_path.as_ref() != Some(&path) {
+                continue;
+            }
+            match self.preview_checksum {
+                None => self.preview_checksum = Some(sum),
+                Some(read) if read != sum => {
+                    self.preview_changed = true;
+                    changed = true;
+                }
+                Some(_) => {}
+            }
+        }
+        // Nothing visible changes until the selection reaches them
+        while let Ok((key, prefetched)) = self.prefetch_rx.try_recv() {
+            self.prefetched.insert(key, prefetched);
+        }
+        while let Ok((path, output)) = self.command_preview_rx.try_recv() {
+            if self.preview_path.as_ref() == Some(&path) {
+                self.preview_levels = fold::indent_levels(&output);
+                self.folds.clear();
+                // A late entry count would put the directory sample back
+                self.directory_sample = None;
+                self.preview_content = Some(output);
+                changed = true;
+            }
+        }
+        while let Ok((path, count)) = self.count_rx.try_recv() {
+            if self.preview_path.as_ref() == Some(&path)
+                && let Some(sample) = &self.directory_sample
+            {
+                self.preview_content = Some(sample.render(Some(count)));
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Preview through `bat` when requested and installed. Returns `None` to
+    /// fall back to the built-in reader.
+    fn read_with_bat(&mut self, path: &Path) -> Option<String> {
+        if !self.uses_bat()
+            || has_binary_extension(path)
+            || self.is_large_file(path)
+        {
+            return None;
+        }
+
+        match Command::new(Tool::Bat.binary())
+            .args(["--color=always", "--style=plain", "--paging=never"])
+            .arg(path)
+            .output()
+        {
+            Ok(output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Ok(_) => None,
+            Err(_) => {
+                // Only report this once; afterwards the built-in reader is used silently
+                self.tools.mark_unavailable(Tool::Bat);
+                self.status_message = Some("bat failed to run, using built-in preview".to_string());
+                None
+            }
+        }
+    }
+
+    fn uses_bat(&self) -> bool {
+        self.config.use_bat && self.tools.is_available(Tool::Bat)
+    }
+
+    fn is_large_file(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|meta| meta.len() > self.config.preview_limit)
+    }
+
+}
+
+/// Modification time and size of `path`, which change whenever its
+/// contents might have.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// A preview read ahead of the selection.
+struct Prefetched {
+    /// When the file was last modified as it was read, so edits since are
+    /// not hidden by a stale preview
+    modified: Option<SystemTime>,
+    content: String,
+}
+
+impl Prefetched {
+    fn is_current(&self, path: &Path) -> bool {
+        self.modified.is_some() && fs::metadata(path).and_then(|meta| meta.modified()).ok() == self.modified
+    }
+}
+
+/// Whether the preview of `path` is only its first and last lines. Large
+/// files (typically logs) show both ends rather than only the head, unless
+/// there is a line to jump to and it isn't too far in.
+fn shows_both_ends(path: &Path, limit: u64, focused: bool) -> bool {
+    let size = fs::metadata(path).map_or(0, |meta| meta.len());
+    let focusable = focused && size <= LARGE_FILE_BYTES * 20;
+    size > limit && !focusable
+}
+
+/// The built-in preview of the file at `path`. Files over `limit` bytes
+/// show only their first and last lines, unless `focused` on a line.
+fn read_file_content(path: &Path, limit: u64, focused: bool) -> Option<String> {
+    // Never read device nodes, FIFOs or sockets
+    if !fs::metadata(path).is_ok_and(|meta| meta.is_file()) {
+        return Some("Not a regular file".to_string());
+    }
+    if media::is_media(path)
+        && let Some(info) = media::probe(path)
+    {
+        return Some(info.card(path));
+    }
+    if thumbnail::is_image(path) {
+        return Some(image_card(path));
+    }
+    if has_binary_extension(path) {
+        return Some(format!("Binary file: {}", path.file_name()?.to_string_lossy()));
+    }
+
+    if shows_both_ends(path, limit, focused) {
+        return match preview::head_and_tail(path, HEAD_TAIL_LINES) {
+            Ok(Some(content)) => Some(content),
+            Ok(None) => Some(format!("Binary file: {}", path.file_name()?.to_string_lossy())),
+            Err(_) => Some("Could not read file".to_string()),
+        };
+    }
+
+    // Try to read as text
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            // Check if content looks like binary (contains null bytes)
+            if content.contains('\0') {
+                Some(format!("Binary file: {}", path.file_name()?.to_string_lossy()))
+            } else {
+                Some(content)
+            }
+        }
+        Err(_) => Some("Could not read file".to_string()),
+    }
+}
+
+
+/// What the preview says about an image, above the image itself when the
+/// terminal can show it.
+fn image_card(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let size = preview::human_size(fs::metadata(path).map_or(0, |meta| meta.len()));
+    match imageinfo::dimensions(path) {
+        Some((width, height)) => format!("Image: {}\n{}×{}, {}", name, width, height, size),
+        None => format!("Image: {}\n{}", name, size),
+    }
+}
+
+/// Check if file is likely binary by extension
+fn has_binary_extension(path: &Path) -> bool {
+    let Some(extension) = path.extension() else {
+        return false;
+    };
+    let ext = extension.to_string_lossy().to_lowercase();
+    let binary_extensions = [
+        "exe", "bin", "dll", "so", "dylib", "a", "o", "obj",
+        "jpg", "jpeg", "png", "gif", "bmp", "ico", "tiff", "webp",
+        "mp3", "mp4", "wav", "flac", "ogg", "avi", "mkv", "mov",
+        "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx",
+        "zip", "tar", "gz", "bz2", "7z", "rar",
+    ];
+    binary_extensions.contains(&ext.as_str())
+}
+
+/// Whether `path` is a dot-file, which is left out unless `--hidden`.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+}
+
+/// Group TODO-style markers by tag, in the order the tags were given, then
+/// by location. The tag leads the label.
+fn sort_todos(items: &mut [Entry], tags: &[String]) {
+    items.sort_by_cached_key(|entry| {
+        let tag = entry.label.as_deref().and_then(|label| label.split(' ').next()).unwrap_or_default();
+        (todos::rank(tag, tags), entry.path.clone(), entry.line)
+    });
+}
+
+/// Order a directory listing: `..` first, then directories, then files,
+/// both alphabetically. Each entry is statted once, as this runs again for
+/// every batch of a listing being read.
+fn sort_listing(items: &mut [Entry]) {
+    items.sort_by_cached_key(|entry| {
+        let name = safe_filename_to_string(&entry.path);
+        (name != "..", !entry.is_dir(), name)
+    });
+}
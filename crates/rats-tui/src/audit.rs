@@ -0,0 +1,92 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::{state_dir, time_ago};
+
+/// One filesystem change made through rats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub user: String,
+    pub pid: u32,
+    /// What was done, e.g. `restore`, `purge`, `unmount`
+    pub action: String,
+    pub path: PathBuf,
+    /// Where the entry ended up, for operations that move it
+    pub new_path: Option<PathBuf>,
+}
+
+impl Operation {
+    /// One-line summary used as the list label.
+    pub fn label(&self) -> String {
+        let target = match &self.new_path {
+            Some(new_path) => format!("{} -> {}", self.path.display(), new_path.display()),
+            None => self.path.display().to_string(),
+        };
+        format!(
+            "{:>8}  {:<8} {:<8} {}",
+            time_ago(self.timestamp),
+            self.user,
+            self.action,
+            target
+        )
+    }
+}
+
+fn audit_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("audit.log"))
+}
+
+/// Append a successful operation to the audit log, one JSON object per line.
+pub fn record(action: &str, path: &Path, new_path: Option<&Path>) -> io::Result<()> {
+    let file = audit_file().ok_or_else(|| io::Error::other("no state directory"))?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let operation = Operation {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        user: current_user(),
+        pid: std::process::id(),
+        action: action.to_string(),
+        path: absolute(path),
+        new_path: new_path.map(absolute),
+    };
+    let line = serde_json::to_string(&operation).map_err(io::Error::other)?;
+    let mut out = OpenOptions::new().create(true).append(true).open(&file)?;
+    writeln!(out, "{}", line)
+}
+
+/// Every logged operation, most recent first. The log is never compacted:
+/// it is a trail, not a cache.
+pub fn load() -> Vec<Operation> {
+    let content = audit_file()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .unwrap_or_default();
+    content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Log entries outlive the working directory they were made in.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn current_user() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("LOGNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
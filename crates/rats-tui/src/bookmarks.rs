@@ -0,0 +1,69 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::expand::config_dir;
+
+/// Directories saved under a letter with `m<letter>`, kept in
+/// `$XDG_CONFIG_HOME/rats/bookmarks` (or `~/.config/rats/bookmarks`) as
+/// `letter = path` lines, like the aliases file.
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    marks: BTreeMap<char, PathBuf>,
+}
+
+impl Bookmarks {
+    pub fn load() -> Bookmarks {
+        let content = bookmarks_file()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .unwrap_or_default();
+        let marks = content
+            .lines()
+            // Only whole-line comments, as paths may hold a `#`
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .filter_map(|(letter, path)| {
+                let mut letter = letter.trim().chars();
+                match (letter.next(), letter.next(), path.trim()) {
+                    (Some(letter), None, path) if !path.is_empty() => Some((letter, PathBuf::from(path))),
+                    _ => None,
+                }
+            })
+            .collect();
+        Bookmarks { marks }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let file = bookmarks_file().ok_or_else(|| io::Error::other("no config directory"))?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content: String = self
+            .marks
+            .iter()
+            .map(|(letter, path)| format!("{} = {}\n", letter, path.display()))
+            .collect();
+        fs::write(file, content)
+    }
+
+    pub fn get(&self, letter: char) -> Option<&Path> {
+        self.marks.get(&letter).map(PathBuf::as_path)
+    }
+
+    /// Save `dir` under `letter`, replacing what was there, and persist.
+    pub fn set(&mut self, letter: char, dir: PathBuf) -> io::Result<()> {
+        self.marks.insert(letter, dir);
+        self.save()
+    }
+
+    /// Every bookmark, by letter.
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Path)> {
+        self.marks.iter().map(|(&letter, path)| (letter, path.as_path()))
+    }
+}
+
+fn bookmarks_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("bookmarks"))
+}
@@ -0,0 +1,100 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Deserialize;
+
+use crate::walk::walk_files;
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    manifest_path: PathBuf,
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+    src_path: PathBuf,
+}
+
+/// A row of cargo mode: a package's manifest, the root file of one of its
+/// targets, or one of its files.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub path: PathBuf,
+    pub label: String,
+}
+
+/// Every package of the workspace around `dir` with its targets and files,
+/// grouped by package. Files of a package nested inside another are only
+/// listed under the inner one.
+pub fn workspace(dir: &Path) -> Result<Vec<Item>, String> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(dir)
+        .output()
+        .map_err(|err| format!("cannot run cargo: {}", err))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("cargo metadata failed");
+        return Err(reason.trim().to_string());
+    }
+    let mut metadata: Metadata =
+        serde_json::from_slice(&output.stdout).map_err(|err| format!("unexpected cargo metadata: {}", err))?;
+    metadata.packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let roots: Vec<&Path> = metadata
+        .packages
+        .iter()
+        .filter_map(|package| package.manifest_path.parent())
+        .collect();
+    let width = metadata.packages.iter().map(|package| package.name.len()).max().unwrap_or(0);
+
+    let mut items = Vec::new();
+    for package in &metadata.packages {
+        let Some(root) = package.manifest_path.parent() else {
+            continue;
+        };
+        let name = format!("{:<width$}", package.name);
+        items.push(Item {
+            path: package.manifest_path.clone(),
+            label: format!("{}  Cargo.toml", name),
+        });
+
+        let mut targets: Vec<&Target> = package.targets.iter().collect();
+        targets.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+        for target in targets {
+            let file = target.src_path.strip_prefix(root).unwrap_or(&target.src_path);
+            items.push(Item {
+                path: target.src_path.clone(),
+                label: format!("{}  [{}] {}  {}", name, target.kind.join(","), target.name, file.display()),
+            });
+        }
+
+        let nested = |path: &Path| {
+            roots
+                .iter()
+                .any(|other| other.starts_with(root) && *other != root && path.starts_with(other))
+        };
+        for file in walk_files(root) {
+            if file == package.manifest_path || nested(&file) {
+                continue;
+            }
+            let relative = file.strip_prefix(root).unwrap_or(&file);
+            items.push(Item {
+                label: format!("{}  {}", name, relative.display()),
+                path: file,
+            });
+        }
+    }
+    Ok(items)
+}
@@ -0,0 +1,316 @@
+use std::{ffi::OsString, path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand, builder::OsStringValueParser, builder::TypedValueParser};
+use ratatui::layout::Alignment;
+
+use crate::{
+    age::AgeScale,
+    config::{NetworkMode, SortOrder, Source, TerminalBackend},
+    expand::expand,
+    fields::Fields,
+    keymap::Keymap,
+    preview::parse_size,
+    widgets::{BorderStyle, ChromeConfig, Colors},
+};
+
+/// The command line as clap parses it; `Config::from_args` turns it into a
+/// `Config`. Options can be given before or after a subcommand, and a later
+/// occurrence of an option overrides an earlier one (the config file and
+/// `RATS_DEFAULT_OPTS` go first), except `--bind`, `--color` and
+/// `--on-start`, which add up.
+#[derive(Debug, Parser)]
+#[command(
+    name = "rats",
+    about = "A fast fuzzy file browser for the terminal",
+    args_override_self = true,
+    disable_version_flag = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directory to browse (default: current), or a file to select in its
+    /// directory
+    #[arg(value_name = "DIRECTORY", value_parser = OsStringValueParser::new().try_map(parse_directory))]
+    pub path: Option<PathBuf>,
+
+    /// Where to start when no DIRECTORY is given (the config file's
+    /// `directory`)
+    #[arg(long, global = true, value_name = "DIR", value_parser = OsStringValueParser::new().try_map(parse_directory))]
+    pub directory: Option<PathBuf>,
+
+    /// Print version information
+    #[arg(short = 'V', long, global = true)]
+    pub version: bool,
+
+    /// Output results as JSON (for integrations)
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Start with search query
+    #[arg(long, global = true)]
+    pub query: Option<String>,
+    /// What to list: browse (default), diagnostics, todos, trash, mounts,
+    /// history, oplog, cargo, man, clipboard, grep, bookmarks, jump,
+    /// command, stdin
+    #[arg(long, global = true, value_parser = parse_mode)]
+    pub mode: Option<Source>,
+    /// Comma-separated markers for todos mode (default: TODO,FIXME,HACK)
+    #[arg(long, global = true, value_name = "TAGS")]
+    pub todo_tags: Option<String>,
+    /// Pick from the lines piped on stdin (the default when stdin isn't a
+    /// terminal and no other mode is asked for)
+    #[arg(long, global = true)]
+    pub stdin: bool,
+    /// Search file contents for the filter text
+    #[arg(long, global = true)]
+    pub grep: bool,
+    /// Lines shown above the match in content search previews (default:
+    /// center it)
+    #[arg(long, global = true, value_name = "N")]
+    pub grep_context: Option<usize>,
+    /// Mark every match in the previewed file on its edge
+    #[arg(long, global = true)]
+    pub grep_minimap: bool,
+    /// Show dot-files when browsing (toggle with .)
+    #[arg(long, global = true)]
+    pub hidden: bool,
+    /// Include files ignored by git (toggle with I)
+    #[arg(long, global = true)]
+    pub no_ignore: bool,
+    /// List every file below the directory by relative path
+    #[arg(long, global = true)]
+    pub recursive: bool,
+    /// Group recursive results under directory headers
+    #[arg(long, global = true)]
+    pub group: bool,
+    /// Ask before accepting files bigger than this (default: 100M; off to
+    /// never ask)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_warn_size)]
+    pub warn_size: Option<u64>,
+    /// Allow decrypting .gpg and .age files for the preview
+    #[arg(long, global = true)]
+    pub decrypt: bool,
+    /// Identity file for decrypting .age files
+    #[arg(long, global = true, value_name = "FILE")]
+    pub age_identity: Option<PathBuf>,
+    /// History file for clipboard mode (default: clipman's)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub clipboard_history: Option<PathBuf>,
+    /// Scan the whole repository, not just active subtrees
+    #[arg(long, global = true)]
+    pub whole_repo: bool,
+    /// Network-filesystem mode: auto (default), on or off
+    #[arg(long, global = true, value_name = "WHEN", value_parser = parse_network)]
+    pub network: Option<NetworkMode>,
+    /// Tint names by how recently they were modified
+    #[arg(long, global = true)]
+    pub age_colors: bool,
+    /// Ages where the tint dims (default: 1d,7d,30d)
+    #[arg(long, global = true, value_name = "LIST", value_parser = parse_age_thresholds)]
+    pub age_thresholds: Option<AgeScale>,
+    /// Show how long the last directory load and filter pass took
+    #[arg(long, global = true)]
+    pub timings: bool,
+    /// On a crash, write a diagnostic report to ~/.local/state/rats
+    #[arg(long, global = true)]
+    pub crash_report: bool,
+    /// Use bat for syntax-highlighted previews
+    #[arg(long, global = true)]
+    pub bat: bool,
+    /// Preview with what COMMAND prints, {} in it standing for the entry,
+    /// {1}, {2..} for its fields and {q} for the query
+    #[arg(long, visible_alias = "preview", global = true, value_name = "COMMAND")]
+    pub preview_cmd: Option<String>,
+    /// Double-click interval in milliseconds (default: 400)
+    #[arg(long, global = true, value_name = "MS")]
+    pub double_click_ms: Option<u64>,
+    /// Lines per mouse wheel tick (default: 3)
+    #[arg(long, global = true, value_name = "N")]
+    pub scroll_lines: Option<usize>,
+    /// Wheel over the list scrolls the view instead of moving the selection
+    #[arg(long, global = true)]
+    pub wheel_scrolls_viewport: bool,
+    /// No header, borders, titles or help, only the list, the preview and
+    /// a prompt line
+    #[arg(long, global = true)]
+    pub minimal: bool,
+    /// Pane borders: plain (default), rounded, double, thick or none
+    #[arg(long, global = true, value_name = "STYLE", value_parser = parse_border)]
+    pub border: Option<BorderStyle>,
+    /// Pane titles: left (default), center or right
+    #[arg(long, global = true, value_name = "POS", value_parser = parse_title_align)]
+    pub title_align: Option<Alignment>,
+    /// Rebind keys, e.g. normal.J=bottom,insert.ctrl-j=next, or bind
+    /// events, e.g. one:accept
+    #[arg(long, global = true, value_name = "LIST", value_parser = parse_bindings)]
+    pub bind: Vec<String>,
+    /// Colors as part=color pairs, e.g. header=red,selection=#334455
+    #[arg(long, global = true, value_name = "LIST", value_parser = parse_colors)]
+    pub color: Vec<String>,
+    /// Actions or `:` commands to run at startup, separated by semicolons
+    #[arg(long, global = true, value_name = "LIST")]
+    pub on_start: Vec<String>,
+    /// List what COMMAND prints, run again for each filter ({q} in it is
+    /// replaced by the filter text)
+    #[arg(long, global = true, value_name = "COMMAND")]
+    pub live: Option<String>,
+    /// Files bigger than this preview only their first and last lines
+    /// (default: 50000 bytes)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_preview_limit)]
+    pub preview_limit: Option<u64>,
+    /// With the preview hidden (v), show the first line of each file under
+    /// its entry
+    #[arg(long, global = true)]
+    pub excerpts: bool,
+    /// End the printed selection with NUL instead of a newline
+    #[arg(long, global = true)]
+    pub print0: bool,
+    /// Entries from the listing command are separated by NUL instead of
+    /// newlines
+    #[arg(long, global = true)]
+    pub read0: bool,
+    /// What separates the fields of listed lines (default: runs of
+    /// whitespace)
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub delimiter: Option<String>,
+    /// Text shown above the list, such as instructions
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub header: Option<String>,
+    /// Show the first N lines of stdin or the listing command's output
+    /// above the list, such as column titles, instead of in it
+    #[arg(long, global = true, value_name = "N")]
+    pub header_lines: Option<usize>,
+    /// Fields of listed lines the filter matches, e.g. 1 or 2..,-1
+    #[arg(long, global = true, value_name = "FIELDS", value_parser = Fields::parse)]
+    pub nth: Option<Fields>,
+    /// Fields of listed lines shown; accepting one still prints it whole
+    #[arg(long, global = true, value_name = "FIELDS", value_parser = Fields::parse)]
+    pub with_nth: Option<Fields>,
+    /// Exit with status 124 after this many seconds, selection or not
+    #[arg(long, global = true, value_name = "SECS", value_parser = parse_seconds)]
+    pub timeout: Option<Duration>,
+    /// Exit with status 124 after this many seconds without input
+    #[arg(long, global = true, value_name = "SECS", value_parser = parse_seconds)]
+    pub idle_timeout: Option<Duration>,
+    /// Start in the gallery grid view
+    #[arg(long, global = true)]
+    pub gallery: bool,
+    /// name (default) or taken (photo capture date)
+    #[arg(long, global = true, value_name = "ORDER", value_parser = parse_sort)]
+    pub sort: Option<SortOrder>,
+    /// Don't draw image thumbnails in kitty-compatible terminals
+    #[arg(long, global = true)]
+    pub no_thumbnails: bool,
+    /// Terminal library: crossterm (default) or termion
+    #[arg(long, global = true, value_name = "NAME", value_parser = parse_backend)]
+    pub backend: Option<TerminalBackend>,
+    /// Restore the state journaled by a crashed session
+    #[arg(long, global = true)]
+    pub resume_crashed: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Browse previously accepted selections (like --mode history)
+    History,
+    /// Replace this binary with the latest release, if it is newer. The
+    /// download is checked against the release's SHA256SUMS, which catches
+    /// corruption but not a tampered release (there is no signature check)
+    SelfUpdate {
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+    },
+    /// Carry out a batch plan exported with `e` from the plan review
+    Apply {
+        /// The plan, or - to read it from stdin
+        plan: PathBuf,
+        /// Only show what would be done
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Answer a file chooser request, for an xdg-desktop-portal-termfilechooser
+    /// wrapper
+    Portal {
+        #[arg(num_args = 5.., required = true, value_names = ["MULTIPLE", "DIRECTORY", "SAVE", "PATH", "OUT"])]
+        request: Vec<OsString>,
+    },
+}
+
+/// A directory (or file) that exists, after expanding `~`, `$VARS` and
+/// `@aliases`.
+fn parse_directory(arg: OsString) -> Result<PathBuf, String> {
+    let path = match arg.to_str() {
+        Some(path) => PathBuf::from(expand(path)),
+        None => PathBuf::from(arg),
+    };
+    match path.try_exists() {
+        Ok(true) => Ok(path),
+        Ok(false) => Err(format!("{} does not exist", path.display())),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+fn parse_mode(name: &str) -> Result<Source, String> {
+    Source::from_name(name).ok_or_else(|| "see --help for the modes".to_string())
+}
+
+/// A size, or `off` (or 0), which is returned as 0.
+fn parse_warn_size(size: &str) -> Result<u64, String> {
+    match size {
+        "off" | "0" => Ok(0),
+        size => parse_size(size).ok_or_else(|| "expected e.g. 100M or off".to_string()),
+    }
+}
+
+fn parse_preview_limit(size: &str) -> Result<u64, String> {
+    parse_size(size).ok_or_else(|| "expected e.g. 50K".to_string())
+}
+
+fn parse_network(name: &str) -> Result<NetworkMode, String> {
+    NetworkMode::from_name(name).ok_or_else(|| "expected auto, on or off".to_string())
+}
+
+fn parse_age_thresholds(spec: &str) -> Result<AgeScale, String> {
+    AgeScale::parse(spec).ok_or_else(|| "expected e.g. 1d,7d,30d".to_string())
+}
+
+fn parse_border(name: &str) -> Result<BorderStyle, String> {
+    BorderStyle::from_name(name).ok_or_else(|| "expected none, plain, rounded, double or thick".to_string())
+}
+
+fn parse_title_align(name: &str) -> Result<Alignment, String> {
+    ChromeConfig::alignment_from_name(name).ok_or_else(|| "expected left, center or right".to_string())
+}
+
+/// Key bindings, checked here so a mistake is reported like any other
+/// invalid value.
+fn parse_bindings(spec: &str) -> Result<String, String> {
+    Keymap::default().bind(spec)?;
+    Ok(spec.to_string())
+}
+
+fn parse_colors(spec: &str) -> Result<String, String> {
+    Colors::default().set(spec)?;
+    Ok(spec.to_string())
+}
+
+/// Seconds, possibly fractional, more than zero.
+fn parse_seconds(secs: &str) -> Result<Duration, String> {
+    secs.parse()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .filter(|limit| !limit.is_zero())
+        .ok_or_else(|| "expected seconds, e.g. 30 or 0.5".to_string())
+}
+
+fn parse_sort(name: &str) -> Result<SortOrder, String> {
+    SortOrder::from_name(name).ok_or_else(|| "expected name or taken".to_string())
+}
+
+fn parse_backend(name: &str) -> Result<TerminalBackend, String> {
+    TerminalBackend::from_name(name).ok_or_else(|| {
+        let supported = if cfg!(feature = "termion") { "crossterm or termion" } else { "crossterm" };
+        format!("this build supports {}", supported)
+    })
+}
@@ -0,0 +1,86 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::tools::{Tool, Tools};
+
+/// Longest label clipboard mode shows for a clip; the preview has the rest.
+const MAX_LABEL_CHARS: usize = 500;
+
+/// Clipboard programs in order of preference, with the arguments that make
+/// them read the clipboard contents from stdin.
+const COPY_COMMANDS: [(Tool, &[&str]); 3] = [
+    (Tool::WlCopy, &[]),
+    (Tool::Xclip, &["-selection", "clipboard"]),
+    (Tool::Pbcopy, &[]),
+];
+
+/// Put `text` on the system clipboard through the first available program.
+/// Returns the name of the program used.
+pub fn copy(tools: &Tools, text: &str) -> io::Result<&'static str> {
+    let (tool, args) = COPY_COMMANDS
+        .iter()
+        .find(|(tool, _)| tools.is_available(*tool))
+        .ok_or_else(|| io::Error::other("no clipboard program found (wl-copy, xclip or pbcopy)"))?;
+
+    let mut child = Command::new(tool.binary())
+        .args(*args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        return Err(io::Error::other(format!("{} failed", tool.binary())));
+    }
+    Ok(tool.binary())
+}
+
+/// Prefix of the entries clipboard mode lists, followed by the index of the
+/// clip in the history.
+pub const SCHEME: &str = "clip:";
+
+/// The history file clipboard mode reads by default: clipman's, at
+/// `$XDG_DATA_HOME/clipman.json` or `~/.local/share/clipman.json`.
+pub fn default_history() -> Option<PathBuf> {
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data) if !data.is_empty() => Some(PathBuf::from(data).join("clipman.json")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/clipman.json")),
+    }
+}
+
+/// The clips in the history file at `path`, newest first. The file is a
+/// JSON array of strings, oldest first, as clipman writes it, or one JSON
+/// string per line.
+pub fn history(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let mut clips: Vec<String> = match serde_json::from_str(&text) {
+        Ok(clips) => clips,
+        Err(_) => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|err| format!("{} is not a clipboard history: {}", path.display(), err))?,
+    };
+    clips.retain(|clip| !clip.trim().is_empty());
+    clips.reverse();
+    Ok(clips)
+}
+
+/// `clip` on one line for the list, with line breaks shown as `↵`, so all
+/// of it can be searched.
+pub fn label(clip: &str) -> String {
+    let label: String = clip
+        .trim()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(" ↵ ");
+    label.chars().take(MAX_LABEL_CHARS).collect()
+}
@@ -0,0 +1,416 @@
+use std::{ffi::OsString, io::IsTerminal, path::PathBuf, time::Duration};
+
+use clap::Parser;
+
+use crate::{action::Action, age::{AgeScale, DEFAULT_THRESHOLDS}, cli::{Cli, Command}, config_file, expand::split_words, fields::{Delimiter, Fields}, keymap::{self, Keymap}, mouse::MouseConfig, portal::ChooserRequest, preview::LARGE_FILE_BYTES, todos::DEFAULT_TAGS, update::version_string, widgets::ChromeConfig};
+
+/// Files bigger than this need confirming before they are accepted, as an
+/// editor opening whatever is printed could choke on them.
+const DEFAULT_WARN_SIZE: u64 = 100 * 1024 * 1024;
+
+/// What populates the result list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// Browse the filesystem starting at `directory`
+    Browse,
+    /// Compiler/linter output piped on stdin
+    Diagnostics,
+    /// TODO/FIXME/HACK markers found under `directory`
+    Todos,
+    /// Contents of the freedesktop.org trash
+    Trash,
+    /// Mounted filesystems and removable devices
+    Mounts,
+    /// Previously accepted selections
+    History,
+    /// Directories bookmarked with `m<letter>`
+    Bookmarks,
+    /// Directories browsed before, most frecent first
+    Jump,
+    /// The audit log of file operations
+    Oplog,
+    /// Files that differ between two directories
+    Diff,
+    /// Paths printed by `RATS_DEFAULT_COMMAND`
+    Command,
+    /// Lines piped on stdin
+    Stdin,
+    /// Packages, targets and files of the Cargo workspace
+    Cargo,
+    /// Man pages known to `apropos`
+    Man,
+    /// Texts from a clipboard history file
+    Clipboard,
+    /// Lines of the files under `directory` containing the filter text
+    Grep,
+}
+
+impl Source {
+    pub fn from_name(name: &str) -> Option<Source> {
+        match name {
+            "browse" => Some(Source::Browse),
+            "diagnostics" => Some(Source::Diagnostics),
+            "todos" => Some(Source::Todos),
+            "trash" => Some(Source::Trash),
+            "mounts" => Some(Source::Mounts),
+            "history" => Some(Source::History),
+            "bookmarks" => Some(Source::Bookmarks),
+            "jump" => Some(Source::Jump),
+            "oplog" => Some(Source::Oplog),
+            "command" => Some(Source::Command),
+            "stdin" => Some(Source::Stdin),
+            "cargo" => Some(Source::Cargo),
+            "man" => Some(Source::Man),
+            "clipboard" => Some(Source::Clipboard),
+            "grep" => Some(Source::Grep),
+            _ => None,
+        }
+    }
+}
+
+/// Order of the list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// Best match first; with no filter, directories then files by name
+    Name,
+    /// Photos by EXIF capture date, oldest first, then everything else
+    Taken,
+}
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Option<SortOrder> {
+        match name {
+            "name" => Some(SortOrder::Name),
+            "taken" => Some(SortOrder::Taken),
+            _ => None,
+        }
+    }
+}
+
+/// When to browse in network-filesystem mode, which skips per-entry
+/// metadata, directory previews and `.git` discovery.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetworkMode {
+    /// On NFS, SMB and similar mounts
+    Auto,
+    On,
+    Off,
+}
+
+impl NetworkMode {
+    pub fn from_name(name: &str) -> Option<NetworkMode> {
+        match name {
+            "auto" => Some(NetworkMode::Auto),
+            "on" => Some(NetworkMode::On),
+            "off" => Some(NetworkMode::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Library used for terminal I/O.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TerminalBackend {
+    Crossterm,
+    /// Only available when built with the `termion` feature
+    #[cfg(feature = "termion")]
+    Termion,
+}
+
+impl TerminalBackend {
+    pub fn from_name(name: &str) -> Option<TerminalBackend> {
+        match name {
+            "crossterm" => Some(TerminalBackend::Crossterm),
+            #[cfg(feature = "termion")]
+            "termion" => Some(TerminalBackend::Termion),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub directory: PathBuf,
+    pub query: String,
+    pub json_mode: bool,
+    pub use_bat: bool,
+    /// `--preview-cmd`: previews the selected entry, `{}` in it standing
+    /// for the entry
+    pub preview_command: Option<String>,
+    /// `--header`: shown above the list
+    pub header: Option<String>,
+    /// `--header-lines`: how many of the first lines a command prints (or
+    /// stdin holds) are shown above the list instead of in it
+    pub header_lines: usize,
+    pub source: Source,
+    /// `RATS_DEFAULT_COMMAND`: lists the entries instead of reading the
+    /// directory, when set
+    pub default_command: Option<String>,
+    /// `--live`: run the command again as the filter changes, in place of
+    /// matching its output against the filter
+    pub live: bool,
+    pub todo_tags: Vec<String>,
+    /// `--preview-limit`: files bigger than this preview only their first
+    /// and last lines
+    pub preview_limit: u64,
+    /// `--bind`: keys rebound by the user
+    pub keymap: Keymap,
+    /// `--on-start`: actions run once the first listing is read
+    pub on_start: Vec<Action>,
+    /// `--warn-size`: accepting a file bigger than this needs a second
+    /// Enter; `None` with `--warn-size off`
+    pub warn_size: Option<u64>,
+    /// `--decrypt`: allow decrypting gpg and age files for the preview
+    /// (with U), which is off unless asked for
+    pub decrypt: bool,
+    /// `--age-identity`: identity file for decrypting age files
+    pub age_identity: Option<PathBuf>,
+    /// `--clipboard-history`: history file for clipboard mode, instead of
+    /// clipman's
+    pub clipboard_history: Option<PathBuf>,
+    /// `rats self-update`: replace the binary instead of running the UI
+    pub self_update: bool,
+    /// `--check`: only report whether an update is available
+    pub check_only: bool,
+    /// Entry to highlight once the initial list is loaded
+    pub select: Option<PathBuf>,
+    /// `--resume-crashed`: restore the state journaled by a crashed session
+    pub resume_crashed: bool,
+    pub mouse: MouseConfig,
+    /// `--minimal`, `--border` and `--title-align`
+    pub chrome: ChromeConfig,
+    /// `--age-colors`: tint names by how recently they were modified
+    pub age_colors: bool,
+    /// `--age-thresholds`: ages at which the tint gets dimmer
+    pub age_scale: AgeScale,
+    /// `--excerpts`: show a line of each file under its entry while the
+    /// preview pane is hidden
+    pub excerpts: bool,
+    pub backend: TerminalBackend,
+    /// Show image thumbnails when the terminal supports kitty graphics
+    /// (`--no-thumbnails` turns them off)
+    pub thumbnails: bool,
+    /// `--gallery`: start in the gallery view
+    pub gallery: bool,
+    /// `--network`: when to use network-filesystem mode
+    pub network: NetworkMode,
+    /// `--timings`: show how long loading and filtering took
+    pub timings: bool,
+    /// `--whole-repo`: recursive scans ignore the active subtrees
+    pub whole_repo: bool,
+    /// `--hidden`: list dot-files when browsing (toggled with `.`)
+    pub hidden: bool,
+    /// `--grep-context`: lines shown above the matched line in content
+    /// search previews, instead of centering it
+    pub grep_context: Option<usize>,
+    /// `--grep-minimap`: mark every match in the previewed file on its
+    /// right edge in content search
+    pub grep_minimap: bool,
+    /// `--crash-report`: write a diagnostic report to the state directory if
+    /// rats panics
+    pub crash_report: bool,
+    /// `--group`: cluster recursive results under directory headers
+    pub group: bool,
+    /// `--no-ignore`: list and search paths ignored by git too (toggled with
+    /// `I`)
+    pub no_ignore: bool,
+    /// `--recursive`: list every file below the directory by its relative
+    /// path instead of just the directory's own entries
+    pub recursive: bool,
+    /// `--sort`: order of the list
+    pub sort: SortOrder,
+    /// `--print0`: end the printed selection with NUL instead of newline
+    pub print0: bool,
+    /// `--read0`: the listing command separates entries with NUL instead of
+    /// newline
+    pub read0: bool,
+    /// `--delimiter`: what separates the fields of listed lines (default:
+    /// runs of whitespace)
+    pub delimiter: Delimiter,
+    /// `--nth`: the fields of listed lines the filter matches
+    pub nth: Option<Fields>,
+    /// `--with-nth`: the fields of listed lines shown
+    pub with_nth: Option<Fields>,
+    /// `--timeout`: give up this long after starting, selection or not
+    pub timeout: Option<Duration>,
+    /// `--idle-timeout`: give up after this long without any input
+    pub idle_timeout: Option<Duration>,
+    /// `rats apply <plan.json>`: run a batch plan instead of the UI
+    pub apply: Option<PathBuf>,
+    /// `--dry-run`: with `apply`, only show what would be done
+    pub dry_run: bool,
+    /// `rats portal ...`: answer a file chooser request instead of printing
+    /// the selection
+    pub chooser: Option<ChooserRequest>,
+}
+
+impl Config {
+    pub fn from_args() -> Config {
+        // Arguments are kept as OsStrings so a directory that isn't valid
+        // UTF-8 still works
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        // Defaults go before the explicit arguments, which override them:
+        // the config file first, then RATS_DEFAULT_OPTS
+        let mut defaults = config_file::load().unwrap_or_else(|err| {
+            eprintln!("Invalid config file: {}", err);
+            std::process::exit(2);
+        });
+        defaults.extend(split_words(&std::env::var("RATS_DEFAULT_OPTS").unwrap_or_default()));
+        let at = args.len().min(1);
+        args.splice(at..at, defaults.into_iter().map(OsString::from));
+        let cli = Cli::parse_from(args);
+        if cli.version {
+            println!("{}", version_string());
+            std::process::exit(0);
+        }
+
+        let mut default_command = std::env::var("RATS_DEFAULT_COMMAND").ok().filter(|command| !command.trim().is_empty());
+        let mut source = match cli.mode {
+            Some(mode) => mode,
+            None if default_command.is_some() => Source::Command,
+            None => Source::Browse,
+        };
+        if cli.grep {
+            source = Source::Grep;
+        }
+        if cli.live.is_some() {
+            default_command = cli.live.clone();
+            source = Source::Command;
+        }
+        // Piped lines are picked from unless something else is asked for
+        let piped = cli.mode.is_none()
+            && !cli.grep
+            && default_command.is_none()
+            && cli.command.is_none()
+            && !std::io::stdin().is_terminal();
+        if cli.stdin || piped {
+            source = Source::Stdin;
+        }
+        let todo_tags = match &cli.todo_tags {
+            Some(tags) => tags.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect(),
+            None => DEFAULT_TAGS.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let mut keymap = Keymap::default();
+        for bindings in &cli.bind {
+            // Already checked while parsing
+            let _ = keymap.bind(bindings);
+        }
+        let on_start = cli.on_start.iter().flat_map(|actions| keymap::parse_actions(actions)).collect();
+        let mut mouse = MouseConfig::default();
+        if let Some(ms) = cli.double_click_ms {
+            mouse.double_click = Duration::from_millis(ms);
+        }
+        if let Some(lines) = cli.scroll_lines {
+            mouse.scroll_lines = lines;
+        }
+        mouse.wheel_moves_selection = !cli.wheel_scrolls_viewport;
+        let mut chrome = ChromeConfig { minimal: cli.minimal, ..ChromeConfig::default() };
+        if let Some(border) = cli.border {
+            chrome.border = border;
+        }
+        if let Some(alignment) = cli.title_align {
+            chrome.title_alignment = alignment;
+        }
+        for colors in &cli.color {
+            let _ = chrome.colors.set(colors);
+        }
+
+        let mut directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let mut select = None;
+        // The positional directory wins over --directory
+        if let Some(path) = cli.path.or(cli.directory) {
+            // A file opens its directory with it selected
+            if path.is_file()
+                && let Ok(file) = std::path::absolute(&path)
+                && let Some(parent) = file.parent()
+            {
+                directory = parent.to_path_buf();
+                select = Some(file);
+            } else {
+                directory = path;
+            }
+        }
+
+        let (mut self_update, mut check_only, mut apply, mut dry_run, mut chooser) = (false, false, None, false, None);
+        match cli.command {
+            // `rats history` is shorthand for `--mode history`
+            Some(Command::History) => source = Source::History,
+            Some(Command::SelfUpdate { check }) => {
+                self_update = true;
+                check_only = check;
+            }
+            Some(Command::Apply { plan, dry_run: dry }) => {
+                apply = Some(plan);
+                dry_run = dry;
+            }
+            // Called from an xdg-desktop-portal-termfilechooser wrapper; the
+            // request says where to start
+            Some(Command::Portal { request }) => {
+                let request = ChooserRequest::from_args(&request).expect("clap requires five arguments");
+                directory = request.start_dir();
+                source = Source::Browse;
+                select = (!request.save && request.path.is_file()).then(|| request.path.clone());
+                chooser = Some(request);
+            }
+            None => {}
+        }
+
+        let age_scale = cli.age_thresholds.clone();
+        Config {
+            json_mode: cli.json,
+            use_bat: cli.bat,
+            preview_command: cli.preview_cmd,
+            header: cli.header,
+            header_lines: cli.header_lines.unwrap_or(0),
+            source,
+            default_command,
+            live: cli.live.is_some(),
+            todo_tags,
+            clipboard_history: cli.clipboard_history,
+            warn_size: match cli.warn_size {
+                Some(0) => None,
+                size => Some(size.unwrap_or(DEFAULT_WARN_SIZE)),
+            },
+            keymap,
+            on_start,
+            preview_limit: cli.preview_limit.unwrap_or(LARGE_FILE_BYTES),
+            decrypt: cli.decrypt,
+            age_identity: cli.age_identity,
+            self_update,
+            check_only,
+            select,
+            resume_crashed: cli.resume_crashed,
+            mouse,
+            chrome,
+            excerpts: cli.excerpts,
+            age_colors: cli.age_colors || age_scale.is_some(),
+            age_scale: age_scale.unwrap_or_else(|| AgeScale::parse(DEFAULT_THRESHOLDS).expect("default thresholds parse")),
+            backend: cli.backend.unwrap_or(TerminalBackend::Crossterm),
+            thumbnails: !cli.no_thumbnails,
+            gallery: cli.gallery,
+            sort: cli.sort.unwrap_or(SortOrder::Name),
+            whole_repo: cli.whole_repo,
+            recursive: cli.recursive,
+            hidden: cli.hidden,
+            no_ignore: cli.no_ignore,
+            group: cli.group,
+            crash_report: cli.crash_report,
+            grep_context: cli.grep_context,
+            grep_minimap: cli.grep_minimap,
+            timings: cli.timings,
+            network: cli.network.unwrap_or(NetworkMode::Auto),
+            print0: cli.print0,
+            read0: cli.read0,
+            delimiter: Delimiter::new(cli.delimiter.as_deref()),
+            nth: cli.nth,
+            with_nth: cli.with_nth,
+            timeout: cli.timeout,
+            idle_timeout: cli.idle_timeout,
+            apply,
+            dry_run,
+            chooser,
+            query: cli.query.unwrap_or_default(),
+            directory,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,91 @@
+use std::{env, fs, io, path::PathBuf};
+
+use toml::{Table, Value};
+
+/// `$XDG_CONFIG_HOME/rats/config.toml` or `~/.config/rats/config.toml`.
+pub fn path() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) if !config.is_empty() => Some(PathBuf::from(config).join("rats/config.toml")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/rats/config.toml")),
+    }
+}
+
+/// The settings in the config file, as the command line arguments they
+/// stand for, so they are parsed (and overridden) like any other. A missing
+/// file has no settings.
+///
+/// Each key is a long option: `border = "rounded"` is `--border rounded`,
+/// `minimal = true` is `--minimal`, and a list is joined with commas.
+/// The `[colors]` table is `--color`, and the `[keys.<mode>]` and
+/// `[events]` tables are `--bind`.
+pub fn load() -> Result<Vec<String>, String> {
+    let Some(path) = path() else {
+        return Ok(Vec::new());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("cannot read {}: {}", path.display(), err)),
+    };
+    let table: Table = text.parse().map_err(|err| format!("{}: {}", path.display(), err))?;
+    to_args(&table).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn to_args(table: &Table) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("colors", Value::Table(colors)) => {
+                let colors = colors
+                    .iter()
+                    .map(|(part, color)| Ok(format!("{}={}", part, scalar(part, color)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                args.push("--color".to_string());
+                args.push(colors.join(","));
+            }
+            ("keys", Value::Table(modes)) => {
+                let mut bindings = Vec::new();
+                for (mode, keys) in modes {
+                    let Value::Table(keys) = keys else {
+                        return Err(format!("unexpected value for keys.{}", mode));
+                    };
+                    for (key, action) in keys {
+                        bindings.push(format!("{}.{}={}", mode, key, scalar(key, action)?));
+                    }
+                }
+                args.push("--bind".to_string());
+                args.push(bindings.join(","));
+            }
+            ("events", Value::Table(events)) => {
+                let bindings = events
+                    .iter()
+                    .map(|(event, actions)| Ok(format!("{}:{}", event, scalar(event, actions)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                args.push("--bind".to_string());
+                args.push(bindings.join(","));
+            }
+            ("colors" | "keys" | "events", _) => return Err(format!("unexpected value for {}", key)),
+            (_, Value::Boolean(true)) => args.push(format!("--{}", key)),
+            (_, Value::Boolean(false)) => {}
+            (_, Value::Array(items)) => {
+                let items = items.iter().map(|item| scalar(key, item)).collect::<Result<Vec<_>, _>>()?;
+                args.push(format!("--{}", key));
+                args.push(items.join(","));
+            }
+            _ => {
+                args.push(format!("--{}", key));
+                args.push(scalar(key, value)?);
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn scalar(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Float(number) => Ok(number.to_string()),
+        _ => Err(format!("unexpected value for {}", key)),
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    env,
+    fmt::Write as _,
+    fs, io,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{self, AtomicBool},
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    event::DisableMouseCapture,
+    execute,
+    terminal::{self, LeaveAlternateScreen},
+};
+
+use crate::{action::Action, config::Config, history::state_dir, term, update};
+
+/// How many of the latest actions a crash report lists.
+const RECENT_ACTIONS: usize = 50;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static CONFIG: OnceLock<String> = OnceLock::new();
+
+/// From now on, write a report to the state directory if rats panics: the
+/// panic and its backtrace, the latest actions, `config` and what the
+/// terminal is, and print where it went.
+pub fn install(config: &Config) {
+    ENABLED.store(true, atomic::Ordering::Relaxed);
+    let _ = CONFIG.set(format!("{:#?}", config));
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // Anything printed on the alternate screen would be lost
+        let _ = terminal::disable_raw_mode();
+        if let Ok(mut output) = term::output() {
+            let _ = execute!(output, LeaveAlternateScreen, DisableMouseCapture);
+        }
+        previous(info);
+        match write_report(info) {
+            Ok(file) => eprintln!(
+                "rats crashed. A report was written to {}; please attach it to a bug report.",
+                file.display()
+            ),
+            Err(err) => eprintln!("rats crashed, and writing the crash report failed: {}", err),
+        }
+    }));
+}
+
+/// Remember `action` for a crash report, if reports are on.
+pub fn record(action: &Action) {
+    if !ENABLED.load(atomic::Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(mut recent) = RECENT.lock() {
+        if recent.len() == RECENT_ACTIONS {
+            recent.pop_front();
+        }
+        recent.push_back(format!("{:?}", action));
+    }
+}
+
+fn write_report(info: &PanicHookInfo) -> io::Result<PathBuf> {
+    let dir = state_dir().ok_or_else(|| io::Error::other("no state directory"))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let file = dir.join(format!("crash-{}.txt", now));
+
+    let mut report = String::new();
+    let _ = writeln!(report, "{}", update::version_string());
+    let _ = writeln!(report, "time: {} (unix)", now);
+    let _ = writeln!(report, "os: {} {}", env::consts::OS, env::consts::ARCH);
+    let _ = writeln!(report, "thread: {}", thread::current().name().unwrap_or("unnamed"));
+    let _ = writeln!(report, "panic: {}", info);
+
+    let _ = writeln!(report, "\nterminal:");
+    for var in ["TERM", "COLORTERM", "TERM_PROGRAM", "TERM_PROGRAM_VERSION", "TMUX"] {
+        if let Some(value) = env::var_os(var) {
+            let _ = writeln!(report, "  {}={}", var, value.to_string_lossy());
+        }
+    }
+    if let Ok((columns, rows)) = terminal::size() {
+        let _ = writeln!(report, "  size: {}x{}", columns, rows);
+    }
+
+    let _ = writeln!(report, "\nlatest actions, oldest first:");
+    if let Ok(recent) = RECENT.lock() {
+        for action in recent.iter() {
+            let _ = writeln!(report, "  {}", action);
+        }
+    }
+
+    let _ = writeln!(report, "\nconfig:\n{}", CONFIG.get().map_or("unknown", String::as_str));
+    let _ = writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture());
+
+    fs::create_dir_all(&dir)?;
+    fs::write(&file, report)?;
+    Ok(file)
+}
@@ -0,0 +1,86 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+};
+
+use crate::pool::{CancelToken, Priority, WorkerPool};
+
+/// How an encrypted file was encrypted, going by its extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cipher {
+    Gpg,
+    Age,
+}
+
+impl Cipher {
+    pub fn of(path: &Path) -> Option<Cipher> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "gpg" | "pgp" => Some(Cipher::Gpg),
+            "age" => Some(Cipher::Age),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cipher::Gpg => "gpg",
+            Cipher::Age => "age",
+        }
+    }
+}
+
+/// Decrypt `path` with the `gpg` or `age` command, keeping the plaintext in
+/// memory. age needs `identity`; gpg asks its agent for the key. Errors are
+/// the tool's own explanation, skipping the warnings that come before it.
+pub fn decrypt(path: &Path, cipher: Cipher, identity: Option<&Path>) -> Result<String, String> {
+    let mut command = Command::new(cipher.name());
+    match cipher {
+        // A terminal pinentry would draw over the UI, so without a
+        // graphical one only keys the agent has unlocked can be used
+        Cipher::Gpg => {
+            command.args(["--batch", "--quiet", "--decrypt"]).env_remove("GPG_TTY");
+        }
+        Cipher::Age => {
+            let identity = identity.ok_or("age needs an identity file (--age-identity)")?;
+            command.arg("--decrypt").arg("--identity").arg(identity);
+        }
+    }
+    let output = command
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("cannot run {}: {}", cipher.name(), err))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let reason = lines
+            .iter()
+            .find(|line| line.contains("error") || line.contains("failed"))
+            .or(lines.last());
+        return Err(reason.map_or("decryption failed", |line| line).to_string());
+    }
+    match String::from_utf8(output.stdout) {
+        Ok(text) if !text.contains('\0') => Ok(text),
+        _ => Err("the plaintext is binary".to_string()),
+    }
+}
+
+/// Decrypt `path` on the worker pool, sending the result back as
+/// `(path, result)` unless `token` is cancelled first.
+pub fn spawn_decrypt(
+    pool: &WorkerPool,
+    path: PathBuf,
+    cipher: Cipher,
+    identity: Option<PathBuf>,
+    token: CancelToken,
+    tx: Sender<(PathBuf, Result<String, String>)>,
+) {
+    pool.spawn(Priority::High, token, move |token| {
+        let result = decrypt(&path, cipher, identity.as_deref());
+        if !token.is_cancelled() {
+            let _ = tx.send((path, result));
+        }
+    });
+}
@@ -0,0 +1,178 @@
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn parse(word: &str) -> Option<Severity> {
+        match word.to_lowercase().as_str() {
+            "error" | "fatal error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "note" | "help" | "info" => Some(Severity::Note),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+        f.pad(name)
+    }
+}
+
+/// A single compiler or linter message pointing at a source location.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// One-line summary used as the list label.
+    pub fn label(&self) -> String {
+        let location = match self.column {
+            Some(column) => format!("{}:{}:{}", self.path.display(), self.line, column),
+            None => format!("{}:{}", self.path.display(), self.line),
+        };
+        format!("{:<7} {} {}", self.severity, location, self.message)
+    }
+}
+
+/// Parse compiler output into diagnostics. Understands rustc/cargo's
+/// `--> file:line:col` blocks, the gcc/clang `file:line:col: severity: msg`
+/// format, and eslint's default (stylish) and compact formatters.
+pub fn parse(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // rustc prints the headline before the location
+    let mut pending_rustc: Option<(Severity, String)> = None;
+    // eslint's stylish format prints the file name on its own line
+    let mut eslint_file: Option<PathBuf> = None;
+
+    for raw_line in output.lines() {
+        let line = crate::ansi::strip(raw_line);
+        let trimmed = line.trim();
+
+        if let Some((severity, message)) = parse_rustc_headline(&line) {
+            pending_rustc = Some((severity, message));
+            continue;
+        }
+
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            if let Some((severity, message)) = pending_rustc.take()
+                && let Some((path, line, column)) = parse_location(location)
+            {
+                diagnostics.push(Diagnostic { path, line, column, severity, message });
+            }
+            continue;
+        }
+
+        if let Some(diagnostic) = parse_gcc(trimmed).or_else(|| parse_eslint_compact(trimmed)) {
+            diagnostics.push(diagnostic);
+            continue;
+        }
+
+        if let Some(path) = &eslint_file
+            && let Some(diagnostic) = parse_eslint_stylish(path, trimmed)
+        {
+            diagnostics.push(diagnostic);
+            continue;
+        }
+
+        // An unindented, existing path starts a new eslint stylish block
+        if !line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            let path = PathBuf::from(trimmed);
+            eslint_file = path.is_file().then_some(path);
+        }
+    }
+
+    diagnostics
+}
+
+/// `error[E0308]: mismatched types` / `warning: unused variable`
+fn parse_rustc_headline(line: &str) -> Option<(Severity, String)> {
+    let (head, message) = line.split_once(": ")?;
+    let word = head.split('[').next()?;
+    // Summary lines such as `warning: 3 warnings emitted` also match here;
+    // they are never followed by `-->` and so never become diagnostics.
+    Some((Severity::parse(word)?, message.to_string()))
+}
+
+/// `src/main.c:12:5: error: message`
+fn parse_gcc(line: &str) -> Option<Diagnostic> {
+    let (path, rest) = line.split_once(':')?;
+    let (line_no, rest) = rest.split_once(':')?;
+    // The column is optional
+    let (column, rest) = match rest.split_once(':') {
+        Some((column, tail)) if column.trim().parse::<usize>().is_ok() => {
+            (column.trim().parse().ok(), tail)
+        }
+        _ => (None, rest),
+    };
+    let (severity, message) = rest.split_once(':')?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(Diagnostic {
+        path: PathBuf::from(path),
+        line: line_no.trim().parse().ok()?,
+        column,
+        severity: Severity::parse(severity.trim())?,
+        message: message.trim().to_string(),
+    })
+}
+
+/// `/path/file.js: line 12, col 5, Error - message (rule)`
+fn parse_eslint_compact(line: &str) -> Option<Diagnostic> {
+    let (path, rest) = line.split_once(": line ")?;
+    let (line_no, rest) = rest.split_once(", col ")?;
+    let (column, rest) = rest.split_once(", ")?;
+    let (severity, message) = rest.split_once(" - ")?;
+    Some(Diagnostic {
+        path: PathBuf::from(path),
+        line: line_no.parse().ok()?,
+        column: column.parse().ok(),
+        severity: Severity::parse(severity)?,
+        message: message.to_string(),
+    })
+}
+
+/// `  12:5  error  'x' is not defined  no-undef` under a file heading
+fn parse_eslint_stylish(path: &std::path::Path, line: &str) -> Option<Diagnostic> {
+    let mut words = line.split_whitespace();
+    let (line_no, column) = words.next()?.split_once(':')?;
+    let severity = Severity::parse(words.next()?)?;
+    let message = words.collect::<Vec<_>>().join(" ");
+    Some(Diagnostic {
+        path: path.to_path_buf(),
+        line: line_no.parse().ok()?,
+        column: column.parse().ok(),
+        severity,
+        message,
+    })
+}
+
+/// `file:line[:col]`
+fn parse_location(location: &str) -> Option<(PathBuf, usize, Option<usize>)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let last = parts.next()?.parse().ok()?;
+    let middle = parts.next()?;
+    match (middle.parse::<usize>(), parts.next()) {
+        (Ok(line), Some(path)) => Some((PathBuf::from(path), line, Some(last))),
+        _ => {
+            let path = location.trim().rsplit_once(':')?.0;
+            Some((PathBuf::from(path), last, None))
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fs::File,
+    io,
+    iter,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    plan::{FileOp, Plan},
+    walk::walk_files,
+};
+
+/// How a file differs between the two trees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    OnlyLeft,
+    OnlyRight,
+    /// In both, with a different size or content
+    Differs,
+}
+
+#[derive(Debug, Clone)]
+pub struct Difference {
+    /// Path below both roots
+    pub relative: PathBuf,
+    pub change: Change,
+}
+
+/// Files that differ between two directory trees, like `rsync --dry-run`.
+/// Hidden and build/dependency directories are skipped, as in every scan.
+#[derive(Debug, Clone)]
+pub struct DirDiff {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// Sorted by path, as many as have been found so far
+    pub differences: Vec<Difference>,
+}
+
+impl DirDiff {
+    pub fn count(&self, change: Change) -> usize {
+        self.differences.iter().filter(|difference| difference.change == change).count()
+    }
+
+    /// The file a difference is listed under: the left one unless it is
+    /// only on the right.
+    pub fn path(&self, difference: &Difference) -> PathBuf {
+        match difference.change {
+            Change::OnlyRight => self.right.join(&difference.relative),
+            Change::OnlyLeft | Change::Differs => self.left.join(&difference.relative),
+        }
+    }
+
+    pub fn label(&self, difference: &Difference) -> String {
+        let marker = match difference.change {
+            Change::OnlyLeft => "<  only left ",
+            Change::OnlyRight => " > only right",
+            Change::Differs => "<> differs   ",
+        };
+        format!("{}  {}", marker, difference.relative.display())
+    }
+
+    /// Copy every file missing on one side from the other. Files that
+    /// differ are left alone, as there is no telling which side is right.
+    pub fn copy_missing(&self) -> Plan {
+        let operations = self
+            .differences
+            .iter()
+            .filter_map(|difference| {
+                let (from, to) = match difference.change {
+                    Change::OnlyLeft => (&self.left, &self.right),
+                    Change::OnlyRight => (&self.right, &self.left),
+                    Change::Differs => return None,
+                };
+                Some(FileOp::Copy {
+                    from: from.join(&difference.relative),
+                    to: to.join(&difference.relative),
+                })
+            })
+            .collect();
+        Plan { operations }
+    }
+}
+
+/// The files that differ between the trees under `left` and `right`, by
+/// path below them. Both trees are walked up front; the files in both are
+/// compared as the differences are taken.
+pub fn differences(left: &Path, right: &Path) -> impl Iterator<Item = Difference> + Send + use<> {
+    let relative = |root: &Path| -> BTreeMap<PathBuf, PathBuf> {
+        walk_files(root)
+            .into_iter()
+            .filter_map(|path| Some((path.strip_prefix(root).ok()?.to_path_buf(), path)))
+            .collect()
+    };
+    let mut left_files = relative(left).into_iter().peekable();
+    let mut right_files = relative(right).into_iter().peekable();
+    // Merge the two sorted lists, which keeps the differences sorted too
+    iter::from_fn(move || {
+        loop {
+            let order = match (left_files.peek(), right_files.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((left, _)), Some((right, _))) => left.cmp(right),
+            };
+            let (relative, change) = match order {
+                Ordering::Less => (left_files.next()?.0, Change::OnlyLeft),
+                Ordering::Greater => (right_files.next()?.0, Change::OnlyRight),
+                Ordering::Equal => {
+                    let (relative, left_file) = left_files.next()?;
+                    let (_, right_file) = right_files.next()?;
+                    if same_content(&left_file, &right_file) {
+                        continue;
+                    }
+                    (relative, Change::Differs)
+                }
+            };
+            return Some(Difference { relative, change });
+        }
+    })
+}
+
+/// Whether two files hold the same bytes, hashing them only when the sizes
+/// match. Unreadable files count as different.
+fn same_content(a: &Path, b: &Path) -> bool {
+    let (Ok(a_meta), Ok(b_meta)) = (a.metadata(), b.metadata()) else {
+        return false;
+    };
+    if a_meta.len() != b_meta.len() {
+        return false;
+    }
+    let hash = |path: &Path| -> io::Result<Vec<u8>> {
+        let mut hasher = Sha256::new();
+        io::copy(&mut File::open(path)?, &mut hasher)?;
+        Ok(hasher.finalize().to_vec())
+    };
+    matches!((hash(a), hash(b)), (Ok(a), Ok(b)) if a == b)
+}
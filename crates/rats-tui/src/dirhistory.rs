@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+/// A directory left behind, and the entry that was selected in it.
+#[derive(Debug, Clone)]
+pub struct Visit {
+    pub dir: PathBuf,
+    pub selected: Option<PathBuf>,
+}
+
+/// The directories browsed this session, to step back and forward through
+/// like a web browser's history.
+#[derive(Debug, Default)]
+pub struct DirHistory {
+    current: Option<PathBuf>,
+    back: Vec<Visit>,
+    forward: Vec<Visit>,
+}
+
+impl DirHistory {
+    /// Note that `dir` is being browsed, leaving the current directory with
+    /// `selected` selected. Going somewhere new drops the forward history.
+    /// Returns whether `dir` wasn't the current directory already.
+    pub fn visit(&mut self, dir: &Path, selected: Option<PathBuf>) -> bool {
+        if self.current.as_deref() == Some(dir) {
+            return false;
+        }
+        if let Some(left) = self.current.replace(dir.to_path_buf()) {
+            self.back.push(Visit { dir: left, selected });
+            self.forward.clear();
+        }
+        true
+    }
+
+    /// Step back, leaving the current directory with `selected` selected.
+    /// Returns where to go, or `None` at the start of the history.
+    pub fn back(&mut self, selected: Option<PathBuf>) -> Option<Visit> {
+        let visit = self.back.pop()?;
+        if let Some(left) = self.current.replace(visit.dir.clone()) {
+            self.forward.push(Visit { dir: left, selected });
+        }
+        Some(visit)
+    }
+
+    /// Step forward again after `back`.
+    pub fn forward(&mut self, selected: Option<PathBuf>) -> Option<Visit> {
+        let visit = self.forward.pop()?;
+        if let Some(left) = self.current.replace(visit.dir.clone()) {
+            self.back.push(Visit { dir: left, selected });
+        }
+        Some(visit)
+    }
+}
@@ -0,0 +1,135 @@
+use std::{env, fs, path::PathBuf};
+
+/// Expand a path typed by the user: a leading `@alias` (defined in
+/// `~/.config/rats/aliases`), a leading `~`, and `$VAR` or `${VAR}`
+/// anywhere. Unknown aliases and unset variables are left as written.
+pub fn expand(input: &str) -> String {
+    let input = expand_alias(input);
+    let input = expand_tilde(&input);
+    expand_vars(&input)
+}
+
+fn expand_alias(input: &str) -> String {
+    let Some(rest) = input.strip_prefix('@') else {
+        return input.to_string();
+    };
+    let (name, tail) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, ""),
+    };
+    match aliases().into_iter().find(|(alias, _)| alias == name) {
+        Some((_, target)) => format!("{}{}", target, tail),
+        None => input.to_string(),
+    }
+}
+
+fn expand_tilde(input: &str) -> String {
+    let Some(home) = env::var_os("HOME") else {
+        return input.to_string();
+    };
+    let home = home.to_string_lossy();
+    if input == "~" {
+        home.into_owned()
+    } else if let Some(rest) = input.strip_prefix("~/") {
+        format!("{}/{}", home.trim_end_matches('/'), rest)
+    } else {
+        input.to_string()
+    }
+}
+
+fn expand_vars(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => out.push_str(&value),
+            _ => out.push_str(&rest[dollar..dollar + 1 + consumed]),
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split `input` into words the way a shell would: on whitespace, except
+/// inside single or double quotes or after a backslash. Nothing is expanded.
+pub fn split_words(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"'), '\\') => word.extend(chars.next()),
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Aliases from `$XDG_CONFIG_HOME/rats/aliases` (or `~/.config/rats/aliases`),
+/// one `name = path` per line, `#` starting a comment.
+fn aliases() -> Vec<(String, String)> {
+    let Some(file) = config_dir().map(|dir| dir.join("aliases")) else {
+        return Vec::new();
+    };
+    let content = fs::read_to_string(file).unwrap_or_default();
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, path)| {
+            let name = name.trim().trim_start_matches('@').to_string();
+            // Alias targets may use `~` and variables themselves
+            let path = expand_vars(&expand_tilde(path.trim()));
+            (name, path)
+        })
+        .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+        .collect()
+}
+
+/// Per-user configuration directory: `$XDG_CONFIG_HOME/rats` or
+/// `~/.config/rats`.
+pub fn config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) if !config.is_empty() => Some(PathBuf::from(config).join("rats")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/rats")),
+    }
+}
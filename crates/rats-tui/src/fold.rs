@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+
+use crate::ansi;
+
+/// Indentation width of each line of `content` (tabs count as four
+/// columns), or `None` for blank lines, which never start or end a fold.
+pub fn indent_levels(content: &str) -> Vec<Option<usize>> {
+    ansi::strip(content)
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return None;
+            }
+            let width = line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum();
+            Some(width)
+        })
+        .collect()
+}
+
+/// End (exclusive) of the block started by `start`: the following lines
+/// indented deeper than it, including blank lines between them. `None` when
+/// nothing is nested under the line.
+pub fn fold_end(levels: &[Option<usize>], start: usize) -> Option<usize> {
+    let level = (*levels.get(start)?)?;
+    let mut end = None;
+    for (i, line_level) in levels.iter().enumerate().skip(start + 1) {
+        match line_level {
+            None => continue,
+            Some(l) if *l > level => end = Some(i + 1),
+            Some(_) => break,
+        }
+    }
+    end
+}
+
+/// The innermost block containing `line`, as its start line. A line that
+/// starts a block of its own counts as that block.
+pub fn enclosing_fold(levels: &[Option<usize>], line: usize) -> Option<usize> {
+    (0..=line)
+        .rev()
+        .find(|&start| fold_end(levels, start).is_some_and(|end| end > line))
+}
+
+/// Every line that starts a block.
+pub fn all_folds(levels: &[Option<usize>]) -> BTreeSet<usize> {
+    (0..levels.len())
+        .filter(|&start| fold_end(levels, start).is_some())
+        .collect()
+}
+
+/// Lines left visible by the folds in `folded`, each with the number of
+/// lines hidden under it.
+pub fn visible_lines(levels: &[Option<usize>], folded: &BTreeSet<usize>) -> Vec<(usize, usize)> {
+    let mut visible = Vec::with_capacity(levels.len());
+    let mut line = 0;
+    while line < levels.len() {
+        if folded.contains(&line)
+            && let Some(end) = fold_end(levels, line)
+        {
+            visible.push((line, end - line - 1));
+            line = end;
+        } else {
+            visible.push((line, 0));
+            line += 1;
+        }
+    }
+    visible
+}
@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+/// Frames drawn per second at most, however fast state changes.
+pub const TARGET_FPS: u32 = 60;
+
+/// How long to wait for input when nothing needs drawing, which is also how
+/// often results from background work are picked up.
+const IDLE_TICK: Duration = Duration::from_millis(50);
+
+/// Decides when to draw: only when something changed since the last frame,
+/// and no more often than the target frame rate. Changes arriving between
+/// frames (key repeats, background results) are batched into one draw.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    interval: Duration,
+    last_frame: Option<Instant>,
+    dirty: bool,
+}
+
+impl FrameScheduler {
+    pub fn new(fps: u32) -> FrameScheduler {
+        FrameScheduler {
+            interval: Duration::from_secs(1) / fps.max(1),
+            last_frame: None,
+            // The first frame is always drawn
+            dirty: true,
+        }
+    }
+
+    /// Record that visible state changed and a frame is needed.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether a frame should be drawn now.
+    pub fn should_draw(&self, now: Instant) -> bool {
+        self.dirty
+            && self
+                .last_frame
+                .is_none_or(|last| now.duration_since(last) >= self.interval)
+    }
+
+    pub fn drawn(&mut self, now: Instant) {
+        self.dirty = false;
+        self.last_frame = Some(now);
+    }
+
+    /// How long the event loop may block waiting for input before it has
+    /// to come back to draw a pending frame or poll background work.
+    pub fn timeout(&self, now: Instant) -> Duration {
+        match self.last_frame {
+            Some(last) if self.dirty => (last + self.interval).saturating_duration_since(now),
+            _ if self.dirty => Duration::ZERO,
+            _ => IDLE_TICK,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    history::{now, state_dir, time_ago},
+    names::{path_from_bytes, path_to_bytes},
+};
+
+/// Directories kept in the file, the least frecent dropped first.
+const MAX_DIRS: usize = 1000;
+
+/// A directory browsed before, how often and how lately.
+#[derive(Debug, Clone)]
+pub struct Visited {
+    pub dir: PathBuf,
+    pub visits: u64,
+    /// Seconds since the Unix epoch
+    pub last: u64,
+}
+
+impl Visited {
+    /// Visits weighted by how recent the last one was, as zoxide does.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let weight = match now.saturating_sub(self.last) {
+            0..3600 => 4.0,
+            3600..86400 => 2.0,
+            86400..604800 => 0.5,
+            _ => 0.25,
+        };
+        self.visits as f64 * weight
+    }
+
+    /// List label, e.g. `3h ago  /src/rats`.
+    pub fn label(&self) -> String {
+        format!("{:>8}  {}", time_ago(self.last), self.dir.display())
+    }
+}
+
+fn dirs_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("dirs"))
+}
+
+/// Count a visit to `dir`.
+pub fn record(dir: &Path) -> io::Result<()> {
+    let file = dirs_file().ok_or_else(|| io::Error::other("no state directory"))?;
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let now = now();
+    let mut dirs = load();
+    match dirs.iter_mut().find(|visited| visited.dir == dir) {
+        Some(visited) => {
+            visited.visits += 1;
+            visited.last = now;
+        }
+        None => dirs.push(Visited { dir, visits: 1, last: now }),
+    }
+    sort(&mut dirs, now);
+    dirs.truncate(MAX_DIRS);
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Paths are written as raw bytes so ones that aren't UTF-8 survive
+    let mut content = Vec::new();
+    for visited in &dirs {
+        write!(content, "{}\t{}\t", visited.visits, visited.last)?;
+        content.write_all(&path_to_bytes(&visited.dir))?;
+        content.write_all(b"\n")?;
+    }
+    fs::write(file, content)
+}
+
+/// Every directory visited, most frecent first.
+pub fn load() -> Vec<Visited> {
+    let Some(content) = dirs_file().and_then(|file| fs::read(file).ok()) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<Visited> = content
+        .split(|&b| b == b'\n')
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, |&b| b == b'\t');
+            let (Some(visits), Some(last), Some(dir)) = (fields.next(), fields.next(), fields.next()) else {
+                return None;
+            };
+            Some(Visited {
+                visits: std::str::from_utf8(visits).ok()?.parse().ok()?,
+                last: std::str::from_utf8(last).ok()?.parse().ok()?,
+                dir: path_from_bytes(dir),
+            })
+        })
+        .collect();
+    sort(&mut dirs, now());
+    dirs
+}
+
+fn sort(dirs: &mut [Visited], now: u64) {
+    dirs.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)).then(b.last.cmp(&a.last)));
+}
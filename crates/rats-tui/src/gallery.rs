@@ -0,0 +1,87 @@
+use ratatui::layout::{Position, Rect};
+
+/// Size of one gallery cell, borders included.
+pub const CELL_WIDTH: u16 = 20;
+pub const CELL_HEIGHT: u16 = 10;
+
+/// Rows at the bottom of a cell for the name and image size.
+pub const CAPTION_ROWS: u16 = 2;
+
+/// Terminal cells are roughly twice as tall as they are wide.
+const CELL_ASPECT: f64 = 2.0;
+
+/// Layout of the gallery grid, recorded on each draw for keyboard
+/// navigation and hit testing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Grid {
+    pub area: Rect,
+    pub columns: usize,
+    pub rows: usize,
+    /// Index of the first visible row
+    pub first_row: usize,
+}
+
+impl Grid {
+    pub fn new(area: Rect, first_row: usize) -> Grid {
+        Grid {
+            area,
+            columns: (area.width / CELL_WIDTH).max(1) as usize,
+            rows: (area.height / CELL_HEIGHT).max(1) as usize,
+            first_row,
+        }
+    }
+
+    /// Scroll as little as possible to show the cell of `index`.
+    pub fn scroll_to(&mut self, index: usize) {
+        let row = index / self.columns;
+        if row < self.first_row {
+            self.first_row = row;
+        } else if row >= self.first_row + self.rows {
+            self.first_row = row + 1 - self.rows;
+        }
+    }
+
+    /// Indexes of the entries with a visible cell.
+    pub fn visible(&self, len: usize) -> std::ops::Range<usize> {
+        let start = (self.first_row * self.columns).min(len);
+        let end = ((self.first_row + self.rows) * self.columns).min(len);
+        start..end
+    }
+
+    /// Screen area of the cell for `index`, if it is visible.
+    pub fn cell(&self, index: usize) -> Option<Rect> {
+        let row = (index / self.columns).checked_sub(self.first_row)?;
+        if row >= self.rows {
+            return None;
+        }
+        let column = index % self.columns;
+        Some(Rect::new(
+            self.area.x + column as u16 * CELL_WIDTH,
+            self.area.y + row as u16 * CELL_HEIGHT,
+            CELL_WIDTH,
+            CELL_HEIGHT,
+        ))
+    }
+
+    /// Index of the entry whose cell contains `position`.
+    pub fn index_at(&self, position: Position) -> Option<usize> {
+        if !self.area.contains(position) {
+            return None;
+        }
+        let column = ((position.x - self.area.x) / CELL_WIDTH) as usize;
+        let row = ((position.y - self.area.y) / CELL_HEIGHT) as usize;
+        (column < self.columns).then_some((self.first_row + row) * self.columns + column)
+    }
+}
+
+/// Size in cells to draw an image of `pixels` at, as large as fits in
+/// `cells` without distorting it.
+pub fn fit(pixels: (u32, u32), cells: (u16, u16)) -> (u16, u16) {
+    let (width, height) = (pixels.0.max(1) as f64, pixels.1.max(1) as f64);
+    let (columns, rows) = (cells.0 as f64, cells.1 as f64 * CELL_ASPECT);
+    let scale = (columns / width).min(rows / height);
+    (
+        ((width * scale).round() as u16).clamp(1, cells.0),
+        ((height * scale / CELL_ASPECT).round() as u16).clamp(1, cells.1),
+    )
+}
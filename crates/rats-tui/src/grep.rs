@@ -0,0 +1,118 @@
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{names::path_from_bytes, preview::LARGE_FILE_BYTES, walk};
+
+/// Longest matched line kept for the list; minified files would otherwise
+/// fill the list with a single entry.
+const MAX_TEXT_CHARS: usize = 500;
+
+/// A line containing the searched text.
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// Relative to the searched directory
+    pub path: PathBuf,
+    pub line: usize,
+    pub text: String,
+}
+
+impl Match {
+    /// `path:line: text`, as listed.
+    pub fn label(&self) -> String {
+        format!("{}:{}: {}", self.path.display(), self.line, self.text)
+    }
+
+    fn new(path: PathBuf, line: usize, text: &str) -> Match {
+        Match {
+            path,
+            line,
+            text: text.trim().chars().take(MAX_TEXT_CHARS).collect(),
+        }
+    }
+}
+
+/// Arguments making `rg` search the current directory for `pattern` as
+/// plain text, printing lines `parse_rg` understands. Without `gitignore`,
+/// files ignore files exclude are searched too.
+pub fn rg_args(pattern: &str, gitignore: bool) -> Vec<String> {
+    ["--line-number", "--no-heading", "--null", "--color", "never", "--smart-case", "--fixed-strings"]
+        .into_iter()
+        .chain((!gitignore).then_some("--no-ignore"))
+        .map(str::to_string)
+        .chain(["-e".to_string(), pattern.to_string(), ".".to_string()])
+        .collect()
+}
+
+/// Parse a line of `rg_args` output: the path, a NUL, then `line:text`.
+pub fn parse_rg(line: &[u8]) -> Option<Match> {
+    let nul = line.iter().position(|&b| b == 0)?;
+    let path = path_from_bytes(&line[..nul]);
+    let rest = String::from_utf8_lossy(&line[nul + 1..]);
+    let (number, text) = rest.split_once(':')?;
+    let path = path.strip_prefix(".").map(Path::to_path_buf).unwrap_or(path);
+    Some(Match::new(path, number.parse().ok()?, text))
+}
+
+/// Search the text files under `root` for `pattern` without ripgrep,
+/// ignoring case unless the pattern has capitals (like `rg --smart-case`).
+/// Files are read as the iterator is advanced; `gitignore` is as for
+/// `walk::files`.
+pub fn search(root: PathBuf, pattern: String, gitignore: bool) -> impl Iterator<Item = Match> {
+    let ignore_case = !pattern.chars().any(char::is_uppercase);
+    let pattern = if ignore_case { pattern.to_lowercase() } else { pattern };
+    walk::files(root.clone(), gitignore).flat_map(move |path| {
+        let relative = path.strip_prefix(&root).map(Path::to_path_buf).unwrap_or_else(|_| path.clone());
+        search_file(&path, &pattern, ignore_case)
+            .into_iter()
+            .map(move |(line, text)| Match::new(relative.clone(), line, &text))
+    })
+}
+
+/// Byte ranges of `text` where `pattern` occurs, with the same smart case
+/// as `search`.
+pub fn find_all(text: &str, pattern: &str) -> Vec<Range<usize>> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let ignore_case = !pattern.chars().any(char::is_uppercase);
+    let lower;
+    let (haystack, needle) = match ignore_case {
+        // Offsets into the lowercased text only carry over if lowercasing
+        // kept every character the same length
+        true if text.to_lowercase().len() == text.len() => {
+            lower = (text.to_lowercase(), pattern.to_lowercase());
+            (lower.0.as_str(), lower.1.as_str())
+        }
+        _ => (text, pattern),
+    };
+    haystack
+        .match_indices(needle)
+        .map(|(start, found)| start..start + found.len())
+        .collect()
+}
+
+/// Lines of `path` containing `pattern`, skipping large, binary and
+/// non-UTF-8 files.
+fn search_file(path: &Path, pattern: &str, ignore_case: bool) -> Vec<(usize, String)> {
+    if fs::metadata(path).map_or(true, |meta| meta.len() > LARGE_FILE_BYTES * 20) {
+        return Vec::new();
+    }
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    if content.contains('\0') {
+        return Vec::new();
+    }
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| match ignore_case {
+            true => line.to_lowercase().contains(pattern),
+            false => line.contains(pattern),
+        })
+        .map(|(i, line)| (i + 1, line.to_string()))
+        .collect()
+}
@@ -0,0 +1,129 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    app::Selection,
+    names::{path_from_bytes, path_to_bytes},
+};
+
+/// Entries kept when the history file is compacted.
+const MAX_ENTRIES: usize = 1000;
+
+/// Size past which the history file is compacted, far more than
+/// `MAX_ENTRIES` entries take.
+const COMPACT_BYTES: u64 = 1024 * 1024;
+
+/// A previously accepted selection.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl HistoryEntry {
+    /// One-line summary used as the list label, e.g. `3h ago  /src/main.rs`.
+    pub fn label(&self) -> String {
+        let location = match self.line {
+            Some(line) => format!("{}:{}", self.path.display(), line),
+            None => self.path.display().to_string(),
+        };
+        format!("{:>8}  {}", time_ago(self.timestamp), location)
+    }
+}
+
+/// Per-user state directory: `$XDG_STATE_HOME/rats` or `~/.local/state/rats`.
+pub fn state_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_STATE_HOME") {
+        Some(state) if !state.is_empty() => Some(PathBuf::from(state).join("rats")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state/rats")),
+    }
+}
+
+fn history_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("history"))
+}
+
+/// Append an accepted selection to the history file.
+pub fn record(selection: &Selection) -> io::Result<()> {
+    let file = history_file().ok_or_else(|| io::Error::other("no state directory"))?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let path = selection.path.canonicalize().unwrap_or_else(|_| selection.path.clone());
+    let mut out = OpenOptions::new().create(true).append(true).open(&file)?;
+    write_entry(&mut out, now(), selection.line, &path)?;
+    let size = out.metadata()?.len();
+    drop(out);
+
+    // Keep the file from growing without bound. Accepting the same paths
+    // again only adds lines, so it's the size that counts, not the entries
+    if size > COMPACT_BYTES {
+        let entries = load();
+        let mut content = Vec::new();
+        for entry in entries.iter().take(MAX_ENTRIES).rev() {
+            write_entry(&mut content, entry.timestamp, entry.line, &entry.path)?;
+        }
+        fs::write(&file, content)?;
+    }
+    Ok(())
+}
+
+/// Paths are written as raw bytes so ones that aren't UTF-8 survive.
+fn write_entry(out: &mut impl Write, timestamp: u64, line: Option<usize>, path: &Path) -> io::Result<()> {
+    let line = line.map(|line| line.to_string()).unwrap_or_default();
+    write!(out, "{}\t{}\t", timestamp, line)?;
+    out.write_all(&path_to_bytes(path))?;
+    out.write_all(b"\n")
+}
+
+/// All recorded selections, most recent first, one entry per location.
+pub fn load() -> Vec<HistoryEntry> {
+    let Some(content) = history_file().and_then(|file| fs::read(file).ok()) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    for line in content.split(|&b| b == b'\n').rev() {
+        let mut fields = line.splitn(3, |&b| b == b'\t');
+        let (Some(timestamp), Some(line_no), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some(timestamp) = std::str::from_utf8(timestamp).ok().and_then(|t| t.parse().ok()) else {
+            continue;
+        };
+        let entry = HistoryEntry {
+            path: path_from_bytes(path),
+            line: std::str::from_utf8(line_no).ok().and_then(|l| l.parse().ok()),
+            timestamp,
+        };
+        if !entries.iter().any(|e| e.path == entry.path && e.line == entry.line) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Coarse relative time such as `just now`, `5m ago` or `2d ago`.
+pub fn time_ago(timestamp: u64) -> String {
+    let elapsed = now().saturating_sub(timestamp);
+    match elapsed {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", elapsed / 60),
+        3600..86400 => format!("{}h ago", elapsed / 3600),
+        _ => format!("{}d ago", elapsed / 86400),
+    }
+}
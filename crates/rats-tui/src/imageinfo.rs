@@ -0,0 +1,188 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Width and height in pixels of a PNG, JPEG, GIF, BMP or WebP image, read
+/// from its header without decoding it.
+pub fn dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mut header = [0u8; 30];
+    let len = read_up_to(&mut file, &mut header).ok()?;
+    let header = &header[..len];
+
+    let le16 = |at: usize| Some(u16::from_le_bytes(header.get(at..at + 2)?.try_into().ok()?) as u32);
+    let be32 = |at: usize| Some(u32::from_be_bytes(header.get(at..at + 4)?.try_into().ok()?));
+    let le32 = |at: usize| Some(i32::from_le_bytes(header.get(at..at + 4)?.try_into().ok()?));
+    let le24 = |at: usize| {
+        let bytes = header.get(at..at + 3)?;
+        Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+    };
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some((be32(16)?, be32(20)?))
+    } else if header.starts_with(b"GIF8") {
+        Some((le16(6)?, le16(8)?))
+    } else if header.starts_with(b"BM") {
+        // Height is negative for top-down bitmaps
+        Some((le32(18)?.unsigned_abs(), le32(22)?.unsigned_abs()))
+    } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP") {
+        match header.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = header.get(21..25)?;
+                let width = 1 + (bits[0] as u32 | (bits[1] as u32 & 0x3f) << 8);
+                let height = 1
+                    + ((bits[1] as u32) >> 6 | (bits[2] as u32) << 2 | (bits[3] as u32 & 0x0f) << 10);
+                Some((width, height))
+            }
+            b"VP8X" => Some((1 + le24(24)?, 1 + le24(27)?)),
+            _ => None,
+        }
+    } else if header.starts_with(b"\xff\xd8") {
+        file.seek(SeekFrom::Start(2)).ok()?;
+        jpeg_dimensions(&mut file)
+    } else {
+        None
+    }
+}
+
+/// Walk JPEG segments up to the start-of-frame, which holds the size.
+fn jpeg_dimensions(file: &mut (impl Read + Seek)) -> Option<(u32, u32)> {
+    // SOF0-SOF15, except DHT (c4), JPG (c8) and DAC (cc)
+    find_jpeg_segment(file, |marker| {
+        matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc)
+    })?;
+    let mut frame = [0u8; 5];
+    file.read_exact(&mut frame).ok()?;
+    let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+    let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+    Some((width, height))
+}
+
+/// Skip ahead to the next JPEG segment whose marker is `wanted`, leaving
+/// the reader at its content. Returns the marker and the content length.
+fn find_jpeg_segment(file: &mut (impl Read + Seek), wanted: impl Fn(u8) -> bool) -> Option<(u8, u64)> {
+    loop {
+        let mut marker = [0u8; 2];
+        file.read_exact(&mut marker).ok()?;
+        if marker[0] != 0xff {
+            return None;
+        }
+        // Padding bytes and markers without a length
+        if marker[1] == 0xff {
+            file.seek(SeekFrom::Current(-1)).ok()?;
+            continue;
+        }
+        if matches!(marker[1], 0xd0..=0xd9 | 0x01) {
+            continue;
+        }
+        let mut length = [0u8; 2];
+        file.read_exact(&mut length).ok()?;
+        let length = (u16::from_be_bytes(length) as u64).saturating_sub(2);
+        if wanted(marker[1]) {
+            return Some((marker[1], length));
+        }
+        file.seek(SeekFrom::Current(length as i64)).ok()?;
+    }
+}
+
+/// Extensions of files that may carry EXIF data: JPEGs and TIFF-based
+/// camera raw formats.
+const EXIF_EXTENSIONS: &[&str] = &["jpg", "jpeg", "tif", "tiff", "dng", "nef", "cr2", "arw", "orf", "rw2"];
+
+/// How much of a TIFF-based file is searched for EXIF tags.
+const TIFF_HEAD_BYTES: u64 = 512 * 1024;
+
+pub fn has_exif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXIF_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// When a photo was taken, from EXIF `DateTimeOriginal` (or failing that
+/// `DateTimeDigitized` or `DateTime`), as `YYYY-MM-DD HH:MM:SS`.
+pub fn capture_date(path: &Path) -> Option<String> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).ok()?;
+    let tiff = if magic.starts_with(b"\xff\xd8") {
+        file.seek(SeekFrom::Start(2)).ok()?;
+        jpeg_exif(&mut file)?
+    } else if &magic == b"II*\0" || &magic == b"MM\0*" {
+        file.seek(SeekFrom::Start(0)).ok()?;
+        let mut head = Vec::new();
+        file.take(TIFF_HEAD_BYTES).read_to_end(&mut head).ok()?;
+        head
+    } else {
+        return None;
+    };
+    exif_date(&tiff)
+}
+
+/// The TIFF structure inside a JPEG's `Exif` APP1 segment.
+fn jpeg_exif(file: &mut (impl Read + Seek)) -> Option<Vec<u8>> {
+    loop {
+        // Metadata segments come before the scan (SOS)
+        let (marker, length) = find_jpeg_segment(file, |marker| marker == 0xe1 || marker == 0xda)?;
+        if marker == 0xda {
+            return None;
+        }
+        let mut segment = vec![0u8; length as usize];
+        file.read_exact(&mut segment).ok()?;
+        if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+            return Some(tiff.to_vec());
+        }
+    }
+}
+
+fn exif_date(tiff: &[u8]) -> Option<String> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let bytes = tiff.get(at..at + 2)?.try_into().ok()?;
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let u32_at = |at: usize| {
+        let bytes = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+    // Offset of the 12-byte IFD entry for `tag`
+    let entry = |ifd: usize, tag: u16| {
+        let count = u16_at(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| u16_at(entry) == Some(tag))
+    };
+    // Dates are 19 ASCII characters plus NUL, stored at an offset
+    let date_at = |entry: usize| {
+        let at = u32_at(entry + 8)? as usize;
+        let raw = std::str::from_utf8(tiff.get(at..at + 19)?).ok()?;
+        let date = raw.replacen(':', "-", 2);
+        (date.as_bytes()[0].is_ascii_digit() && !date.starts_with("0000")).then_some(date)
+    };
+
+    let ifd0 = u32_at(4)? as usize;
+    let exif_ifd = entry(ifd0, 0x8769)
+        .and_then(|entry| u32_at(entry + 8))
+        .map(|offset| offset as usize);
+    exif_ifd
+        .and_then(|ifd| entry(ifd, 0x9003).or_else(|| entry(ifd, 0x9004)))
+        .and_then(date_at)
+        .or_else(|| entry(ifd0, 0x0132).and_then(date_at))
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::state_dir;
+
+/// Size past which the journal is compacted.
+const COMPACT_BYTES: u64 = 128 * 1024;
+
+/// Lines kept when the journal is compacted.
+const MAX_LINES: usize = 200;
+
+/// A snapshot of the session, appended to the session's journal whenever
+/// the directory, query or selection changes. Each session has its own
+/// journal, removed on a clean exit, so one whose session is no longer
+/// running belongs to a crashed session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalState {
+    pub timestamp: u64,
+    pub pid: u32,
+    pub cwd: PathBuf,
+    pub query: String,
+    pub selected: Option<PathBuf>,
+}
+
+/// This session's journal.
+fn journal_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(format!("journal.{}", std::process::id())))
+}
+
+fn crashed_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("journal.crashed"))
+}
+
+/// Set aside the journals left behind by crashed sessions, the latest as
+/// the one to resume, leaving those of sessions still running alone.
+/// Returns true if there were any.
+pub fn preserve_crashed() -> bool {
+    let (Some(dir), Some(crashed)) = (state_dir(), crashed_file()) else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return false;
+    };
+    let mut orphans: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            // `journal` is where sessions before per-session journals wrote
+            path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                name == "journal"
+                    || name.strip_prefix("journal.").is_some_and(|pid| pid.bytes().all(|b| b.is_ascii_digit()))
+            })
+        })
+        .filter_map(|path| {
+            let state = read_last(&path)?;
+            (state.pid != std::process::id() && !is_running(state.pid)).then_some((state.timestamp, path))
+        })
+        .collect();
+    orphans.sort();
+    let Some((_, latest)) = orphans.pop() else {
+        return false;
+    };
+    for (_, path) in orphans {
+        let _ = fs::remove_file(path);
+    }
+    fs::rename(latest, crashed).is_ok()
+}
+
+/// Whether a process with this id exists. Where that can't be asked, every
+/// other session is taken to have ended.
+fn is_running(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+        // SAFETY: signal 0 only checks that the process exists.
+        unsafe { libc::kill(pid, 0) == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM) }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        false
+    }
+}
+
+pub fn append(cwd: PathBuf, query: String, selected: Option<PathBuf>) -> io::Result<()> {
+    let file = journal_file().ok_or_else(|| io::Error::other("no state directory"))?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let state = JournalState {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        pid: std::process::id(),
+        // Relative paths would resolve differently after a restart elsewhere
+        cwd: cwd.canonicalize().unwrap_or(cwd),
+        query,
+        selected: selected.map(|path| path.canonicalize().unwrap_or(path)),
+    };
+    let line = serde_json::to_string(&state).map_err(io::Error::other)?;
+
+    // Keep only the tail of long sessions; the latest state is what matters
+    if fs::metadata(&file).is_ok_and(|meta| meta.len() > COMPACT_BYTES) {
+        let existing = fs::read_to_string(&file)?;
+        let lines: Vec<&str> = existing.lines().collect();
+        let kept = &lines[lines.len().saturating_sub(MAX_LINES)..];
+        fs::write(&file, kept.join("\n") + "\n")?;
+    }
+
+    let mut out = OpenOptions::new().create(true).append(true).open(&file)?;
+    writeln!(out, "{}", line)
+}
+
+/// The last state written by a session that did not exit cleanly.
+pub fn last_state() -> Option<JournalState> {
+    preserve_crashed();
+    read_last(&crashed_file()?)
+}
+
+fn read_last(file: &Path) -> Option<JournalState> {
+    let content = fs::read_to_string(file).ok()?;
+    content
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str(line).ok())
+}
+
+/// Remove this session's journal after a clean exit.
+pub fn clear() {
+    if let Some(file) = journal_file() {
+        let _ = fs::remove_file(file);
+    }
+}
+
+/// Forget the crashed session once it has been resumed.
+pub fn clear_crashed() {
+    if let Some(file) = crashed_file() {
+        let _ = fs::remove_file(file);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{action::Action, mode::Mode};
+
+/// Something happening in the app that actions can be bound to, like a
+/// key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// Another entry was selected
+    Focus,
+    /// A listing was read in full
+    Load,
+    /// Nothing matches the filter any more
+    Zero,
+    /// Exactly one entry matches the filter
+    One,
+}
+
+impl Event {
+    fn from_name(name: &str) -> Option<Event> {
+        Some(match name {
+            "focus" => Event::Focus,
+            "load" => Event::Load,
+            "zero" => Event::Zero,
+            "one" => Event::One,
+            _ => return None,
+        })
+    }
+}
+
+/// Keys bound with `--bind` (or the `[keys]` tables of the config file),
+/// looked up before the built-in keys of a mode, and actions bound to
+/// events.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    /// `None` unbinds the key
+    bindings: HashMap<(Mode, KeyCode, KeyModifiers), Option<Action>>,
+    events: HashMap<Event, Vec<Action>>,
+}
+
+impl Keymap {
+    /// Add bindings like `normal.ctrl-n=next,insert.ctrl-j=next`, separated
+    /// by commas. The mode defaults to normal. An event is bound with a
+    /// colon to actions joined by `+`, like `one:accept` or
+    /// `focus:reload-preview+top`.
+    pub fn bind(&mut self, spec: &str) -> Result<(), String> {
+        let bindings = split_outside_parens(spec, ',');
+        for binding in bindings.into_iter().map(str::trim).filter(|binding| !binding.is_empty()) {
+            if let Some((event, actions)) = binding.split_once(':')
+                && let Some(event) = Event::from_name(event.trim())
+            {
+                let actions = split_outside_parens(actions, '+')
+                    .into_iter()
+                    .map(|name| action_from_name(name.trim()).ok_or_else(|| format!("unknown action: {}", name.trim())))
+                    .collect::<Result<Vec<_>, String>>()?;
+                self.events.insert(event, actions);
+                continue;
+            }
+            let (key, action) = binding
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=action, got {}", binding))?;
+            let (mode, key) = match key.split_once('.') {
+                Some((mode, key)) if !key.is_empty() => (mode_from_name(mode)?, key),
+                _ => (Mode::Normal, key),
+            };
+            let (code, modifiers) = parse_key(key).ok_or_else(|| format!("unknown key: {}", key))?;
+            let action = match action.trim() {
+                "ignore" => None,
+                name => Some(action_from_name(name).ok_or_else(|| format!("unknown action: {}", name))?),
+            };
+            self.bindings.insert((mode, code, modifiers), action);
+        }
+        Ok(())
+    }
+
+    /// What `key` is bound to in `mode`: `Some(None)` if it was unbound,
+    /// `None` if it keeps its built-in meaning.
+    pub fn get(&self, mode: &Mode, key: &KeyEvent) -> Option<Option<Action>> {
+        self.bindings.get(&(mode.clone(), key.code, significant(key.code, key.modifiers))).cloned()
+    }
+
+    /// The actions bound to `event`, in order.
+    pub fn on(&self, event: Event) -> &[Action] {
+        self.events.get(&event).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Parse `--on-start` steps like `toggle-hidden;sort taken;recursive`,
+/// separated by semicolons: each is an action name as for `--bind`, or
+/// else a command as typed after `:`.
+pub fn parse_actions(spec: &str) -> Vec<Action> {
+    split_outside_parens(spec, ';')
+        .into_iter()
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(|step| action_from_name(step).unwrap_or_else(|| Action::RunCommand(step.to_string())))
+        .collect()
+}
+
+/// Split `spec` at `separator`, except inside the parentheses of an action
+/// like `reload(ls a, b; ls c)`.
+fn split_outside_parens(spec: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&spec[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&spec[start..]);
+    parts
+}
+
+/// Shift is part of the character itself (`G`), so only Ctrl and Alt
+/// distinguish keys.
+fn significant(code: KeyCode, modifiers: KeyModifiers) -> KeyModifiers {
+    match code {
+        KeyCode::Char(_) => modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        _ => modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT),
+    }
+}
+
+fn mode_from_name(name: &str) -> Result<Mode, String> {
+    match name {
+        "normal" => Ok(Mode::Normal),
+        "insert" => Ok(Mode::Insert),
+        "command" => Ok(Mode::Command),
+        "plan" => Ok(Mode::Plan),
+        _ => Err(format!("unknown mode: {} (expected normal, insert, command or plan)", name)),
+    }
+}
+
+/// Parse `j`, `G`, `ctrl-n`, `alt-enter`, `f5`, ...
+fn parse_key(name: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    loop {
+        if let Some(after) = rest.strip_prefix("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("shift-") {
+            modifiers |= KeyModifiers::SHIFT;
+            rest = after;
+        } else {
+            break;
+        }
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "comma" => KeyCode::Char(','),
+            "equal" => KeyCode::Char('='),
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => KeyCode::F(rest.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+        },
+    };
+    Some((code, significant(code, modifiers)))
+}
+
+fn action_from_name(name: &str) -> Option<Action> {
+    if let Some(command) = name.strip_prefix("reload(").and_then(|rest| rest.strip_suffix(')')) {
+        return Some(Action::Reload(Some(command.trim().to_string()).filter(|command| !command.is_empty())));
+    }
+    if let Some(command) = name.strip_prefix("execute-silent(").and_then(|rest| rest.strip_suffix(')')) {
+        return Some(Action::Execute(command.trim().to_string())).filter(|_| !command.trim().is_empty());
+    }
+    Some(match name {
+        "quit" => Action::Quit,
+        "next" => Action::Next,
+        "previous" => Action::Previous,
+        "top" => Action::Top,
+        "bottom" => Action::Bottom,
+        "accept" => Action::Accept,
+        "normal-mode" => Action::SetMode(Mode::Normal),
+        "insert-mode" => Action::SetMode(Mode::Insert),
+        "command-mode" => Action::SetMode(Mode::Command),
+        "tag-mode" => Action::SetMode(Mode::Tag),
+        "toggle-preview" => Action::TogglePreview,
+        "toggle-hidden" => Action::ToggleHidden,
+        "toggle-ignored" => Action::ToggleIgnored,
+        "focus-preview" => Action::TogglePreviewFocus,
+        "preview-down" => Action::ScrollPreview(5),
+        "preview-up" => Action::ScrollPreview(-5),
+        "toggle-mark" => Action::ToggleMark,
+        "toggle-pin" => Action::TogglePin,
+        "dismiss" => Action::Dismiss,
+        "undismiss-all" => Action::UndismissAll,
+        "yank" => Action::Yank,
+        "cut" => Action::Cut,
+        "paste" => Action::Paste,
+        "annotate" => Action::Annotate,
+        "export-tree" => Action::ExportTree,
+        "history" => Action::ToggleHistory,
+        "bookmarks" => Action::ToggleBookmarks,
+        "jump" => Action::ToggleJump,
+        "back" => Action::Back,
+        "forward" => Action::Forward,
+        "grep" => Action::ToggleGrep,
+        "open-containing-dir" => Action::OpenContainingDir,
+        "trash" => Action::MoveToTrash,
+        "delete" => Action::Delete,
+        "rename" => Action::Rename,
+        "new-file" => Action::Create(false),
+        "new-directory" => Action::Create(true),
+        "toggle-group" => Action::ToggleGroup,
+        "toggle-all-groups" => Action::ToggleAllGroups,
+        "decrypt" => Action::Decrypt,
+        "reload-preview" => Action::ReloadPreview,
+        "clear-filter" => Action::ClearFilter,
+        "choose" => Action::Choose,
+        "stop-loading" => Action::CancelLoading,
+        "reload" => Action::Reload(None),
+        _ => return None,
+    })
+}
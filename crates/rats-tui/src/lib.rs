@@ -0,0 +1,61 @@
+//! Everything rats shows in the terminal: the app state, its actions,
+//! the widgets drawing it and the features behind them. The binary only
+//! parses arguments and runs the event loop.
+
+pub use rats_core::{backend, entry, fields, fuzzy, names, pool, query, walk};
+
+pub mod action;
+pub mod age;
+pub mod ansi;
+pub mod app;
+pub mod audit;
+pub mod bookmarks;
+pub mod cargo;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
+pub mod config_file;
+pub mod crash;
+pub mod crypt;
+pub mod diagnostics;
+pub mod dirhistory;
+pub mod dirdiff;
+pub mod expand;
+pub mod fold;
+pub mod frecency;
+pub mod frame;
+pub mod gallery;
+pub mod grep;
+pub mod history;
+pub mod imageinfo;
+pub mod journal;
+pub mod keymap;
+pub mod listing;
+pub mod manpages;
+pub mod media;
+pub mod mode;
+pub mod mounts;
+pub mod mouse;
+pub mod notes;
+pub mod pathref;
+pub mod picker;
+pub mod pins;
+pub mod plan;
+pub mod portal;
+pub mod preview;
+pub mod privilege;
+pub mod subtrees;
+pub mod tags;
+pub mod term;
+#[cfg(feature = "termion")]
+pub mod term_termion;
+pub mod thumbnail;
+pub mod timing;
+pub mod todos;
+pub mod tools;
+pub mod trash;
+pub mod tree;
+pub mod ui;
+pub mod update;
+pub mod watch;
+pub mod widgets;
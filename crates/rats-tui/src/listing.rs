@@ -0,0 +1,280 @@
+use std::{
+    fs::{self, FileType},
+    io::{self, BufRead, BufReader, Read},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    dirdiff::{self, Difference},
+    grep::{self, Match},
+    names::path_from_bytes,
+    pool::CancelToken,
+    todos::{self, Todo},
+    walk,
+};
+
+/// How often entries read so far are handed over while a directory is
+/// still being read.
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of a directory read or command run.
+#[derive(Debug)]
+pub enum Listing {
+    /// More entries, in directory order, with their type when the
+    /// filesystem reports it without a `stat`
+    Entries(Vec<(PathBuf, Option<FileType>)>),
+    /// More paths found by a walk, relative to where it started, in order
+    Lines(Vec<String>),
+    /// More entries printed by a command, byte for byte, in order
+    Printed(Vec<PathBuf>),
+    /// More lines found by a content search
+    Matches(Vec<Match>),
+    /// More files found to differ between two trees, in order
+    Differences(Vec<Difference>),
+    /// More TODO-style markers, in the order they were found
+    Todos(Vec<Todo>),
+    /// Every entry has been sent
+    Done,
+    Failed(String),
+}
+
+/// Start reading `dir`, sending entries in batches as they arrive, leaving
+/// out those ignored by git with `gitignore`. Reading stops once `token` is
+/// cancelled.
+///
+/// This runs on its own thread rather than the worker pool: on a hung
+/// network mount or a spun-down disk a read can block for a long time, and
+/// it shouldn't hold up previews meanwhile.
+pub fn spawn(dir: PathBuf, gitignore: bool, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                let _ = tx.send(Listing::Failed(err.to_string()));
+                return;
+            }
+        };
+        let entries: Box<dyn Iterator<Item = _> + Send> = match gitignore {
+            true => Box::new(walk::unignored_entries(&dir)),
+            false => Box::new(entries.map(|entry| entry.map(|entry| (entry.path(), entry.file_type().ok())))),
+        };
+        if send_batched(&tx, entries, &token, Listing::Entries) {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// Start walking the tree under `dir` like `walk::files`, sending the
+/// paths of the files found relative to `dir` in batches. The walk stops
+/// once `token` is cancelled.
+pub fn spawn_walk(dir: PathBuf, gitignore: bool, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let root = dir.clone();
+        let files = walk::files(dir, gitignore).map(move |path| {
+            Ok(path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().into_owned())
+        });
+        if send_batched(&tx, files, &token, Listing::Lines) {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// Start searching the files under `dir` for `pattern`, with ripgrep when
+/// `use_rg`, sending the matches in batches as they are found. The search
+/// stops once `token` is cancelled.
+pub fn spawn_grep(
+    pattern: String,
+    dir: PathBuf,
+    use_rg: bool,
+    gitignore: bool,
+    token: CancelToken,
+) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if !use_rg {
+            let matches = grep::search(dir, pattern, gitignore).map(Ok);
+            if send_batched(&tx, matches, &token, Listing::Matches) {
+                let _ = tx.send(Listing::Done);
+            }
+            return;
+        }
+        let mut child = match Command::new("rg")
+            .args(grep::rg_args(&pattern, gitignore))
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                let _ = tx.send(Listing::Failed(err.to_string()));
+                return;
+            }
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+        let matches = BufReader::new(stdout)
+            .split(b'\n')
+            .filter_map(|line| line.map(|line| grep::parse_rg(&line)).transpose());
+        let finished = send_batched(&tx, matches, &token, Listing::Matches);
+        // rg exits with an error for no matches or an unreadable file, which
+        // don't make the matches found any less complete
+        let _ = child.kill();
+        let _ = child.wait();
+        if finished {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// Start scanning the files under `roots` for TODO-style `tags`, sending
+/// the markers in batches as they are found. The scan stops once `token`
+/// is cancelled.
+pub fn spawn_todos(roots: Vec<PathBuf>, tags: Vec<String>, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if send_batched(&tx, todos::scan(roots, tags).map(Ok), &token, Listing::Todos) {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// Start comparing the trees under `left` and `right`, sending the files
+/// that differ in batches as they are found. The comparison stops once
+/// `token` is cancelled.
+pub fn spawn_diff(left: PathBuf, right: PathBuf, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let differences = dirdiff::differences(&left, &right).map(Ok);
+        if send_batched(&tx, differences, &token, Listing::Differences) {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// Run `command` with `sh -c` in `dir`, sending what it prints in batches
+/// as it comes, split at `delimiter` (a newline, or NUL for `--read0`). The
+/// command is killed once `token` is cancelled.
+pub fn spawn_command(command: String, dir: PathBuf, delimiter: u8, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut child = match Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                let _ = tx.send(Listing::Failed(err.to_string()));
+                return;
+            }
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+        if !send_batched(&tx, split_entries(stdout, delimiter), &token, Listing::Printed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return;
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => {
+                let _ = tx.send(Listing::Failed(format!("`{}` {}", command, status)));
+            }
+            _ => {
+                let _ = tx.send(Listing::Done);
+            }
+        }
+    });
+    rx
+}
+
+/// Read the entries piped on stdin, sending them in batches as they come,
+/// split at `delimiter`. Reading stops once `token` is cancelled.
+pub fn spawn_stdin(delimiter: u8, token: CancelToken) -> Receiver<Listing> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if send_batched(&tx, split_entries(io::stdin(), delimiter), &token, Listing::Printed) {
+            let _ = tx.send(Listing::Done);
+        }
+    });
+    rx
+}
+
+/// The non-empty entries `reader` holds, split at `delimiter`, with the
+/// `\r` of Windows line endings dropped when that is a newline.
+fn split_entries(reader: impl Read, delimiter: u8) -> impl Iterator<Item = io::Result<PathBuf>> {
+    BufReader::new(reader)
+        .split(delimiter)
+        .map(move |entry| {
+            entry.map(|mut entry| {
+                if delimiter == b'\n' && entry.last() == Some(&b'\r') {
+                    entry.pop();
+                }
+                entry
+            })
+        })
+        .filter(|entry| !matches!(entry, Ok(entry) if entry.is_empty()))
+        .map(|entry| entry.map(|entry| path_from_bytes(&entry)))
+}
+
+/// Send `items` wrapped by `batch`: whatever has arrived every
+/// `BATCH_INTERVAL`, then the rest at the end. The items are pulled on
+/// another thread, so a batch goes out on time however long the next item
+/// takes. Returns false if it stopped early, on an error (which is sent)
+/// or once `token` is cancelled.
+fn send_batched<T: Send + 'static>(
+    tx: &Sender<Listing>,
+    items: impl Iterator<Item = io::Result<T>> + Send + 'static,
+    token: &CancelToken,
+    batch: fn(Vec<T>) -> Listing,
+) -> bool {
+    let (item_tx, item_rx) = mpsc::channel();
+    thread::spawn(move || {
+        for item in items {
+            if item_tx.send(item).is_err() {
+                return;
+            }
+        }
+    });
+    let mut pending = Vec::new();
+    let mut deadline = Instant::now() + BATCH_INTERVAL;
+    loop {
+        if token.is_cancelled() {
+            return false;
+        }
+        match item_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Ok(item)) => pending.push(item),
+            Ok(Err(err)) => {
+                let _ = tx.send(Listing::Failed(err.to_string()));
+                return false;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if Instant::now() >= deadline {
+            if !pending.is_empty() && tx.send(batch(std::mem::take(&mut pending))).is_err() {
+                return false;
+            }
+            deadline = Instant::now() + BATCH_INTERVAL;
+        }
+    }
+    tx.send(batch(pending)).is_ok()
+}
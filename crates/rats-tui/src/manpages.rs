@@ -0,0 +1,120 @@
+use std::{path::PathBuf, process::Command, sync::mpsc::Sender};
+
+use crate::pool::{CancelToken, Priority, WorkerPool};
+
+/// Prefix of the references man pages are listed and printed as, like
+/// `man:ls(1)`.
+const SCHEME: &str = "man:";
+
+/// A man page from the `apropos` database.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub name: String,
+    pub section: String,
+    pub description: String,
+}
+
+impl Page {
+    /// `man:name(section)`, what accepting the page prints.
+    pub fn reference(&self) -> String {
+        format!("{}{}({})", SCHEME, self.name, self.section)
+    }
+
+    pub fn label(&self) -> String {
+        format!("{:<28} {}", format!("{}({})", self.name, self.section), self.description)
+    }
+}
+
+/// Every man page `apropos` knows about, by name then section.
+pub fn list() -> Result<Vec<Page>, String> {
+    let output = Command::new("apropos")
+        .arg(".")
+        .output()
+        .or_else(|_| Command::new("man").args(["-k", "."]).output())
+        .map_err(|err| format!("cannot run apropos: {}", err))?;
+    let mut pages: Vec<Page> = String::from_utf8_lossy(&output.stdout).lines().filter_map(parse_line).collect();
+    if pages.is_empty() {
+        return Err("apropos found no man pages (is the man database built?)".to_string());
+    }
+    pages.sort_by(|a, b| (&a.name, &a.section).cmp(&(&b.name, &b.section)));
+    pages.dedup_by(|a, b| a.name == b.name && a.section == b.section);
+    Ok(pages)
+}
+
+/// Parse `ls (1) - list directory contents`. BSD lists several names
+/// before the section (`cat, tac(1)`); the first one is used.
+fn parse_line(line: &str) -> Option<Page> {
+    let (names, description) = line.split_once(" - ")?;
+    let open = names.rfind('(')?;
+    let close = names[open..].find(')')? + open;
+    let name = names[..open].split(',').next()?.trim();
+    let section = names[open + 1..close].trim();
+    if name.is_empty() || section.is_empty() {
+        return None;
+    }
+    Some(Page {
+        name: name.to_string(),
+        section: section.to_string(),
+        description: description.trim().to_string(),
+    })
+}
+
+/// The page `reference` (as made by `Page::reference`) points to, rendered
+/// `width` columns wide, with bold and underlined text as ANSI escapes.
+pub fn render(reference: &str, width: u16) -> Option<String> {
+    let (name, section) = reference.strip_prefix(SCHEME)?.strip_suffix(')')?.split_once('(')?;
+    let output = Command::new("man")
+        .args([section, name])
+        .env("MANWIDTH", width.to_string())
+        .env("MANPAGER", "cat")
+        .env("PAGER", "cat")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(overstrike_to_ansi(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Render the page at `path` (a reference) on the worker pool, sending it
+/// back as `(path, page)` unless `token` is cancelled first.
+pub fn spawn_render(pool: &WorkerPool, path: PathBuf, width: u16, token: CancelToken, tx: Sender<(PathBuf, String)>) {
+    pool.spawn(Priority::High, token, move |token| {
+        let page = render(&path.to_string_lossy(), width)
+            .unwrap_or_else(|| "No rendered page (is man installed?)".to_string());
+        if !token.is_cancelled() {
+            let _ = tx.send((path, page));
+        }
+    });
+}
+
+/// Turn terminal overstriking (`c\bc` for bold, `_\bc` for underline), as
+/// `man` prints when not writing to a terminal, into SGR escapes.
+fn overstrike_to_ansi(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut current = "";
+    let mut i = 0;
+    while i < chars.len() {
+        // Bold may be struck more than once
+        let mut style = "";
+        while i + 2 < chars.len() && chars[i + 1] == '\u{8}' {
+            style = if chars[i] == '_' && style.is_empty() { "\x1b[4m" } else { "\x1b[1m" };
+            i += 2;
+        }
+        let c = chars[i];
+        if style != current {
+            if !current.is_empty() {
+                out.push_str("\x1b[0m");
+            }
+            out.push_str(style);
+            current = style;
+        }
+        out.push(c);
+        i += 1;
+    }
+    if !current.is_empty() {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
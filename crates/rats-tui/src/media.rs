@@ -0,0 +1,480 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+#[cfg(feature = "media")]
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey, Value},
+    probe::Hint,
+};
+
+/// Extensions of audio and video files shown as a metadata card.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "ogg", "oga", "opus", "wav", "m4a", "aac", "mka", "mp4", "m4v", "mov", "mkv",
+    "webm",
+];
+
+/// Most tags listed on the card.
+#[cfg(feature = "media")]
+const MAX_TAGS: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct VideoTrack {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AudioTrack {
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+}
+
+/// What the container says about a media file.
+#[derive(Debug, Clone, Default)]
+pub struct MediaInfo {
+    /// In seconds
+    pub duration: Option<f64>,
+    pub video: Vec<VideoTrack>,
+    pub audio: Vec<AudioTrack>,
+    pub tags: Vec<(String, String)>,
+}
+
+pub fn is_media(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    Some(path.extension()?.to_str()?.to_ascii_lowercase())
+}
+
+/// Read the metadata of an audio or video file. Audio streams and tags come
+/// from symphonia; video streams, which symphonia doesn't describe, from the
+/// MP4 or Matroska headers.
+pub fn probe(path: &Path) -> Option<MediaInfo> {
+    let mut info = probe_audio(path).unwrap_or_default();
+    let container = match extension(path)?.as_str() {
+        "mp4" | "m4v" | "m4a" | "mov" => mp4_info(path).ok(),
+        "mkv" | "webm" | "mka" => mkv_info(path).ok(),
+        _ => None,
+    };
+    if let Some(container) = container {
+        info.video = container.video;
+        info.duration = info.duration.or(container.duration);
+    }
+    (info.duration.is_some() || !info.audio.is_empty() || !info.video.is_empty()).then_some(info)
+}
+
+impl MediaInfo {
+    /// The preview shown for a media file.
+    pub fn card(&self, path: &Path) -> String {
+        let mut card = String::new();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let _ = writeln!(card, "{}\n", name);
+        if let Some(duration) = self.duration {
+            let _ = writeln!(card, "Duration:  {}", format_duration(duration));
+        }
+        for video in &self.video {
+            let _ = writeln!(card, "Video:     {} {}×{}", video.codec, video.width, video.height);
+        }
+        for audio in &self.audio {
+            let mut line = audio.codec.clone();
+            if let Some(rate) = audio.sample_rate {
+                let _ = write!(line, ", {} Hz", rate);
+            }
+            if let Some(channels) = audio.channels {
+                let _ = write!(line, ", {} channel{}", channels, if channels == 1 { "" } else { "s" });
+            }
+            let _ = writeln!(card, "Audio:     {}", line);
+        }
+        // Overall bitrate, container overhead included
+        if let Some(duration) = self.duration.filter(|&duration| duration > 0.0)
+            && let Ok(meta) = path.metadata()
+        {
+            let bits_per_second = meta.len() as f64 * 8.0 / duration;
+            let _ = writeln!(card, "Bitrate:   {}", format_bitrate(bits_per_second));
+        }
+        if !self.tags.is_empty() {
+            card.push('\n');
+            for (key, value) in &self.tags {
+                let _ = writeln!(card, "{:<10} {}", format!("{}:", key), value);
+            }
+        }
+        card
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+fn format_bitrate(bits_per_second: f64) -> String {
+    if bits_per_second >= 1_000_000.0 {
+        format!("{:.1} Mb/s", bits_per_second / 1_000_000.0)
+    } else {
+        format!("{:.0} kb/s", bits_per_second / 1000.0)
+    }
+}
+
+/// Without the `media` feature only video containers are read.
+#[cfg(not(feature = "media"))]
+fn probe_audio(_path: &Path) -> Option<MediaInfo> {
+    None
+}
+
+#[cfg(feature = "media")]
+fn probe_audio(path: &Path) -> Option<MediaInfo> {
+    let source = MediaSourceStream::new(Box::new(File::open(path).ok()?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = extension(path) {
+        hint.with_extension(&ext);
+    }
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?;
+
+    let mut info = MediaInfo::default();
+    let codecs = symphonia::default::get_codecs();
+    for track in probed.format.tracks() {
+        let params = &track.codec_params;
+        // Tracks symphonia can't identify are video or subtitles
+        let Some(codec) = codecs.get_codec(params.codec) else {
+            continue;
+        };
+        if let (Some(base), Some(frames)) = (params.time_base, params.n_frames) {
+            let time = base.calc_time(frames);
+            let seconds = time.seconds as f64 + time.frac;
+            info.duration = Some(info.duration.map_or(seconds, |longest| longest.max(seconds)));
+        }
+        info.audio.push(AudioTrack {
+            codec: codec.short_name.to_uppercase(),
+            sample_rate: params.sample_rate,
+            channels: params.channels.map(|channels| channels.count()),
+        });
+    }
+
+    // Tags may come before the container (ID3) or inside it
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|meta| meta.current()) {
+        add_tags(&mut info.tags, revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        add_tags(&mut info.tags, revision);
+    }
+    Some(info)
+}
+
+#[cfg(feature = "media")]
+fn add_tags(tags: &mut Vec<(String, String)>, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let key = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => "title",
+            Some(StandardTagKey::Artist) => "artist",
+            Some(StandardTagKey::Album) => "album",
+            Some(StandardTagKey::AlbumArtist) => "album artist",
+            Some(StandardTagKey::Composer) => "composer",
+            Some(StandardTagKey::Date | StandardTagKey::ReleaseDate) => "date",
+            Some(StandardTagKey::Genre) => "genre",
+            Some(StandardTagKey::TrackNumber) => "track",
+            Some(StandardTagKey::Comment) => "comment",
+            Some(StandardTagKey::Encoder) => "encoder",
+            _ => continue,
+        };
+        // Cover art and other binary tags don't belong on the card
+        if matches!(tag.value, Value::Binary(_)) || tags.len() >= MAX_TAGS {
+            continue;
+        }
+        if !tags.iter().any(|(existing, _)| existing == key) {
+            tags.push((key.to_string(), tag.value.to_string()));
+        }
+    }
+}
+
+/// Duration and video tracks from a container's own headers.
+#[derive(Debug, Default)]
+struct ContainerInfo {
+    duration: Option<f64>,
+    video: Vec<VideoTrack>,
+}
+
+/// Largest header box read into memory; anything bigger is not one we want.
+const MAX_BOX: u64 = 1 << 20;
+
+/// Child boxes of an MP4 box spanning `start..end`, as (type, content range).
+fn mp4_boxes(file: &mut (impl Read + Seek), start: u64, end: u64) -> io::Result<Vec<([u8; 4], u64, u64)>> {
+    let mut boxes = Vec::new();
+    let mut at = start;
+    while at + 8 <= end {
+        file.seek(SeekFrom::Start(at))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (size, header_len) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            0 => (end - at, 8),
+            1 => {
+                let mut large = [0u8; 8];
+                file.read_exact(&mut large)?;
+                (u64::from_be_bytes(large), 16)
+            }
+            size => (size as u64, 8),
+        };
+        if size < header_len {
+            break;
+        }
+        boxes.push((kind, at + header_len, (at + size).min(end)));
+        at += size;
+    }
+    Ok(boxes)
+}
+
+fn read_range(file: &mut (impl Read + Seek), start: u64, end: u64) -> io::Result<Vec<u8>> {
+    let len = end.saturating_sub(start).min(MAX_BOX);
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn be_u32(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn mp4_info(path: &Path) -> io::Result<ContainerInfo> {
+    let mut file = BufReader::new(File::open(path)?);
+    let len = file.get_ref().metadata()?.len();
+    let mut info = ContainerInfo::default();
+    let find = |boxes: &[([u8; 4], u64, u64)], kind: &[u8; 4]| {
+        boxes.iter().find(|(found, _, _)| found == kind).map(|&(_, start, end)| (start, end))
+    };
+
+    let top = mp4_boxes(&mut file, 0, len)?;
+    let Some((moov_start, moov_end)) = find(&top, b"moov") else {
+        return Ok(info);
+    };
+    let moov = mp4_boxes(&mut file, moov_start, moov_end)?;
+
+    if let Some((start, end)) = find(&moov, b"mvhd") {
+        let mvhd = read_range(&mut file, start, end)?;
+        // Version 1 uses 64-bit times
+        let (timescale, duration) = if mvhd.first() == Some(&1) {
+            (be_u32(&mvhd, 20), be_u64(&mvhd, 24))
+        } else {
+            (be_u32(&mvhd, 12), be_u32(&mvhd, 16).map(u64::from))
+        };
+        if let (Some(timescale), Some(duration)) = (timescale, duration)
+            && timescale > 0
+        {
+            info.duration = Some(duration as f64 / timescale as f64);
+        }
+    }
+
+    for &(kind, start, end) in &moov {
+        if &kind != b"trak" {
+            continue;
+        }
+        let trak = mp4_boxes(&mut file, start, end)?;
+        let Some((mdia_start, mdia_end)) = find(&trak, b"mdia") else {
+            continue;
+        };
+        let mdia = mp4_boxes(&mut file, mdia_start, mdia_end)?;
+        let handler = match find(&mdia, b"hdlr") {
+            Some((start, end)) => read_range(&mut file, start, end)?,
+            None => continue,
+        };
+        if handler.get(8..12) != Some(b"vide") {
+            continue;
+        }
+
+        // Display size, as 16.16 fixed point at the end of tkhd
+        let (mut width, mut height) = (0, 0);
+        if let Some((start, end)) = find(&trak, b"tkhd") {
+            let tkhd = read_range(&mut file, start, end)?;
+            let at = if tkhd.first() == Some(&1) { 88 } else { 76 };
+            width = be_u32(&tkhd, at).unwrap_or(0) >> 16;
+            height = be_u32(&tkhd, at + 4).unwrap_or(0) >> 16;
+        }
+
+        // Codec from the first sample description: minf/stbl/stsd
+        let mut codec = String::from("unknown");
+        let stsd = find(&mdia, b"minf")
+            .map(|(start, end)| mp4_boxes(&mut file, start, end))
+            .transpose()?
+            .and_then(|minf| find(&minf, b"stbl"))
+            .map(|(start, end)| mp4_boxes(&mut file, start, end))
+            .transpose()?
+            .and_then(|stbl| find(&stbl, b"stsd"));
+        if let Some((start, end)) = stsd {
+            let stsd = read_range(&mut file, start, end)?;
+            if let Some(fourcc) = stsd.get(12..16) {
+                codec = video_codec_name(&String::from_utf8_lossy(fourcc));
+            }
+        }
+        info.video.push(VideoTrack { codec, width, height });
+    }
+    Ok(info)
+}
+
+// Matroska element IDs
+const EBML_SEGMENT: u32 = 0x1853_8067;
+const EBML_INFO: u32 = 0x1549_A966;
+const EBML_TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const EBML_DURATION: u32 = 0x4489;
+const EBML_TRACKS: u32 = 0x1654_AE6B;
+const EBML_TRACK_ENTRY: u32 = 0xAE;
+const EBML_TRACK_TYPE: u32 = 0x83;
+const EBML_CODEC_ID: u32 = 0x86;
+const EBML_VIDEO: u32 = 0xE0;
+const EBML_PIXEL_WIDTH: u32 = 0xB0;
+const EBML_PIXEL_HEIGHT: u32 = 0xBA;
+const EBML_CLUSTER: u32 = 0x1F43_B675;
+
+/// A variable-length EBML integer. IDs keep their length marker, sizes
+/// don't; an all-ones size means "unknown".
+fn ebml_vint(file: &mut impl Read, keep_marker: bool) -> io::Result<(u64, bool)> {
+    let mut first = [0u8; 1];
+    file.read_exact(&mut first)?;
+    let len = first[0].leading_zeros() as usize + 1;
+    if len > 8 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad EBML integer"));
+    }
+    let mut value = if keep_marker {
+        first[0] as u64
+    } else {
+        (first[0] as u64) & (0xff >> len)
+    };
+    let mut all_ones = value == (0xff >> len);
+    for _ in 1..len {
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte)?;
+        value = value << 8 | byte[0] as u64;
+        all_ones &= byte[0] == 0xff;
+    }
+    Ok((value, !keep_marker && all_ones))
+}
+
+/// Child elements of an element spanning `start..end`, as (id, content
+/// range). Stops at the first cluster: the headers come before the media.
+fn ebml_elements(file: &mut (impl Read + Seek), start: u64, end: u64) -> io::Result<Vec<(u32, u64, u64)>> {
+    let mut elements = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    let mut at = start;
+    while at < end {
+        let Ok((id, _)) = ebml_vint(file, true) else {
+            break;
+        };
+        let (size, unknown) = ebml_vint(file, false)?;
+        let content = file.stream_position()?;
+        if id as u32 == EBML_CLUSTER {
+            break;
+        }
+        let content_end = if unknown { end } else { (content + size).min(end) };
+        elements.push((id as u32, content, content_end));
+        at = content_end;
+        file.seek(SeekFrom::Start(at))?;
+    }
+    Ok(elements)
+}
+
+fn mkv_info(path: &Path) -> io::Result<ContainerInfo> {
+    let mut file = BufReader::new(File::open(path)?);
+    let len = file.get_ref().metadata()?.len();
+    let mut info = ContainerInfo::default();
+
+    let uint = |buf: &[u8]| buf.iter().fold(0u64, |value, &byte| value << 8 | byte as u64);
+    let top = ebml_elements(&mut file, 0, len)?;
+    let Some(&(_, segment_start, segment_end)) = top.iter().find(|(id, _, _)| *id == EBML_SEGMENT) else {
+        return Ok(info);
+    };
+
+    for (id, start, end) in ebml_elements(&mut file, segment_start, segment_end)? {
+        match id {
+            EBML_INFO => {
+                let mut scale = 1_000_000.0; // nanoseconds per timestamp unit
+                let mut duration = None;
+                for (id, start, end) in ebml_elements(&mut file, start, end)? {
+                    let value = read_range(&mut file, start, end)?;
+                    match (id, value.len()) {
+                        (EBML_TIMESTAMP_SCALE, _) => scale = uint(&value) as f64,
+                        (EBML_DURATION, 4) => {
+                            duration = Some(f32::from_be_bytes(value[..4].try_into().unwrap_or_default()) as f64)
+                        }
+                        (EBML_DURATION, 8) => {
+                            duration = Some(f64::from_be_bytes(value[..8].try_into().unwrap_or_default()))
+                        }
+                        _ => {}
+                    }
+                }
+                info.duration = duration.map(|duration| duration * scale / 1e9);
+            }
+            EBML_TRACKS => {
+                for (id, start, end) in ebml_elements(&mut file, start, end)? {
+                    if id != EBML_TRACK_ENTRY {
+                        continue;
+                    }
+                    let (mut is_video, mut codec, mut width, mut height) = (false, String::new(), 0, 0);
+                    for (id, start, end) in ebml_elements(&mut file, start, end)? {
+                        match id {
+                            EBML_TRACK_TYPE => is_video = uint(&read_range(&mut file, start, end)?) == 1,
+                            EBML_CODEC_ID => {
+                                codec = String::from_utf8_lossy(&read_range(&mut file, start, end)?)
+                                    .trim_end_matches('\0')
+                                    .to_string()
+                            }
+                            EBML_VIDEO => {
+                                for (id, start, end) in ebml_elements(&mut file, start, end)? {
+                                    let value = uint(&read_range(&mut file, start, end)?) as u32;
+                                    match id {
+                                        EBML_PIXEL_WIDTH => width = value,
+                                        EBML_PIXEL_HEIGHT => height = value,
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    if is_video {
+                        info.video.push(VideoTrack {
+                            codec: video_codec_name(&codec),
+                            width,
+                            height,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// Common name for an MP4 sample entry or Matroska codec ID.
+fn video_codec_name(id: &str) -> String {
+    match id {
+        "avc1" | "avc3" | "V_MPEG4/ISO/AVC" => "H.264",
+        "hvc1" | "hev1" | "V_MPEGH/ISO/HEVC" => "H.265",
+        "av01" | "V_AV1" => "AV1",
+        "vp09" | "V_VP9" => "VP9",
+        "vp08" | "V_VP8" => "VP8",
+        "mp4v" | "V_MPEG4/ISO/ASP" => "MPEG-4",
+        "apch" | "apcn" | "apcs" | "apco" | "ap4h" => "ProRes",
+        other => other,
+    }
+    .to_string()
+}
@@ -0,0 +1,32 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Normal,
+    Insert,
+    /// Editing the note on the selected entry
+    Annotate,
+    /// Typing a tag to add to (or remove from) the selected entry
+    Tag,
+    /// Typing `yes` to confirm a destructive operation
+    Confirm,
+    /// Typing a command after `:`
+    Command,
+    /// Reviewing a batch operation before it is applied
+    Plan,
+    /// Typing the file name to answer a save dialog with
+    SaveAs,
+    /// Editing the name of the selected entry
+    Rename,
+    /// Typing the path of a file or directory to create
+    Create,
+}
+
+#[allow(dead_code)]
+impl Mode {
+    pub fn is_normal(&self) -> bool {
+        *self == Mode::Normal
+    }
+
+    pub fn is_insert(&self) -> bool {
+        *self == Mode::Insert
+    }
+}
\ No newline at end of file
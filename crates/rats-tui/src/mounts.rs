@@ -0,0 +1,218 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{preview::human_size, tools::Tool};
+
+/// Pseudo filesystems that are never interesting to browse.
+const PSEUDO_FS_TYPES: [&str; 16] = [
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "securityfs", "pstore",
+    "debugfs", "tracefs", "configfs", "fusectl", "mqueue", "hugetlbfs", "bpf", "autofs",
+];
+
+/// Filesystems where every `stat` is a network round trip.
+const NETWORK_FS_TYPES: [&str; 13] = [
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "9p", "ceph", "glusterfs",
+    "fuse.sshfs", "fuse.rclone", "fuse.davfs2",
+];
+
+/// A mounted filesystem, or a removable partition that could be mounted.
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub device: String,
+    /// `None` for removable partitions that are not currently mounted
+    pub mountpoint: Option<PathBuf>,
+    pub fs_type: String,
+    /// `(used, total)` in bytes
+    pub usage: Option<(u64, u64)>,
+}
+
+impl Mount {
+    /// Path used for the list entry: the mountpoint, or the device node.
+    pub fn path(&self) -> PathBuf {
+        self.mountpoint
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&self.device))
+    }
+
+    /// One-line summary with a usage bar, used as the list label.
+    pub fn label(&self) -> String {
+        let Some(mountpoint) = &self.mountpoint else {
+            return format!("{}  (not mounted, removable)", self.device);
+        };
+        let usage = match self.usage {
+            Some((used, total)) if total > 0 => {
+                let percent = used * 100 / total;
+                let filled = (percent / 10) as usize;
+                format!(
+                    "[{}{}] {:>3}% {}/{}",
+                    "#".repeat(filled),
+                    "-".repeat(10 - filled),
+                    percent,
+                    human_size(used),
+                    human_size(total)
+                )
+            }
+            _ => String::new(),
+        };
+        format!("{}  {}  {}  {}", mountpoint.display(), self.device, self.fs_type, usage)
+    }
+}
+
+/// Mounted filesystems from `/proc/self/mounts`, followed by unmounted
+/// partitions of removable disks. Empty on platforms without procfs.
+pub fn list() -> Vec<Mount> {
+    let Ok(table) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+
+    let mut mounts: Vec<Mount> = Vec::new();
+    for line in table.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mountpoint), Some(fs_type)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if PSEUDO_FS_TYPES.contains(&fs_type) {
+            continue;
+        }
+        let mountpoint = PathBuf::from(unescape(mountpoint));
+        // The same filesystem can be listed several times (bind mounts, namespaces)
+        if mounts.iter().any(|m| m.mountpoint.as_ref() == Some(&mountpoint)) {
+            continue;
+        }
+        mounts.push(Mount {
+            device: unescape(device),
+            usage: usage(&mountpoint),
+            mountpoint: Some(mountpoint),
+            fs_type: fs_type.to_string(),
+        });
+    }
+
+    for device in removable_partitions() {
+        if !mounts.iter().any(|m| m.device == device) {
+            mounts.push(Mount {
+                device,
+                mountpoint: None,
+                fs_type: String::new(),
+                usage: None,
+            });
+        }
+    }
+
+    mounts
+}
+
+/// Whether `path` is on a network filesystem (NFS, SMB, sshfs, ...), going
+/// by the innermost mount containing it. Only reads the mount table, so it
+/// never touches the mount itself.
+pub fn is_network(path: &Path) -> bool {
+    let Ok(table) = fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            Some((PathBuf::from(unescape(fields.next()?)), fields.next()?))
+        })
+        .filter(|(mountpoint, _)| path.starts_with(mountpoint))
+        // Later lines win among equally long mountpoints (over-mounts)
+        .max_by_key(|(mountpoint, _)| mountpoint.as_os_str().len())
+        .is_some_and(|(_, fs_type)| NETWORK_FS_TYPES.contains(&fs_type))
+}
+
+/// Mount a removable partition via udisks, returning the mountpoint message.
+pub fn mount(device: &str) -> io::Result<String> {
+    udisksctl(&["mount", "-b", device])
+}
+
+/// Unmount the filesystem mounted at `mountpoint` via udisks.
+pub fn unmount(mountpoint: &Path) -> io::Result<String> {
+    let device = list()
+        .into_iter()
+        .find(|m| m.mountpoint.as_deref() == Some(mountpoint))
+        .map(|m| m.device)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not a mountpoint"))?;
+    udisksctl(&["unmount", "-b", &device])
+}
+
+fn udisksctl(args: &[&str]) -> io::Result<String> {
+    let output = Command::new(Tool::Udisksctl.binary()).args(args).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    }
+}
+
+/// Partitions (`/dev/sdb1`, ...) of disks whose `removable` flag is set.
+fn removable_partitions() -> Vec<String> {
+    let Ok(disks) = fs::read_dir("/sys/block") else {
+        return Vec::new();
+    };
+    let mut partitions = Vec::new();
+    for disk in disks.flatten() {
+        let removable = fs::read_to_string(disk.path().join("removable")).unwrap_or_default();
+        if removable.trim() != "1" {
+            continue;
+        }
+        let disk_name = disk.file_name().to_string_lossy().to_string();
+        let Ok(children) = fs::read_dir(disk.path()) else {
+            continue;
+        };
+        for child in children.flatten() {
+            let name = child.file_name().to_string_lossy().to_string();
+            if name.starts_with(&disk_name) && child.path().join("partition").exists() {
+                partitions.push(format!("/dev/{}", name));
+            }
+        }
+    }
+    partitions.sort();
+    partitions
+}
+
+#[cfg(unix)]
+fn usage(mountpoint: &Path) -> Option<(u64, u64)> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(mountpoint.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a
+    // properly sized, writable statvfs struct.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block;
+    let free = stat.f_bfree as u64 * block;
+    Some((total.saturating_sub(free), total))
+}
+
+#[cfg(not(unix))]
+fn usage(_mountpoint: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Undo the octal escaping (`\040` for space) used in the mount table.
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => out.push(byte as char),
+                Err(_) => {
+                    out.push(c);
+                    out.push_str(&code);
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
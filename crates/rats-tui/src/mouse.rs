@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+use crate::{action::Action, app::App};
+
+/// Mouse behaviour settings.
+#[derive(Debug, Clone)]
+pub struct MouseConfig {
+    /// Two clicks on the same row within this interval accept the entry
+    pub double_click: Duration,
+    /// Lines moved per wheel tick
+    pub scroll_lines: usize,
+    /// Whether the wheel over the list moves the selection (true) or only
+    /// scrolls the viewport, dragging the selection along at the edges (false)
+    pub wheel_moves_selection: bool,
+}
+
+impl Default for MouseConfig {
+    fn default() -> MouseConfig {
+        MouseConfig {
+            double_click: Duration::from_millis(400),
+            scroll_lines: 3,
+            wheel_moves_selection: true,
+        }
+    }
+}
+
+/// Screen areas inside the panes' frames, recorded on each draw for hit
+/// testing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PaneAreas {
+    pub list: Rect,
+    pub preview: Rect,
+}
+
+/// The last left click, used to detect double clicks.
+#[derive(Debug, Clone, Copy)]
+pub struct LastClick {
+    pub at: Instant,
+    pub row: usize,
+}
+
+impl App {
+    /// The actions a mouse event stands for. A double click selects the
+    /// entry under the pointer and accepts it.
+    pub fn mouse_actions(&mut self, event: MouseEvent) -> Vec<Action> {
+        let position = Position::new(event.column, event.row);
+        let over_list = self.pane_areas.list.contains(position);
+        let over_preview = self.pane_areas.preview.contains(position);
+        let lines = self.config.mouse.scroll_lines as isize;
+
+        match event.kind {
+            // The wheel moves a row at a time through the gallery
+            MouseEventKind::ScrollDown if self.gallery => vec![Action::MoveGallery(self.grid.columns as isize)],
+            MouseEventKind::ScrollUp if self.gallery => vec![Action::MoveGallery(-(self.grid.columns as isize))],
+            MouseEventKind::ScrollDown if over_list => vec![Action::ScrollList(lines)],
+            MouseEventKind::ScrollUp if over_list => vec![Action::ScrollList(-lines)],
+            MouseEventKind::ScrollDown if over_preview => vec![Action::ScrollPreview(lines)],
+            MouseEventKind::ScrollUp if over_preview => vec![Action::ScrollPreview(-lines)],
+            MouseEventKind::Down(MouseButton::Left) if over_list => {
+                let index = if self.gallery {
+                    self.grid
+                        .index_at(position)
+                        .filter(|&index| index < self.filtered_items.len())
+                } else {
+                    let row = event.row.saturating_sub(self.pane_areas.list.y) as usize;
+                    self.index_at_row(row)
+                };
+                let Some(index) = index else {
+                    return Vec::new();
+                };
+
+                let now = Instant::now();
+                let is_double = self.last_click.is_some_and(|last| {
+                    last.row == index && now.duration_since(last.at) <= self.config.mouse.double_click
+                });
+                if is_double {
+                    self.last_click = None;
+                    return vec![Action::Select(index), Action::Accept];
+                }
+                self.last_click = Some(LastClick { at: now, row: index });
+                vec![Action::Select(index)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Index into `filtered_items` of the entry drawn at `row` of the list,
+    /// accounting for entries that take a second row for their excerpt or
+    /// a group header.
+    fn index_at_row(&self, row: usize) -> Option<usize> {
+        let show_excerpts = self.config.excerpts && !self.show_preview;
+        let mut top = 0;
+        for index in self.list_state.offset()..self.filtered_items.len() {
+            let entry = &self.items[self.filtered_items[index].0];
+            let has_excerpt = show_excerpts
+                && matches!(self.excerpts.get(&(entry.path.clone(), entry.line)), Some(Some(_)));
+            top += 1 + usize::from(has_excerpt) + usize::from(self.group_header(index).is_some());
+            if row < top {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    pub fn scroll_list(&mut self, delta: isize) {
+        if self.filtered_items.is_empty() {
+            return;
+        }
+        let last = self.filtered_items.len() - 1;
+        let selected = self.list_state.selected().unwrap_or(0);
+
+        if self.config.mouse.wheel_moves_selection {
+            let target = selected.saturating_add_signed(delta).min(last);
+            self.list_state.select(Some(target));
+        } else {
+            // Move the viewport and keep the selection inside it
+            let height = self.pane_areas.list.height as usize;
+            let max_offset = self.filtered_items.len().saturating_sub(height);
+            let offset = self.list_state.offset().saturating_add_signed(delta).min(max_offset);
+            *self.list_state.offset_mut() = offset;
+            let bottom = (offset + height).saturating_sub(1).min(last);
+            self.list_state.select(Some(selected.clamp(offset, bottom)));
+        }
+        self.load_preview();
+    }
+}
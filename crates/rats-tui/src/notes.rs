@@ -0,0 +1,54 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{self, Path, PathBuf},
+};
+
+use crate::history::state_dir;
+
+/// Short notes attached to paths, shown after the name in the list and
+/// matched by the filter.
+#[derive(Debug, Default)]
+pub struct Notes {
+    by_path: HashMap<PathBuf, String>,
+}
+
+impl Notes {
+    pub fn load() -> Notes {
+        let by_path = notes_file()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Notes { by_path }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        if self.by_path.is_empty() {
+            return None;
+        }
+        let path = path::absolute(path).ok()?;
+        self.by_path.get(&path).map(String::as_str)
+    }
+
+    /// Attach `note` to `path`, or remove its note when `note` is blank.
+    pub fn set(&mut self, path: &Path, note: &str) -> io::Result<()> {
+        let path = path::absolute(path)?;
+        let note = note.trim();
+        if note.is_empty() {
+            self.by_path.remove(&path);
+        } else {
+            self.by_path.insert(path, note.to_string());
+        }
+
+        let file = notes_file().ok_or_else(|| io::Error::other("no state directory"))?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&self.by_path).map_err(io::Error::other)?;
+        fs::write(file, content)
+    }
+}
+
+fn notes_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("notes.json"))
+}
@@ -0,0 +1,36 @@
+use std::path::{Path, PathBuf};
+
+use crate::{expand::expand, query::Query};
+
+/// Characters that delimit a path inside a line of source or log text.
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "\"'`()[]{}<>,;=|".contains(c)
+}
+
+/// Path-like words in `line`, with the line number of a `path:12` suffix.
+/// A word counts as a path if it contains a `/` or a `.`, as in imports,
+/// includes, Makefile prerequisites and compiler messages.
+pub fn extract(line: &str) -> Vec<(String, Option<usize>)> {
+    line.split(is_delimiter)
+        .map(|word| word.trim_end_matches(['.', ':']))
+        .filter(|word| word.contains(['/', '.']) && !word.contains("://"))
+        .map(|word| {
+            let query = Query::parse(word);
+            (query.pattern, query.line)
+        })
+        .filter(|(path, _)| !path.is_empty() && path != "." && path != "..")
+        .collect()
+}
+
+/// Resolve `path` against each of `bases` in turn, returning the first that
+/// exists. Absolute paths (after expanding `~` and variables) are taken as
+/// they are.
+pub fn resolve(path: &str, bases: &[&Path]) -> Option<PathBuf> {
+    let path = PathBuf::from(expand(path));
+    if path.is_absolute() {
+        return path.canonicalize().ok();
+    }
+    bases
+        .iter()
+        .find_map(|base| base.join(&path).canonicalize().ok())
+}
@@ -0,0 +1,248 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::{fuzzy, walk};
+
+/// What feeding an event to a `PickerState` led to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerEvent {
+    /// The event meant nothing to the picker
+    Ignored,
+    /// The filter or the selection changed; redraw
+    Changed,
+    /// Enter was pressed on this path
+    Accepted(PathBuf),
+    /// Esc was pressed
+    Cancelled,
+}
+
+struct Candidate {
+    path: PathBuf,
+    name: String,
+}
+
+/// The fuzzy file picker as a component for other ratatui apps: the files
+/// on offer, the filter typed so far and the selection. Feed it events with
+/// `handle_event` and draw it with `PickerWidget`.
+pub struct PickerState {
+    candidates: Vec<Candidate>,
+    filter: String,
+    /// Indices into `candidates`, best match first
+    matches: Vec<usize>,
+    list: ListState,
+}
+
+impl PickerState {
+    /// Offer the entries of `dir` (dot-files left out), or with `recursive`
+    /// every file below it by relative path, skipping what git ignores.
+    pub fn new(dir: impl AsRef<Path>, recursive: bool) -> io::Result<PickerState> {
+        let dir = dir.as_ref();
+        let mut candidates: Vec<Candidate> = if recursive {
+            fs::read_dir(dir)?;
+            walk::files(dir.to_path_buf(), true)
+                .map(|path| {
+                    let name = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().into_owned();
+                    Candidate { path, name }
+                })
+                .collect()
+        } else {
+            fs::read_dir(dir)?
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    let mut name = path.file_name()?.to_string_lossy().into_owned();
+                    if name.starts_with('.') {
+                        return None;
+                    }
+                    if path.is_dir() {
+                        name.push('/');
+                    }
+                    Some(Candidate { path, name })
+                })
+                .collect()
+        };
+        candidates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(PickerState::from_paths(candidates))
+    }
+
+    /// Offer `paths` as given, matched by their full text.
+    pub fn with_paths(paths: impl IntoIterator<Item = PathBuf>) -> PickerState {
+        let candidates = paths
+            .into_iter()
+            .map(|path| Candidate { name: path.to_string_lossy().into_owned(), path })
+            .collect();
+        PickerState::from_paths(candidates)
+    }
+
+    fn from_paths(candidates: Vec<Candidate>) -> PickerState {
+        let mut state = PickerState {
+            candidates,
+            filter: String::new(),
+            matches: Vec::new(),
+            list: ListState::default(),
+        };
+        state.update_matches();
+        state
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        self.update_matches();
+    }
+
+    /// How many candidates match the filter.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    pub fn selected(&self) -> Option<&Path> {
+        let index = *self.matches.get(self.list.selected()?)?;
+        Some(&self.candidates[index].path)
+    }
+
+    pub fn select_next(&mut self) {
+        if let Some(selected) = self.list.selected() {
+            self.list.select(Some((selected + 1).min(self.matches.len().saturating_sub(1))));
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if let Some(selected) = self.list.selected() {
+            self.list.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    /// Type into the filter, move the selection, accept or cancel. Only key
+    /// presses count; everything else is `Ignored`.
+    pub fn handle_event(&mut self, event: &Event) -> PickerEvent {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(*key),
+            _ => PickerEvent::Ignored,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerEvent {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => PickerEvent::Cancelled,
+            KeyCode::Enter => match self.selected() {
+                Some(path) => PickerEvent::Accepted(path.to_path_buf()),
+                None => PickerEvent::Ignored,
+            },
+            KeyCode::Down => self.moved(Self::select_next),
+            KeyCode::Up => self.moved(Self::select_previous),
+            KeyCode::Char('n') if ctrl => self.moved(Self::select_next),
+            KeyCode::Char('p') if ctrl => self.moved(Self::select_previous),
+            KeyCode::Char('u') if ctrl => {
+                self.set_filter("");
+                PickerEvent::Changed
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.filter.push(c);
+                self.update_matches();
+                PickerEvent::Changed
+            }
+            KeyCode::Backspace if self.filter.pop().is_some() => {
+                self.update_matches();
+                PickerEvent::Changed
+            }
+            _ => PickerEvent::Ignored,
+        }
+    }
+
+    fn moved(&mut self, movement: fn(&mut PickerState)) -> PickerEvent {
+        let before = self.list.selected();
+        movement(self);
+        match self.list.selected() == before {
+            true => PickerEvent::Ignored,
+            false => PickerEvent::Changed,
+        }
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = fuzzy::rank(&self.filter, self.candidates.iter().map(|candidate| candidate.name.as_str()))
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        self.list = ListState::default().with_selected((!self.matches.is_empty()).then_some(0));
+    }
+}
+
+/// Draws a `PickerState`: a `> filter` line above the matches, best first.
+/// Borders and titles are up to the embedding app, through `block`.
+#[derive(Default)]
+pub struct PickerWidget<'a> {
+    block: Option<Block<'a>>,
+}
+
+impl<'a> PickerWidget<'a> {
+    pub fn new() -> PickerWidget<'a> {
+        PickerWidget::default()
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> PickerWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+}
+
+impl StatefulWidget for PickerWidget<'_> {
+    type State = PickerState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut PickerState) {
+        let inner = match self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if inner.height == 0 {
+            return;
+        }
+
+        let prompt = Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Cyan)),
+            Span::raw(state.filter.as_str()),
+            Span::styled(
+                format!("  {}/{}", state.matches.len(), state.candidates.len()),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        ]);
+        buf.set_line(inner.x, inner.y, &prompt, inner.width);
+
+        let items: Vec<ListItem> = state
+            .matches
+            .iter()
+            .map(|&index| ListItem::new(state.candidates[index].name.as_str()))
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+            .highlight_symbol("▶ ");
+        let rows = Rect {
+            y: inner.y + 1,
+            height: inner.height - 1,
+            ..inner
+        };
+        StatefulWidget::render(list, rows, buf, &mut state.list);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{self, Path, PathBuf},
+};
+
+use crate::history::state_dir;
+
+/// Entries pinned to the top of the list, stored per project as paths
+/// relative to the project root.
+#[derive(Debug, Default)]
+pub struct Pins {
+    by_project: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl Pins {
+    pub fn load() -> Pins {
+        let by_project = pins_file()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Pins { by_project }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let file = pins_file().ok_or_else(|| io::Error::other("no state directory"))?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&self.by_project).map_err(io::Error::other)?;
+        fs::write(file, content)
+    }
+
+    pub fn is_pinned(&self, root: &Path, path: &Path) -> bool {
+        let Some(pins) = self.by_project.get(root) else {
+            return false;
+        };
+        relative_to(root, path).is_some_and(|relative| pins.contains(&relative))
+    }
+
+    /// Pin or unpin `path` within the project at `root`, persisting the
+    /// change. Returns whether the path is now pinned.
+    pub fn toggle(&mut self, root: &Path, path: &Path) -> io::Result<bool> {
+        let relative = relative_to(root, path)
+            .ok_or_else(|| io::Error::other("path is outside the project"))?;
+        let pins = self.by_project.entry(root.to_path_buf()).or_default();
+        let pinned = match pins.iter().position(|pin| *pin == relative) {
+            Some(index) => {
+                pins.remove(index);
+                false
+            }
+            None => {
+                pins.push(relative);
+                true
+            }
+        };
+        if pins.is_empty() {
+            self.by_project.remove(root);
+        }
+        self.save()?;
+        Ok(pinned)
+    }
+}
+
+fn pins_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("pins.json"))
+}
+
+/// The project containing `dir`: the nearest ancestor with a `.git`, or
+/// `dir` itself when it is not inside a repository.
+pub fn project_root(dir: &Path) -> PathBuf {
+    let dir = path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(&dir)
+        .to_path_buf()
+}
+
+fn relative_to(root: &Path, path: &Path) -> Option<PathBuf> {
+    let path = path::absolute(path).ok()?;
+    path.strip_prefix(root).ok().map(Path::to_path_buf)
+}
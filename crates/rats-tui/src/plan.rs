@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit,
+    pool::{CancelSource, CancelToken},
+};
+
+/// One step of a batch operation, as written to and read from a plan file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum FileOp {
+    Copy { from: PathBuf, to: PathBuf },
+    Move { from: PathBuf, to: PathBuf },
+    Delete { path: PathBuf },
+    Mkdir { path: PathBuf },
+}
+
+impl FileOp {
+    /// Name recorded in the audit log.
+    fn action(&self) -> &'static str {
+        match self {
+            FileOp::Copy { .. } => "copy",
+            FileOp::Move { .. } => "move",
+            FileOp::Delete { .. } => "delete",
+            FileOp::Mkdir { .. } => "mkdir",
+        }
+    }
+}
+
+impl fmt::Display for FileOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileOp::Copy { from, to } => write!(f, "copy   {} → {}", from.display(), to.display()),
+            FileOp::Move { from, to } => write!(f, "move   {} → {}", from.display(), to.display()),
+            FileOp::Delete { path } => write!(f, "delete {}", path.display()),
+            FileOp::Mkdir { path } => write!(f, "mkdir  {}", path.display()),
+        }
+    }
+}
+
+/// An ordered list of operations, reviewed before it is applied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Plan {
+    pub operations: Vec<FileOp>,
+}
+
+impl Plan {
+    /// Read a plan from a JSON file, or from stdin when `file` is `-`.
+    pub fn load(file: &Path) -> Result<Plan, Box<dyn Error>> {
+        let content = if file == Path::new("-") {
+            io::read_to_string(io::stdin())?
+        } else {
+            fs::read_to_string(file)?
+        };
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// For each operation, why it would fail given the ones before it, if it
+    /// would. Earlier operations are simulated rather than performed, so a
+    /// `move a b` followed by `delete b` is not flagged.
+    pub fn conflicts(&self) -> Vec<Option<String>> {
+        let mut created = HashSet::new();
+        let mut removed = HashSet::new();
+        let exists = |path: &Path, created: &HashSet<PathBuf>, removed: &HashSet<PathBuf>| {
+            created.contains(path)
+                || (!removed.contains(path) && path.symlink_metadata().is_ok())
+        };
+
+        self.operations
+            .iter()
+            .map(|op| {
+                let (source, target) = match op {
+                    FileOp::Copy { from, to } | FileOp::Move { from, to } => (Some(from), Some(to)),
+                    FileOp::Delete { path } => (Some(path), None),
+                    FileOp::Mkdir { path } => (None, Some(path)),
+                };
+                if let Some(source) = source
+                    && !exists(source, &created, &removed)
+                {
+                    return Some(format!("{} does not exist", source.display()));
+                }
+                if let Some(target) = target
+                    && exists(target, &created, &removed)
+                {
+                    return Some(format!("{} already exists", target.display()));
+                }
+                if let FileOp::Copy { from, to } | FileOp::Move { from, to } = op
+                    && is_within(to, from)
+                {
+                    return Some(format!("{} is inside {}", to.display(), from.display()));
+                }
+                if let FileOp::Move { from, .. } | FileOp::Delete { path: from } = op {
+                    created.remove(from);
+                    removed.insert(from.clone());
+                }
+                if let Some(target) = target {
+                    removed.remove(target);
+                    created.insert(target.clone());
+                }
+                None
+            })
+            .collect()
+    }
+
+    /// Perform every operation in order, stopping at the first failure. With
+    /// `dry_run`, only print what would be done and any conflicts.
+    pub fn apply(&self, dry_run: bool) -> Result<(), Box<dyn Error>> {
+        let conflicts = self.conflicts();
+        for (op, conflict) in self.operations.iter().zip(&conflicts) {
+            match conflict {
+                Some(conflict) => println!("{}  ! {}", op, conflict),
+                None => println!("{}", op),
+            }
+        }
+        let conflicted = conflicts.iter().flatten().count();
+        if conflicted > 0 {
+            return Err(format!(
+                "{} of {} operations conflict; nothing was changed",
+                conflicted,
+                self.operations.len()
+            )
+            .into());
+        }
+        if dry_run {
+            return Ok(());
+        }
+        self.execute()
+    }
+
+    /// Perform every operation in order without checking for conflicts,
+    /// stopping at the first failure.
+    pub fn execute(&self) -> Result<(), Box<dyn Error>> {
+        // Nothing stops a batch run in the foreground
+        self.execute_with_progress(&CancelSource::default().current(), &mut |_| {})
+    }
+
+    /// `execute`, calling `progress` with the size of every file copied as
+    /// it is done. Moves within a filesystem are renames and report nothing.
+    /// Stops between files once `token` is cancelled.
+    pub fn execute_with_progress(
+        &self,
+        token: &CancelToken,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<(), Box<dyn Error>> {
+        for (done, op) in self.operations.iter().enumerate() {
+            perform(op, token, progress).map_err(|err| {
+                format!("{}: {} ({} of {} operations done)", op, err, done, self.operations.len())
+            })?;
+            let _ = match op {
+                FileOp::Copy { from, to } | FileOp::Move { from, to } => {
+                    audit::record(op.action(), from, Some(to))
+                }
+                FileOp::Delete { path } | FileOp::Mkdir { path } => {
+                    audit::record(op.action(), path, None)
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+fn perform(op: &FileOp, token: &CancelToken, progress: &mut dyn FnMut(u64)) -> io::Result<()> {
+    if token.is_cancelled() {
+        return Err(stopped());
+    }
+    match op {
+        FileOp::Copy { from, to } => {
+            create_parent(to)?;
+            copy_recursive(from, to, token, progress)
+        }
+        FileOp::Move { from, to } => {
+            create_parent(to)?;
+            match fs::rename(from, to) {
+                // rename can't cross filesystems; copy and remove instead
+                Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                    copy_recursive(from, to, token, progress)?;
+                    remove(from)
+                }
+                result => result,
+            }
+        }
+        FileOp::Delete { path } => remove(path),
+        FileOp::Mkdir { path } => fs::create_dir_all(path),
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Copy a file, symlink or directory tree. Symlinks are copied as links. A
+/// directory is never copied into itself, which would never end.
+fn copy_recursive(from: &Path, to: &Path, token: &CancelToken, progress: &mut dyn FnMut(u64)) -> io::Result<()> {
+    if is_within(to, from) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is inside {}", to.display(), from.display()),
+        ));
+    }
+    copy_tree(from, to, token, progress)
+}
+
+fn copy_tree(from: &Path, to: &Path, token: &CancelToken, progress: &mut dyn FnMut(u64)) -> io::Result<()> {
+    if token.is_cancelled() {
+        return Err(stopped());
+    }
+    let meta = from.symlink_metadata()?;
+    if meta.is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+        #[cfg(not(unix))]
+        return fs::copy(from, to).map(|_| ());
+    }
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()), token, progress)?;
+        }
+        return Ok(());
+    }
+    progress(fs::copy(from, to)?);
+    Ok(())
+}
+
+fn stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "stopped")
+}
+
+/// Whether `path` is `dir` or lies under it, once symlinks and `..` are
+/// resolved. `path` need not exist yet.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    match (resolve(path), dir.canonicalize()) {
+        (Some(path), Ok(dir)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+/// `path` made absolute and canonical as far as it exists, with the rest of
+/// it appended as written.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return Some(rest.iter().rev().fold(canonical, |path, name| path.join(name)));
+        }
+        rest.push(existing.file_name()?);
+        existing = match existing.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+    }
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
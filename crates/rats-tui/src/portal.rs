@@ -0,0 +1,90 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::names::path_to_bytes;
+
+/// A file chooser request from xdg-desktop-portal-termfilechooser. Its
+/// wrapper script is called with `multiple directory save path out`, the
+/// flags being `1` or `0`.
+#[derive(Debug, Clone)]
+pub struct ChooserRequest {
+    /// More than one entry may be chosen
+    pub multiple: bool,
+    /// Directories are chosen instead of files
+    pub directory: bool,
+    /// A save dialog: `path` is the suggested file to create
+    pub save: bool,
+    /// Where to start, or the suggested file when saving
+    pub path: PathBuf,
+    /// File the chosen paths are written to, one per line
+    pub out: PathBuf,
+}
+
+impl ChooserRequest {
+    /// Parse the wrapper's arguments; extra trailing ones (such as the debug
+    /// flag) are ignored.
+    pub fn from_args(args: &[OsString]) -> Option<ChooserRequest> {
+        let [multiple, directory, save, path, out] = args.get(..5)? else {
+            return None;
+        };
+        let flag = |arg: &OsString| arg == "1";
+        Some(ChooserRequest {
+            multiple: flag(multiple),
+            directory: flag(directory),
+            save: flag(save),
+            path: PathBuf::from(path),
+            out: PathBuf::from(out),
+        })
+    }
+
+    /// Directory to start browsing in: `path`, or its parent when it names
+    /// a file (always the case when saving).
+    pub fn start_dir(&self) -> PathBuf {
+        if !self.save && self.path.is_dir() {
+            return self.path.clone();
+        }
+        match self.path.parent() {
+            Some(parent) if parent.is_dir() => parent.to_path_buf(),
+            _ => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        }
+    }
+
+    /// File name offered when saving.
+    pub fn suggested_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// What the user is asked to do, for the header.
+    pub fn title(&self) -> &'static str {
+        match (self.save, self.directory, self.multiple) {
+            (true, _, _) => "Save as",
+            (false, true, true) => "Choose directories",
+            (false, true, false) => "Choose a directory",
+            (false, false, true) => "Choose files",
+            (false, false, false) => "Choose a file",
+        }
+    }
+
+    /// Hand the chosen paths to the portal. Exiting without calling this
+    /// cancels the request.
+    pub fn respond(&self, paths: &[PathBuf]) -> io::Result<()> {
+        let mut out = Vec::new();
+        for path in paths {
+            let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+            out.extend_from_slice(&path_to_bytes(&path));
+            out.push(b'\n');
+        }
+        fs::write(&self.out, out)
+    }
+
+    /// Whether `path` is the kind of entry this request asks for.
+    pub fn accepts(&self, path: &Path) -> bool {
+        path.is_dir() == self.directory
+    }
+}
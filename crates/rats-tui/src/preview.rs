@@ -0,0 +1,330 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::Hasher,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+    thread,
+    time::Duration,
+};
+
+use crate::pool::{CancelToken, Priority, WorkerPool};
+
+/// Maximum number of entries listed when previewing a directory.
+pub const DIRECTORY_SAMPLE_LIMIT: usize = 200;
+
+/// Files larger than this are previewed as head and tail instead of in full.
+pub const LARGE_FILE_BYTES: u64 = 50_000;
+
+/// Number of lines shown from each end of a large file.
+pub const HEAD_TAIL_LINES: usize = 100;
+
+/// Bytes read from the start of a file when looking for an excerpt.
+const EXCERPT_SCAN_BYTES: u64 = 64 * 1024;
+
+/// A one-line excerpt of a text file for showing under its list entry:
+/// line `line` (1-based) if given, otherwise the first non-blank line.
+/// Only the start of the file is read; `None` for binary or unreadable
+/// files.
+pub fn excerpt(path: &Path, line: Option<usize>) -> Option<String> {
+    let mut buf = Vec::new();
+    File::open(path).ok()?.take(EXCERPT_SCAN_BYTES).read_to_end(&mut buf).ok()?;
+    if buf.contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let text = match line {
+        Some(line) => text.lines().nth(line.saturating_sub(1))?,
+        None => text.lines().find(|l| !l.trim().is_empty())?,
+    };
+    Some(text.trim().chars().take(200).collect())
+}
+
+/// Read the first and last `lines` lines of a large file, joined by an
+/// elision marker. Only the two ends of the file are read, so this stays
+/// cheap for multi-gigabyte logs. Returns `Ok(None)` if the file looks binary.
+pub fn head_and_tail(path: &Path, lines: usize) -> io::Result<Option<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut head = Vec::new();
+    let mut head_bytes = 0u64;
+    let mut reader = BufReader::new(&mut file);
+    let mut buf = Vec::new();
+    while head.len() < lines {
+        buf.clear();
+        let n = reader.read_until(b'\n', &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if buf.contains(&0) {
+            return Ok(None);
+        }
+        head_bytes += n as u64;
+        head.push(String::from_utf8_lossy(&buf).trim_end_matches(['\n', '\r']).to_string());
+    }
+
+    // Read backwards in chunks until enough lines have been seen
+    let mut tail_start = len;
+    let mut tail_buf = Vec::new();
+    while tail_start > head_bytes && tail_buf.iter().filter(|&&b| b == b'\n').count() <= lines {
+        let chunk = (tail_start - head_bytes).min(16 * 1024);
+        tail_start -= chunk;
+        let mut chunk_buf = vec![0; chunk as usize];
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.read_exact(&mut chunk_buf)?;
+        chunk_buf.extend_from_slice(&tail_buf);
+        tail_buf = chunk_buf;
+    }
+
+    let tail_text = String::from_utf8_lossy(&tail_buf);
+    let mut tail: Vec<&str> = tail_text.lines().collect();
+    // The first line may be partial unless we read right up to the head
+    if tail_start > head_bytes && !tail.is_empty() {
+        tail.remove(0);
+    }
+    let skip = tail.len().saturating_sub(lines);
+    let tail = &tail[skip..];
+
+    let tail_bytes: u64 = tail.iter().map(|line| line.len() as u64 + 1).sum();
+    let elided = len.saturating_sub(head_bytes + tail_bytes);
+
+    let mut text = head.join("\n");
+    if !tail.is_empty() {
+        if elided > 0 {
+            text.push_str(&format!(
+                "\n\n··· {} bytes elided ({} total) ···\n\n",
+                format_count(elided as usize),
+                format_count(len as usize)
+            ));
+        } else {
+            text.push('\n');
+        }
+        text.push_str(&tail.join("\n"));
+    }
+    Ok(Some(text))
+}
+
+/// The first few entries of a directory, read without enumerating the rest.
+pub struct DirectorySample {
+    pub lines: Vec<String>,
+    pub truncated: bool,
+}
+
+impl DirectorySample {
+    pub fn read(path: &Path, limit: usize) -> Option<DirectorySample> {
+        let mut entries: Vec<(bool, String)> = Vec::new();
+        let mut truncated = false;
+
+        for entry in fs::read_dir(path).ok()?.flatten() {
+            if entries.len() == limit {
+                truncated = true;
+                break;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            entries.push((is_dir, entry.file_name().to_string_lossy().to_string()));
+        }
+
+        // Directories first, then files, both alphabetically
+        entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let lines = entries
+            .into_iter()
+            .map(|(is_dir, name)| if is_dir { format!("📁 {}/", name) } else { format!("📄 {}", name) })
+            .collect();
+        Some(DirectorySample { lines, truncated })
+    }
+
+    /// Render the sample, with a footer describing the entries not shown.
+    /// `total` is `None` while the full count is still being computed.
+    pub fn render(&self, total: Option<usize>) -> String {
+        let mut text = if self.lines.is_empty() {
+            "<empty directory>".to_string()
+        } else {
+            self.lines.join("\n")
+        };
+        if self.truncated {
+            match total {
+                Some(total) => {
+                    let more = total.saturating_sub(self.lines.len());
+                    text.push_str(&format!("\n… and {} more", format_count(more)));
+                }
+                None => text.push_str("\n… counting remaining entries"),
+            }
+        }
+        text
+    }
+}
+
+/// Count the entries of `path` on the worker pool, sending the result back
+/// as `(path, count)`. The walk stops early once `token` is cancelled, i.e.
+/// the preview has been replaced.
+pub fn spawn_entry_count(
+    pool: &WorkerPool,
+    path: PathBuf,
+    token: CancelToken,
+    tx: Sender<(PathBuf, usize)>,
+) {
+    pool.spawn(Priority::High, token, move |token| {
+        let Ok(entries) = fs::read_dir(&path) else {
+            return;
+        };
+        let mut count = 0;
+        for _ in entries {
+            count += 1;
+            if count % 1024 == 0 && token.is_cancelled() {
+                return;
+            }
+        }
+        let _ = tx.send((path, count));
+    });
+}
+
+/// A checksum of what the preview of `path` can show, to tell a file that
+/// was edited from one that was merely touched: its length, its first
+/// `limit` bytes and, past that, its last `limit` bytes. `None` once `token`
+/// is cancelled, checked between chunks.
+pub fn checksum(path: &Path, limit: u64, token: &CancelToken) -> io::Result<Option<u64>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(len);
+    if !hash_span(&mut (&mut file).take(limit), &mut hasher, token)? {
+        return Ok(None);
+    }
+    if len > limit {
+        file.seek(SeekFrom::Start(len.saturating_sub(limit).max(limit)))?;
+        if !hash_span(&mut file.take(limit), &mut hasher, token)? {
+            return Ok(None);
+        }
+    }
+    Ok(Some(hasher.finish()))
+}
+
+/// Feed all of `reader` to `hasher` in chunks; false if `token` was
+/// cancelled first.
+fn hash_span(reader: &mut impl Read, hasher: &mut DefaultHasher, token: &CancelToken) -> io::Result<bool> {
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        if token.is_cancelled() {
+            return Ok(false);
+        }
+        match reader.read(&mut buf)? {
+            0 => return Ok(true),
+            n => hasher.write(&buf[..n]),
+        }
+    }
+}
+
+/// Checksum `path` on the worker pool (see `checksum`), sending the result
+/// back as `(path, checksum)` unless `token` is cancelled first.
+pub fn spawn_checksum(pool: &WorkerPool, path: PathBuf, limit: u64, token: CancelToken, tx: Sender<(PathBuf, u64)>) {
+    pool.spawn(Priority::Low, token, move |token| {
+        if let Ok(Some(sum)) = checksum(&path, limit, token)
+            && !token.is_cancelled()
+        {
+            let _ = tx.send((path, sum));
+        }
+    });
+}
+
+/// How often a running preview command is checked for having finished or
+/// been replaced.
+const COMMAND_POLL: Duration = Duration::from_millis(10);
+
+/// Run `command` with `sh -c` in `dir` on its own thread, sending back
+/// `(path, output)` once it exits successfully, keeping at most `limit`
+/// bytes of its output. The command is killed once `token` is cancelled,
+/// i.e. the preview has been replaced.
+pub fn spawn_command(command: String, dir: PathBuf, path: PathBuf, limit: u64, token: CancelToken, tx: Sender<(PathBuf, String)>) {
+    thread::spawn(move || {
+        let Ok(mut child) = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return;
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+        // Read on another thread so a full pipe never stalls the command;
+        // stopping at the limit closes the pipe, which ends it
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stdout.take(limit).read_to_end(&mut output);
+            output
+        });
+        let status = loop {
+            if token.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return;
+            }
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => thread::sleep(COMMAND_POLL),
+                Err(_) => return,
+            }
+        };
+        let Ok(output) = reader.join() else {
+            return;
+        };
+        if status.success() && !token.is_cancelled() {
+            let _ = tx.send((path, String::from_utf8_lossy(&output).into_owned()));
+        }
+    });
+}
+
+/// Format a count with thousands separators, e.g. `12431` -> `12,431`.
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Format a byte count with a binary unit suffix, e.g. `1.2G`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", format_count(bytes as usize))
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+/// Parse a size like `100M`, `1.5G` or `4096` (bytes), binary units as
+/// `human_size` prints them.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let exponent = match unit.to_ascii_uppercase().trim_end_matches(['B', 'I']) {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return None,
+    };
+    (number >= 0.0).then(|| (number * 1024f64.powi(exponent)) as u64)
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+/// Whether rats runs with root privileges, where a slip costs more.
+#[cfg(unix)]
+pub fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn is_root() -> bool {
+    false
+}
+
+/// Whether the current user may create, rename or delete entries in `dir`.
+#[cfg(unix)]
+pub fn is_writable(dir: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn is_writable(dir: &Path) -> bool {
+    std::fs::metadata(dir).is_ok_and(|meta| !meta.permissions().readonly())
+}
@@ -0,0 +1,81 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    expand::{config_dir, expand},
+    pins::project_root,
+};
+
+/// Slices of a large repository that recursive scans stay within. Declared
+/// in `$XDG_CONFIG_HOME/rats/subtrees` (or `~/.config/rats/subtrees`), one
+/// `repository = subtree subtree ...` per line with subtrees relative to the
+/// repository, `#` starting a comment:
+///
+/// ```text
+/// ~/src/monorepo = services/payments libs/common
+/// ```
+///
+/// Repositories without a line there fall back to the directories of a
+/// cone-mode sparse checkout.
+pub fn active(repository: &Path) -> Vec<PathBuf> {
+    let declared = declared(repository);
+    if !declared.is_empty() {
+        return declared;
+    }
+    sparse_checkout(repository)
+}
+
+/// The active subtrees inside `dir`, outermost only. Empty when there are
+/// none, such as when `dir` is already inside one.
+pub fn within(dir: &Path) -> Vec<PathBuf> {
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let subtrees: Vec<PathBuf> = active(&project_root(&dir))
+        .into_iter()
+        .filter(|subtree| subtree.starts_with(&dir) && *subtree != dir && subtree.is_dir())
+        .collect();
+    // A subtree nested in another is already covered by it
+    subtrees
+        .iter()
+        .filter(|subtree| !subtrees.iter().any(|other| other != *subtree && subtree.starts_with(other)))
+        .cloned()
+        .collect()
+}
+
+fn declared(repository: &Path) -> Vec<PathBuf> {
+    let Some(file) = config_dir().map(|dir| dir.join("subtrees")) else {
+        return Vec::new();
+    };
+    let content = fs::read_to_string(file).unwrap_or_default();
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| line.split_once('='))
+        .filter(|(root, _)| {
+            let root = PathBuf::from(expand(root.trim()));
+            std::path::absolute(&root).is_ok_and(|root| root == repository)
+        })
+        .flat_map(|(_, subtrees)| subtrees.split_whitespace())
+        .map(|subtree| repository.join(subtree.trim_matches('/')))
+        .collect()
+}
+
+/// Directories included by `.git/info/sparse-checkout` in cone mode, where
+/// each is written as `/path/`. Cone mode also lists every parent of such a
+/// directory followed by `!/parent/*/`, which only includes its files, so
+/// those are skipped along with the top-level `/*` line.
+fn sparse_checkout(repository: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(repository.join(".git/info/sparse-checkout")) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = content.lines().map(str::trim).collect();
+    lines
+        .iter()
+        .filter(|line| line.starts_with('/') && !line.contains('*'))
+        .filter(|line| !lines.contains(&format!("!{}*/", line).as_str()))
+        .map(|line| line.trim_matches('/'))
+        .filter(|line| !line.is_empty())
+        .map(|line| repository.join(line))
+        .collect()
+}
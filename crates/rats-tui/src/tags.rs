@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{self, Path, PathBuf},
+};
+
+use ratatui::style::Color;
+
+use crate::history::state_dir;
+
+/// Chip colours, picked per tag name so a tag looks the same everywhere.
+const PALETTE: [Color; 6] = [
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Red,
+    Color::Cyan,
+];
+
+/// User-defined tags on paths, shown as chips in the list and filtered on
+/// with `tag:name`.
+#[derive(Debug, Default)]
+pub struct Tags {
+    by_path: HashMap<PathBuf, Vec<String>>,
+}
+
+impl Tags {
+    pub fn load() -> Tags {
+        let by_path = tags_file()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Tags { by_path }
+    }
+
+    pub fn get(&self, path: &Path) -> &[String] {
+        if self.by_path.is_empty() {
+            return &[];
+        }
+        path::absolute(path)
+            .ok()
+            .and_then(|path| self.by_path.get(&path))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Add `tag` to `path`, or remove it if already present. Returns whether
+    /// the path now has the tag.
+    pub fn toggle(&mut self, path: &Path, tag: &str) -> io::Result<bool> {
+        let path = path::absolute(path)?;
+        let tags = self.by_path.entry(path.clone()).or_default();
+        let added = match tags.iter().position(|existing| existing == tag) {
+            Some(index) => {
+                tags.remove(index);
+                false
+            }
+            None => {
+                tags.push(tag.to_string());
+                tags.sort();
+                true
+            }
+        };
+        if tags.is_empty() {
+            self.by_path.remove(&path);
+        }
+
+        let file = tags_file().ok_or_else(|| io::Error::other("no state directory"))?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&self.by_path).map_err(io::Error::other)?;
+        fs::write(file, content)?;
+        Ok(added)
+    }
+}
+
+fn tags_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("tags.json"))
+}
+
+pub fn color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize));
+    PALETTE[hash % PALETTE.len()]
+}
@@ -0,0 +1,81 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+
+/// Terminal I/O as the event loop sees it: somewhere to draw and a stream
+/// of input events. Events use crossterm's types whatever the backend, so
+/// key handling is written once; other backends translate into them. The
+/// backend also takes raw escape sequences, for graphics ratatui can't draw.
+pub trait Frontend {
+    type Backend: Backend + Write;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend>;
+
+    /// Wait up to `timeout` for an event; true if one is ready to `read`.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+
+    fn read(&mut self) -> io::Result<Event>;
+
+    /// Put the terminal back the way it was found.
+    fn restore(&mut self) -> io::Result<()>;
+}
+
+/// Where the interface is drawn: stdout, or the terminal itself when
+/// stdout is redirected, as in `$(rats)` or `rats | xargs`, where only the
+/// selection belongs.
+pub fn output() -> io::Result<Box<dyn Write>> {
+    if io::stdout().is_terminal() {
+        return Ok(Box::new(io::stdout()));
+    }
+    let tty = if cfg!(windows) { "CONOUT$" } else { "/dev/tty" };
+    Ok(Box::new(OpenOptions::new().write(true).open(tty)?))
+}
+
+/// The default frontend, built on crossterm.
+pub struct CrosstermFrontend {
+    terminal: Terminal<CrosstermBackend<Box<dyn Write>>>,
+}
+
+impl CrosstermFrontend {
+    pub fn new() -> io::Result<CrosstermFrontend> {
+        let mut output = output()?;
+        enable_raw_mode()?;
+        execute!(output, EnterAlternateScreen, EnableMouseCapture)?;
+        let terminal = Terminal::new(CrosstermBackend::new(output))?;
+        Ok(CrosstermFrontend { terminal })
+    }
+}
+
+impl Frontend for CrosstermFrontend {
+    type Backend = CrosstermBackend<Box<dyn Write>>;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend> {
+        &mut self.terminal
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        disable_raw_mode()?;
+        execute!(self.terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+        self.terminal.show_cursor()
+    }
+}
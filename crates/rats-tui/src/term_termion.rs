@@ -0,0 +1,169 @@
+use std::{
+    fs::File,
+    io::{self, IsTerminal},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{backend::TermionBackend, Terminal};
+use termion::{
+    event::{self as termion_event, Key},
+    input::{MouseTerminal, TermRead},
+    raw::{IntoRawMode, RawTerminal},
+    screen::{AlternateScreen, IntoAlternateScreen},
+};
+
+use crate::term::Frontend;
+
+type Output = AlternateScreen<MouseTerminal<RawTerminal<File>>>;
+
+/// Frontend on termion, for terminals where crossterm's input handling
+/// misbehaves. Enabled with the `termion` feature and `--backend termion`.
+pub struct TermionFrontend {
+    /// Dropped by `restore`, which is what resets the terminal
+    terminal: Option<Terminal<TermionBackend<Output>>>,
+    events: Receiver<io::Result<Event>>,
+    /// An event taken off the channel by `poll` but not yet `read`
+    pending: Option<io::Result<Event>>,
+}
+
+impl TermionFrontend {
+    pub fn new() -> io::Result<TermionFrontend> {
+        // termion (and ratatui on it) only sizes the terminal through stdout
+        if !io::stdout().is_terminal() {
+            return Err(io::Error::other("the termion backend can't draw with stdout redirected, use crossterm"));
+        }
+        // The terminal itself rather than stdin, which may be piped entries
+        let output = MouseTerminal::from(termion::get_tty()?.into_raw_mode()?).into_alternate_screen()?;
+        let terminal = Terminal::new(TermionBackend::new(output))?;
+        let input = termion::get_tty()?;
+
+        // termion only offers blocking reads, so read on a thread and poll
+        // the channel instead
+        let (tx, events) = mpsc::channel();
+        thread::spawn(move || {
+            for event in input.events() {
+                let event = match event {
+                    Ok(event) => match translate(event) {
+                        Some(event) => Ok(event),
+                        None => continue,
+                    },
+                    Err(err) => Err(err),
+                };
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(TermionFrontend {
+            terminal: Some(terminal),
+            events,
+            pending: None,
+        })
+    }
+}
+
+impl Frontend for TermionFrontend {
+    type Backend = TermionBackend<Output>;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend> {
+        self.terminal.as_mut().expect("terminal used after restore")
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        if self.pending.is_some() {
+            return Ok(true);
+        }
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => {
+                self.pending = Some(event);
+                Ok(true)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(false),
+            Err(RecvTimeoutError::Disconnected) => Err(io::Error::other("input closed")),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        match self.pending.take() {
+            Some(event) => event,
+            None => self
+                .events
+                .recv()
+                .map_err(|_| io::Error::other("input closed"))?,
+        }
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        if let Some(mut terminal) = self.terminal.take() {
+            terminal.show_cursor()?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a termion event to the crossterm one the event loop handles.
+fn translate(event: termion_event::Event) -> Option<Event> {
+    match event {
+        termion_event::Event::Key(key) => {
+            let (code, modifiers) = match key {
+                Key::Char('\n') => (KeyCode::Enter, KeyModifiers::NONE),
+                Key::Char('\t') => (KeyCode::Tab, KeyModifiers::NONE),
+                Key::Char(c) => (KeyCode::Char(c), KeyModifiers::NONE),
+                Key::Ctrl(c) => (KeyCode::Char(c), KeyModifiers::CONTROL),
+                Key::Alt(c) => (KeyCode::Char(c), KeyModifiers::ALT),
+                Key::Backspace => (KeyCode::Backspace, KeyModifiers::NONE),
+                Key::Delete => (KeyCode::Delete, KeyModifiers::NONE),
+                Key::Insert => (KeyCode::Insert, KeyModifiers::NONE),
+                Key::Left => (KeyCode::Left, KeyModifiers::NONE),
+                Key::Right => (KeyCode::Right, KeyModifiers::NONE),
+                Key::Up => (KeyCode::Up, KeyModifiers::NONE),
+                Key::Down => (KeyCode::Down, KeyModifiers::NONE),
+                Key::Home => (KeyCode::Home, KeyModifiers::NONE),
+                Key::End => (KeyCode::End, KeyModifiers::NONE),
+                Key::PageUp => (KeyCode::PageUp, KeyModifiers::NONE),
+                Key::PageDown => (KeyCode::PageDown, KeyModifiers::NONE),
+                Key::BackTab => (KeyCode::BackTab, KeyModifiers::SHIFT),
+                Key::F(n) => (KeyCode::F(n), KeyModifiers::NONE),
+                Key::Esc => (KeyCode::Esc, KeyModifiers::NONE),
+                _ => return None,
+            };
+            Some(Event::Key(KeyEvent::new(code, modifiers)))
+        }
+        termion_event::Event::Mouse(mouse) => {
+            let (kind, column, row) = match mouse {
+                termion_event::MouseEvent::Press(button, x, y) => {
+                    let kind = match button {
+                        termion_event::MouseButton::Left => MouseEventKind::Down(MouseButton::Left),
+                        termion_event::MouseButton::Right => MouseEventKind::Down(MouseButton::Right),
+                        termion_event::MouseButton::Middle => MouseEventKind::Down(MouseButton::Middle),
+                        termion_event::MouseButton::WheelUp => MouseEventKind::ScrollUp,
+                        termion_event::MouseButton::WheelDown => MouseEventKind::ScrollDown,
+                        termion_event::MouseButton::WheelLeft => MouseEventKind::ScrollLeft,
+                        termion_event::MouseButton::WheelRight => MouseEventKind::ScrollRight,
+                    };
+                    (kind, x, y)
+                }
+                termion_event::MouseEvent::Release(x, y) => {
+                    (MouseEventKind::Up(MouseButton::Left), x, y)
+                }
+                termion_event::MouseEvent::Hold(x, y) => {
+                    (MouseEventKind::Drag(MouseButton::Left), x, y)
+                }
+            };
+            // termion coordinates are 1-based
+            Some(Event::Mouse(MouseEvent {
+                kind,
+                column: column.saturating_sub(1),
+                row: row.saturating_sub(1),
+                modifiers: KeyModifiers::NONE,
+            }))
+        }
+        termion_event::Event::Unsupported(_) => None,
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::UNIX_EPOCH,
+};
+
+use crossterm::terminal;
+
+use sha2::{Digest, Sha256};
+
+use crate::names::path_to_bytes;
+
+/// Extensions ImageMagick can reliably thumbnail.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "tif", "tiff", "heic", "avif",
+];
+
+/// Longest side of a generated thumbnail, in pixels; big enough for the
+/// preview pane.
+const THUMBNAIL_PIXELS: u32 = 512;
+
+/// Pixel size of a cell when the terminal doesn't say.
+const CELL_PIXELS: (u32, u32) = (10, 20);
+
+/// A thumbnail to draw over the list, at a cell position in the terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub column: u16,
+    pub row: u16,
+    /// Size in cells
+    pub width: u16,
+    pub height: u16,
+    pub thumbnail: PathBuf,
+}
+
+/// Whether the terminal understands the kitty graphics protocol.
+pub fn kitty_supported() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    term.contains("kitty")
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+        || matches!(program.as_str(), "WezTerm" | "ghostty")
+}
+
+/// A way of drawing images in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Graphics {
+    /// The kitty graphics protocol, also spoken by WezTerm and Ghostty
+    Kitty,
+    /// iTerm2's inline images
+    Iterm,
+    /// Sixels, as in foot, mlterm or Windows Terminal
+    Sixel,
+}
+
+impl Graphics {
+    /// The best the terminal supports, going by its environment.
+    pub fn detect() -> Option<Graphics> {
+        let term = env::var("TERM").unwrap_or_default();
+        let program = env::var("TERM_PROGRAM").unwrap_or_default();
+        if kitty_supported() {
+            Some(Graphics::Kitty)
+        } else if program == "iTerm.app" || env::var("LC_TERMINAL").is_ok_and(|name| name == "iTerm2") {
+            Some(Graphics::Iterm)
+        } else if term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.contains("sixel")
+            || env::var_os("WT_SESSION").is_some()
+            || matches!(program.as_str(), "contour" | "mintty")
+        {
+            Some(Graphics::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Per-user cache directory: `$XDG_CACHE_HOME/rats` or `~/.cache/rats`.
+fn cache_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CACHE_HOME") {
+        Some(cache) if !cache.is_empty() => Some(PathBuf::from(cache).join("rats")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/rats")),
+    }
+}
+
+/// Cached PNG thumbnail for `image`, keyed by path, size and modification
+/// time so an edited image gets a fresh one.
+fn cache_path(image: &Path) -> Option<PathBuf> {
+    let meta = image.metadata().ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(path_to_bytes(&std::path::absolute(image).ok()?));
+    hasher.update(meta.len().to_le_bytes());
+    hasher.update(modified.as_nanos().to_le_bytes());
+    let name: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    Some(cache_dir()?.join("thumbnails").join(name + ".png"))
+}
+
+/// Thumbnail for `image`, generating it with ImageMagick's `program`
+/// (`magick` or `convert`) unless it is already cached. Slow, so run it in
+/// the background.
+pub fn generate(program: &str, image: &Path) -> Option<PathBuf> {
+    let thumbnail = cache_path(image)?;
+    if thumbnail.is_file() {
+        return Some(thumbnail);
+    }
+    fs::create_dir_all(thumbnail.parent()?).ok()?;
+
+    // Write beside the final name and rename, so a thumbnail is never read
+    // half-written
+    let partial = thumbnail.with_extension(format!("{}.tmp", std::process::id()));
+    let mut source = image.as_os_str().to_os_string();
+    source.push("[0]"); // first frame of animations and multi-page files
+    let mut target = std::ffi::OsString::from("png:");
+    target.push(&partial);
+    let status = Command::new(program)
+        .arg(source)
+        .args(["-auto-orient", "-thumbnail"])
+        .arg(format!("{0}x{0}", THUMBNAIL_PIXELS))
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+    if !status.success() {
+        let _ = fs::remove_file(&partial);
+        return None;
+    }
+    fs::rename(&partial, &thumbnail).ok()?;
+    Some(thumbnail)
+}
+
+/// Replace every image on screen with `placements`. Images sit above the
+/// text layer, so those from the previous frame are always removed first.
+pub fn place(out: &mut impl Write, placements: &[Placement]) -> io::Result<()> {
+    // Delete all placements and free their data; q=2 silences responses
+    out.write_all(b"\x1b_Ga=d,d=A,q=2\x1b\\")?;
+    if !placements.is_empty() {
+        out.write_all(b"\x1b7")?; // save cursor
+        for placement in placements {
+            // Cursor positions are 1-based; t=f has the terminal read the
+            // PNG itself, C=1 leaves the cursor where it is
+            write!(
+                out,
+                "\x1b[{};{}H\x1b_Gf=100,t=f,a=T,c={},r={},C=1,q=2;{}\x1b\\",
+                placement.row + 1,
+                placement.column + 1,
+                placement.width,
+                placement.height,
+                base64(&path_to_bytes(&placement.thumbnail))
+            )?;
+        }
+        out.write_all(b"\x1b8")?; // restore cursor
+    }
+    out.flush()
+}
+
+/// Draws placements with the terminal's protocol, keeping the sixels
+/// encoded so far.
+pub struct Painter {
+    pub graphics: Graphics,
+    /// ImageMagick, to encode sixels
+    program: &'static str,
+    sixels: HashMap<(PathBuf, u16, u16), Vec<u8>>,
+}
+
+impl Painter {
+    pub fn new(graphics: Graphics, program: &'static str) -> Painter {
+        Painter { graphics, program, sixels: HashMap::new() }
+    }
+
+    /// Whether the screen has to be drawn afresh before images are placed:
+    /// sixels and iTerm images are part of the text, and only go away when
+    /// something is drawn over them.
+    pub fn needs_redraw(&self) -> bool {
+        self.graphics != Graphics::Kitty
+    }
+
+    /// Draw `placements`, replacing those drawn before.
+    pub fn place(&mut self, out: &mut impl Write, placements: &[Placement]) -> io::Result<()> {
+        if self.graphics == Graphics::Kitty {
+            return place(out, placements);
+        }
+        out.write_all(b"\x1b7")?;
+        for placement in placements {
+            write!(out, "\x1b[{};{}H", placement.row + 1, placement.column + 1)?;
+            match self.graphics {
+                Graphics::Iterm => {
+                    let Ok(png) = fs::read(&placement.thumbnail) else {
+                        continue;
+                    };
+                    write!(
+                        out,
+                        "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=1:{}\x07",
+                        placement.width,
+                        placement.height,
+                        base64(&png)
+                    )?;
+                }
+                _ => {
+                    if let Some(sixel) = self.sixel(placement) {
+                        out.write_all(sixel)?;
+                    }
+                }
+            }
+        }
+        out.write_all(b"\x1b8")?;
+        out.flush()
+    }
+
+    /// The placement's thumbnail as sixels, scaled to its cells.
+    fn sixel(&mut self, placement: &Placement) -> Option<&Vec<u8>> {
+        let key = (placement.thumbnail.clone(), placement.width, placement.height);
+        if !self.sixels.contains_key(&key) {
+            let (cell_width, cell_height) = match terminal::window_size() {
+                Ok(size) if size.width > 0 && size.columns > 0 && size.rows > 0 => {
+                    (u32::from(size.width / size.columns), u32::from(size.height / size.rows))
+                }
+                _ => CELL_PIXELS,
+            };
+            let output = Command::new(self.program)
+                .arg(&placement.thumbnail)
+                .arg("-resize")
+                .arg(format!(
+                    "{}x{}",
+                    u32::from(placement.width) * cell_width,
+                    u32::from(placement.height) * cell_height
+                ))
+                .arg("sixel:-")
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            self.sixels.insert(key.clone(), output.stdout);
+        }
+        self.sixels.get(&key)
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+/// A load plus filter pass taking longer than this is reported as slow.
+const SLOW: Duration = Duration::from_millis(100);
+
+/// How long the last directory load and filter pass took, for `--timings`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    /// Reading, statting and ordering the directory entries
+    pub load: Duration,
+    /// Fuzzy matching the entries against the filter
+    pub scoring: Duration,
+    /// Ordering the matches
+    pub sorting: Duration,
+    /// Entries the filter pass looked at
+    pub entries: usize,
+}
+
+impl Timings {
+    /// Status line text, naming the slowest stage when the whole pass was
+    /// slow.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "load {} | filter {} ({} entries)",
+            millis(self.load),
+            millis(self.scoring + self.sorting),
+            self.entries
+        );
+        if self.load + self.scoring + self.sorting > SLOW {
+            let (stage, time) = [("IO", self.load), ("scoring", self.scoring), ("sorting", self.sorting)]
+                .into_iter()
+                .max_by_key(|&(_, time)| time)
+                .unwrap_or(("IO", self.load));
+            summary.push_str(&format!(" | slow: {} {}", stage, millis(time)));
+        }
+        summary
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{preview::LARGE_FILE_BYTES, walk};
+
+pub const DEFAULT_TAGS: [&str; 3] = ["TODO", "FIXME", "HACK"];
+
+/// A TODO-style marker found in a source file.
+#[derive(Debug, Clone)]
+pub struct Todo {
+    pub path: PathBuf,
+    pub line: usize,
+    pub tag: String,
+    pub text: String,
+}
+
+impl Todo {
+    /// One-line summary used as the list label, relative to `root`.
+    pub fn label(&self, root: &Path) -> String {
+        let path = self.path.strip_prefix(root).unwrap_or(&self.path);
+        format!("{:<5} {}:{} {}", self.tag, path.display(), self.line, self.text)
+    }
+}
+
+/// Scan every text file under `roots` for the given tags, yielding the
+/// markers as they are found, one directory at a time.
+pub fn scan(roots: Vec<PathBuf>, tags: Vec<String>) -> impl Iterator<Item = Todo> {
+    roots
+        .into_iter()
+        .flat_map(|root| walk::files(root, false))
+        .flat_map(move |path| scan_file(path, &tags))
+}
+
+/// The markers in one file; none for large, binary or non-UTF-8 files.
+fn scan_file(path: PathBuf, tags: &[String]) -> Vec<Todo> {
+    if fs::metadata(&path).map_or(true, |meta| meta.len() > LARGE_FILE_BYTES * 20) {
+        return Vec::new();
+    }
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    if content.contains('\0') {
+        return Vec::new();
+    }
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (tag, text) = find_tag(line, tags)?;
+            Some(Todo {
+                path: path.clone(),
+                line: i + 1,
+                tag: tag.to_string(),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Where a marker's tag comes among `tags`, which the list is grouped by.
+pub fn rank(todo_tag: &str, tags: &[String]) -> usize {
+    tags.iter().position(|tag| tag == todo_tag).unwrap_or(tags.len())
+}
+
+/// Find the first tag appearing as a whole word in `line`, returning it and
+/// the comment text that follows.
+fn find_tag<'a>(line: &str, tags: &'a [String]) -> Option<(&'a str, String)> {
+    tags.iter().find_map(|tag| {
+        let mut search_from = 0;
+        while let Some(offset) = line[search_from..].find(tag.as_str()) {
+            let start = search_from + offset;
+            let end = start + tag.len();
+            let before = line[..start].chars().next_back();
+            let after = line[end..].chars().next();
+            let word_start = before.is_none_or(|c| !c.is_alphanumeric() && c != '_');
+            let word_end = after.is_none_or(|c| matches!(c, ':' | '(' | ' ' | '!'));
+            if word_start && word_end {
+                let text = line[end..]
+                    .trim_start_matches(|c: char| c == ':' || c == '!' || c.is_whitespace())
+                    .trim_end_matches("*/")
+                    .trim()
+                    .to_string();
+                return Some((tag.as_str(), text));
+            }
+            search_from = end;
+        }
+        None
+    })
+}
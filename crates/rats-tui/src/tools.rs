@@ -0,0 +1,96 @@
+use std::{collections::HashMap, env, path::Path};
+
+/// External programs rats can hand work off to when they are installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tool {
+    Bat,
+    Rg,
+    Fd,
+    Zoxide,
+    Udisksctl,
+    WlCopy,
+    Xclip,
+    Pbcopy,
+    /// ImageMagick 7
+    Magick,
+    /// ImageMagick 6
+    Convert,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 10] = [
+        Tool::Bat,
+        Tool::Rg,
+        Tool::Fd,
+        Tool::Zoxide,
+        Tool::Udisksctl,
+        Tool::WlCopy,
+        Tool::Xclip,
+        Tool::Pbcopy,
+        Tool::Magick,
+        Tool::Convert,
+    ];
+
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Tool::Bat => "bat",
+            Tool::Rg => "rg",
+            Tool::Fd => "fd",
+            Tool::Zoxide => "zoxide",
+            Tool::Udisksctl => "udisksctl",
+            Tool::WlCopy => "wl-copy",
+            Tool::Xclip => "xclip",
+            Tool::Pbcopy => "pbcopy",
+            Tool::Magick => "magick",
+            Tool::Convert => "convert",
+        }
+    }
+}
+
+/// Availability of each external tool, detected once at startup so a
+/// missing program costs a single PATH scan rather than a failed spawn on
+/// every selection.
+#[derive(Debug, Clone)]
+pub struct Tools {
+    available: HashMap<Tool, bool>,
+}
+
+impl Tools {
+    pub fn detect() -> Tools {
+        let available = Tool::ALL
+            .iter()
+            .map(|&tool| (tool, find_in_path(tool.binary())))
+            .collect();
+        Tools { available }
+    }
+
+    pub fn is_available(&self, tool: Tool) -> bool {
+        self.available.get(&tool).copied().unwrap_or(false)
+    }
+
+    /// Record that a tool turned out to be unusable (e.g. it vanished or
+    /// failed to spawn) so later calls go straight to the fallback.
+    pub fn mark_unavailable(&mut self, tool: Tool) {
+        self.available.insert(tool, false);
+    }
+}
+
+fn find_in_path(binary: &str) -> bool {
+    let Some(paths) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&paths).any(|dir| is_executable(&dir.join(binary)))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
@@ -0,0 +1,140 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A file in the freedesktop.org trash, along with where it came from.
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    /// Location of the file inside `Trash/files`
+    pub path: PathBuf,
+    pub original_path: PathBuf,
+    /// `DeletionDate` from the .trashinfo file, e.g. `2024-03-01T14:22:05`
+    pub deleted_at: String,
+}
+
+impl TrashedItem {
+    /// One-line summary used as the list label.
+    pub fn label(&self) -> String {
+        format!(
+            "{}  (deleted {})",
+            self.original_path.display(),
+            self.deleted_at.replacen('T', " ", 1)
+        )
+    }
+}
+
+/// The user's home trash: `$XDG_DATA_HOME/Trash`, or `~/.local/share/Trash`.
+pub fn trash_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data) if !data.is_empty() => Some(PathBuf::from(data).join("Trash")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/Trash")),
+    }
+}
+
+/// Everything currently in the trash, most recently deleted first.
+pub fn list() -> Vec<TrashedItem> {
+    let Some(trash) = trash_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(trash.join("files")) else {
+        return Vec::new();
+    };
+
+    let mut items: Vec<TrashedItem> = entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            let (original_path, deleted_at) = read_info(&info_path(&trash, &path))
+                .unwrap_or_else(|| (PathBuf::from(entry.file_name()), "unknown".to_string()));
+            TrashedItem {
+                path,
+                original_path,
+                deleted_at,
+            }
+        })
+        .collect();
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+/// Move `path` to the system trash (the freedesktop.org trash on Linux,
+/// the Recycle Bin or the macOS Trash elsewhere).
+pub fn send(path: &Path) -> io::Result<()> {
+    ::trash::delete(path).map_err(|err| io::Error::other(err.to_string()))
+}
+
+/// Move a trashed file back to its original location. Fails rather than
+/// overwriting if something already exists there.
+pub fn restore(trashed: &Path) -> io::Result<PathBuf> {
+    let trash = trash_dir().ok_or_else(|| io::Error::other("no trash directory"))?;
+    let info = info_path(&trash, trashed);
+    let (original, _) = read_info(&info)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "missing .trashinfo"))?;
+
+    if original.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", original.display()),
+        ));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(trashed, &original)?;
+    fs::remove_file(info)?;
+    Ok(original)
+}
+
+/// Permanently delete a trashed file and its metadata.
+pub fn purge(trashed: &Path) -> io::Result<()> {
+    let trash = trash_dir().ok_or_else(|| io::Error::other("no trash directory"))?;
+    if trashed.is_dir() {
+        fs::remove_dir_all(trashed)?;
+    } else {
+        fs::remove_file(trashed)?;
+    }
+    // A missing .trashinfo is not worth failing over once the data is gone
+    let _ = fs::remove_file(info_path(&trash, trashed));
+    Ok(())
+}
+
+fn info_path(trash: &Path, trashed: &Path) -> PathBuf {
+    let mut name = trashed.file_name().unwrap_or_default().to_os_string();
+    name.push(".trashinfo");
+    trash.join("info").join(name)
+}
+
+/// Read `Path=` and `DeletionDate=` from a .trashinfo file.
+fn read_info(info: &Path) -> Option<(PathBuf, String)> {
+    let content = fs::read_to_string(info).ok()?;
+    let mut path = None;
+    let mut date = String::from("unknown");
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(PathBuf::from(percent_decode(value)));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            date = value.to_string();
+        }
+    }
+    Some((path?, date))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = value.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
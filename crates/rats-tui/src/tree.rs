@@ -0,0 +1,99 @@
+use std::{fs, path::Path};
+
+use crate::walk::SKIPPED_DIRS;
+
+/// Entries rendered before the tree is cut off, so exporting `/` by accident
+/// doesn't walk the whole disk.
+const MAX_ENTRIES: usize = 5000;
+
+/// Entries looked at before the tree is cut off, kept or not, so a filter
+/// matching little doesn't walk the whole disk either.
+const MAX_VISITED: usize = 50_000;
+
+/// How much of the tree is left to render and to look at.
+struct Budget {
+    kept: usize,
+    visited: usize,
+}
+
+impl Budget {
+    fn spent(&self) -> bool {
+        self.kept == 0 || self.visited == 0
+    }
+}
+
+/// Render the directory tree under `root` as `tree`-style text. Only files
+/// whose names satisfy `keep` are included, along with the directories
+/// leading to them; hidden entries and build directories are skipped.
+pub fn render(root: &Path, keep: &dyn Fn(&str) -> bool) -> String {
+    let mut lines = vec![root.display().to_string()];
+    let mut budget = Budget {
+        kept: MAX_ENTRIES,
+        visited: MAX_VISITED,
+    };
+    render_dir(root, keep, &mut lines, &mut budget);
+    if budget.kept == 0 {
+        lines.push(format!("… truncated after {} entries", MAX_ENTRIES));
+    } else if budget.visited == 0 {
+        lines.push(format!("… stopped after looking at {} entries", MAX_VISITED));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Append the lines for the children of `dir`. Returns true if anything was
+/// kept.
+fn render_dir(
+    dir: &Path,
+    keep: &dyn Fn(&str) -> bool,
+    lines: &mut Vec<String>,
+    budget: &mut Budget,
+) -> bool {
+    if budget.spent() {
+        return false;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    let mut children: Vec<_> = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| {
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            (is_dir, entry.file_name().to_string_lossy().into_owned())
+        })
+        .filter(|(is_dir, name)| !(*is_dir && SKIPPED_DIRS.contains(&name.as_str())))
+        .collect();
+    // Directories first, like the list
+    children.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    // Render each child into its own buffer first: whether it is kept (and
+    // so which child is last) is only known once its subtree is done
+    let mut rendered: Vec<(String, Vec<String>)> = Vec::new();
+    for (is_dir, name) in children {
+        if budget.spent() {
+            break;
+        }
+        budget.visited -= 1;
+        let mut sub_lines = Vec::new();
+        let kept = if is_dir {
+            let has_kept_children = render_dir(&dir.join(&name), keep, &mut sub_lines, budget);
+            has_kept_children || keep(&name)
+        } else {
+            keep(&name)
+        };
+        if kept {
+            budget.kept = budget.kept.saturating_sub(1);
+            let name = if is_dir { format!("{}/", name) } else { name };
+            rendered.push((name, sub_lines));
+        }
+    }
+
+    let count = rendered.len();
+    for (index, (name, sub_lines)) in rendered.into_iter().enumerate() {
+        let last = index + 1 == count;
+        let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        lines.push(format!("{}{}", branch, name));
+        lines.extend(sub_lines.into_iter().map(|line| format!("{}{}", indent, line)));
+    }
+    count > 0
+}
@@ -0,0 +1,271 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use crate::{app::App, config::Source, dirdiff::Change, gallery::{self, Grid}, mouse::PaneAreas, thumbnail::{self, Placement}, widgets::{Component, FileList, Popup, PreviewPane, StatusBar}};
+
+pub fn ui(f: &mut Frame, app: &mut App) {
+    let chrome = &app.config.chrome;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(if chrome.minimal { 0 } else { 1 })
+        .constraints([
+            Constraint::Length(if chrome.minimal { 0 } else { chrome.bar_height() }),
+            Constraint::Min(0),
+            Constraint::Length(chrome.bar_height()),
+        ])
+        .split(f.area());
+
+    // Header with current path
+    let hidden_here = app
+        .dismissed
+        .iter()
+        .filter(|path| path.parent() == Some(app.current_path.as_path()))
+        .count();
+    let header_text = match app.config.source {
+        Source::Browse if app.config.chooser.is_some() => format!(
+            "{}: {}",
+            app.config.chooser.as_ref().map_or("", |request| request.title()),
+            app.current_path.display()
+        ),
+        Source::Browse if app.config.recursive && app.loading.is_some() => format!(
+            "Files under: {} (walking, {} so far; Esc to stop)",
+            app.current_path.display(),
+            app.items.len()
+        ),
+        Source::Browse if app.config.recursive => {
+            format!("Files under: {} ({} entries)", app.current_path.display(), app.items.len())
+        }
+        Source::Browse if app.loading.is_some() => format!(
+            "Path: {} (reading, {} entries so far; Esc to stop)",
+            app.current_path.display(),
+            app.items.len()
+        ),
+        Source::Browse if hidden_here > 0 => format!(
+            "Path: {} ({} hidden, X to show)",
+            app.current_path.display(),
+            hidden_here
+        ),
+        Source::Browse => format!("Path: {}", app.current_path.display()),
+        Source::Diagnostics => format!("Diagnostics: {} from stdin", app.items.len()),
+        Source::Todos if app.loading.is_some() => format!(
+            "TODOs: {} so far in {} (scanning; Esc to stop)",
+            app.items.len(),
+            app.current_path.display()
+        ),
+        Source::Todos if app.scanned_subtrees > 0 => format!(
+            "TODOs: {} in {} ({} active subtrees, :widen for all)",
+            app.items.len(),
+            app.current_path.display(),
+            app.scanned_subtrees
+        ),
+        Source::Todos => format!("TODOs: {} in {}", app.items.len(), app.current_path.display()),
+        Source::Cargo => format!("Cargo: {} entries in {}", app.items.len(), app.current_path.display()),
+        Source::Man => format!("Man pages: {}", app.items.len()),
+        Source::Grep if app.filter.is_empty() => {
+            format!("Grep: type to search the files in {} (Ctrl+g for names)", app.current_path.display())
+        }
+        Source::Grep if app.loading.is_some() => format!(
+            "Grep: \"{}\" in {} ({} so far; Esc to stop)",
+            app.filter,
+            app.current_path.display(),
+            app.items.len()
+        ),
+        Source::Grep => format!(
+            "Grep: \"{}\" in {} ({} matches)",
+            app.filter,
+            app.current_path.display(),
+            app.items.len()
+        ),
+        Source::Clipboard => format!("Clipboard: {} clips (Enter to copy)", app.items.len()),
+        Source::Trash => format!("Trash: {} items", app.items.len()),
+        Source::Mounts => format!("Mounts: {}", app.items.len()),
+        Source::History => format!("History: {} selections (Ctrl+r to close)", app.items.len()),
+        Source::Bookmarks => format!("Bookmarks: {} (Enter to browse, M to close)", app.items.len()),
+        Source::Jump => format!("Jump: {} directories (Enter to browse, J to close)", app.items.len()),
+        Source::Command if app.loading.is_some() => format!(
+            "Command: {} ({} so far; Esc to stop)",
+            app.config.default_command.as_deref().unwrap_or_default(),
+            app.items.len()
+        ),
+        Source::Command => format!(
+            "Command: {} ({} entries)",
+            app.config.default_command.as_deref().unwrap_or_default(),
+            app.items.len()
+        ),
+        Source::Stdin if app.loading.is_some() => format!("Stdin: {} lines so far (Esc to stop)", app.items.len()),
+        Source::Stdin => format!("Stdin: {} lines", app.items.len()),
+        Source::Oplog => format!("Operation log: {} entries (:oplog to close)", app.items.len()),
+        Source::Diff => match &app.diff {
+            Some(diff) if app.loading.is_some() => format!(
+                "Diff: {} <> {} | comparing, {} differences so far (Esc to stop)",
+                diff.left.display(),
+                diff.right.display(),
+                diff.differences.len()
+            ),
+            Some(diff) => format!(
+                "Diff: {} <> {} | {} only left, {} only right, {} differ (:copy-missing, :diff to close)",
+                diff.left.display(),
+                diff.right.display(),
+                diff.count(Change::OnlyLeft),
+                diff.count(Change::OnlyRight),
+                diff.count(Change::Differs)
+            ),
+            None => "Diff".to_string(),
+        },
+    };
+    // Make elevated or restricted contexts hard to miss
+    let mut header_spans = Vec::new();
+    if app.is_root {
+        header_spans.push(Span::styled(
+            " ROOT ",
+            Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+        header_spans.push(Span::raw(" "));
+    }
+    if app.network && app.config.source == Source::Browse {
+        header_spans.push(Span::styled(
+            " NETWORK ",
+            Style::default().fg(Color::Black).bg(Color::Blue).add_modifier(Modifier::BOLD),
+        ));
+        header_spans.push(Span::raw(" "));
+    }
+    if app.read_only && app.config.source == Source::Browse {
+        header_spans.push(Span::styled(
+            " READ-ONLY ",
+            Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+        header_spans.push(Span::raw(" "));
+    }
+    header_spans.push(Span::raw(header_text));
+    let header = Paragraph::new(Line::from(header_spans))
+        .block(app.config.chrome.block("Folder Browser"))
+        .style(Style::default().fg(app.config.chrome.colors.header));
+    if !app.config.chrome.minimal {
+        f.render_widget(header, chunks[0]);
+    }
+
+    if app.gallery {
+        draw_gallery(f, app, chunks[1]);
+    } else {
+        draw_panes(f, app, chunks[1]);
+    }
+
+    // Plan screen for a batch operation, over both panes
+    if app.plan.is_some() {
+        Popup.render(f, chunks[1], app);
+    }
+
+    StatusBar.render(f, chunks[2], app);
+}
+
+/// The file list beside the preview.
+fn draw_panes(f: &mut Frame, app: &mut App, area: Rect) {
+    // Split main area horizontally: file list on left, preview on right
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(if app.show_preview {
+            [Constraint::Percentage(50), Constraint::Percentage(50)]
+        } else {
+            [Constraint::Percentage(100), Constraint::Length(0)]
+        })
+        .split(area);
+
+    FileList.render(f, main_chunks[0], app);
+    if app.show_preview {
+        PreviewPane.render(f, main_chunks[1], app);
+    } else {
+        app.pane_areas.preview = Rect::default();
+    }
+}
+
+/// Entries as a grid of cells, each a thumbnail (or icon) over the name and,
+/// for images, the size in pixels.
+fn draw_gallery(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = app.config.chrome.block("Gallery");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut grid = Grid::new(inner, app.grid.first_row);
+    let selected = app.list_state.selected().unwrap_or(0);
+    grid.scroll_to(selected);
+    app.grid = grid;
+    app.pane_areas = PaneAreas {
+        list: inner,
+        preview: Rect::default(),
+    };
+
+    app.placements.clear();
+    for index in grid.visible(app.filtered_items.len()) {
+        let Some(cell) = grid.cell(index) else {
+            continue;
+        };
+        let entry = app.items[app.filtered_items[index].0].clone();
+        let is_image = thumbnail::is_image(&entry.path);
+        let border = if index == selected {
+            Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD)
+        } else if app.marked.contains(&entry.path) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let cell_block = app.config.chrome.framed(Block::default()).border_style(border);
+        let cell_inner = cell_block.inner(cell);
+        f.render_widget(cell_block, cell);
+
+        let picture = Rect {
+            height: cell_inner.height.saturating_sub(gallery::CAPTION_ROWS),
+            ..cell_inner
+        };
+        let caption = Rect {
+            y: picture.y + picture.height,
+            height: cell_inner.height - picture.height,
+            ..cell_inner
+        };
+
+        // The thumbnail when there is one, otherwise a centered icon
+        if is_image && app.show_thumbnails {
+            app.request_thumbnail(&entry.path);
+        }
+        let thumbnail = app.thumbnails.get(&entry.path).cloned().flatten();
+        match thumbnail.and_then(|thumbnail| Some((app.image_dimensions(&thumbnail)?, thumbnail))) {
+            Some((pixels, thumbnail)) if app.plan.is_none() => {
+                let (width, height) = gallery::fit(pixels, (picture.width, picture.height));
+                app.placements.push(Placement {
+                    column: picture.x + (picture.width - width) / 2,
+                    row: picture.y + (picture.height - height) / 2,
+                    width,
+                    height,
+                    thumbnail,
+                });
+            }
+            _ => {
+                let icon = if entry.is_dir() {
+                    "📁"
+                } else if is_image {
+                    "📷"
+                } else {
+                    "📄"
+                };
+                let middle = Rect {
+                    y: picture.y + picture.height / 2,
+                    height: 1.min(picture.height),
+                    ..picture
+                };
+                f.render_widget(Paragraph::new(icon).alignment(Alignment::Center), middle);
+            }
+        }
+
+        let mut lines = vec![Line::raw(entry.display_name())];
+        if is_image && let Some((width, height)) = app.image_dimensions(&entry.path) {
+            lines.push(Line::styled(
+                format!("{}×{}", width, height),
+                Style::default().add_modifier(Modifier::DIM),
+            ));
+        }
+        f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), caption);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    process::Command,
+};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/aharvey101/rats/releases/latest";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Version string shown by `--version`, including build metadata.
+pub fn version_string() -> String {
+    format!(
+        "rats {} ({} {}, {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("RATS_GIT_COMMIT"),
+        env!("RATS_BUILD_DATE"),
+        env!("RATS_TARGET")
+    )
+}
+
+/// A version like `1.2.3` or `1.3.0-rc.1`, ordered as semver orders them
+/// (ignoring build metadata): a pre-release comes before its release.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    /// Without trailing zeros, so `1.2` and `1.2.0` are equal
+    numbers: Vec<u64>,
+    pre: PreRelease,
+}
+
+/// `None`, for a release, comes after any pre-release.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum PreRelease {
+    Some(Vec<Identifier>),
+    None,
+}
+
+/// A dot-separated part of a pre-release: numbers sort before words, and
+/// numerically among themselves.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    Number(u64),
+    Word(String),
+}
+
+impl Version {
+    fn parse(text: &str) -> Option<Version> {
+        let text = text.split('+').next()?;
+        let (numbers, pre) = match text.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre)),
+            None => (text, None),
+        };
+        let mut numbers = numbers.split('.').map(|number| number.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        let pre = match pre {
+            Some(pre) => PreRelease::Some(
+                pre.split('.')
+                    .map(|part| part.parse().map_or_else(|_| Identifier::Word(part.to_string()), Identifier::Number))
+                    .collect(),
+            ),
+            None => PreRelease::None,
+        };
+        Some(Version { numbers, pre })
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Replace the running binary with the latest GitHub release, if it is
+/// newer. With `check_only`, just report whether an update is available.
+///
+/// The download is checked against the release's `SHA256SUMS`, which
+/// catches a corrupted download but, coming from the same place, not a
+/// tampered release: there is no signature to verify yet.
+pub fn self_update(check_only: bool) -> Result<(), Box<dyn Error>> {
+    let release: Release = serde_json::from_slice(&curl(RELEASES_URL)?)?;
+    let latest = release.tag_name.trim_start_matches('v');
+    let current = env!("CARGO_PKG_VERSION");
+
+    let newer = match (Version::parse(latest), Version::parse(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => return Err(format!("cannot compare release {} with {}", release.tag_name, current).into()),
+    };
+    if !newer {
+        println!("rats {} is up to date (latest release: {})", current, latest);
+        return Ok(());
+    }
+    println!("Update available: {} -> {}", current, latest);
+    if check_only {
+        return Ok(());
+    }
+
+    let asset_name = format!("rats-{}", env!("RATS_TARGET"));
+    let find_asset = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| format!("release {} has no {} asset", release.tag_name, name))
+    };
+    let binary_asset = find_asset(&asset_name)?;
+    let checksums_asset = find_asset(CHECKSUMS_ASSET)?;
+
+    let checksums = String::from_utf8(curl(&checksums_asset.browser_download_url)?)?;
+    let expected = checksums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == asset_name)
+        .map(|(hash, _)| hash.to_lowercase())
+        .ok_or_else(|| format!("{} has no entry for {}", CHECKSUMS_ASSET, asset_name))?;
+
+    let binary = curl(&binary_asset.browser_download_url)?;
+    let actual = hex(&Sha256::digest(&binary));
+    if actual != expected {
+        return Err(format!("checksum mismatch: expected {}, got {}", expected, actual).into());
+    }
+
+    // Write next to the current executable and rename over it, which is
+    // atomic on the same filesystem and safe while the old binary runs.
+    let current_exe = env::current_exe()?;
+    let staged = current_exe.with_extension("update");
+    fs::write(&staged, &binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    fs::rename(&staged, &current_exe)?;
+
+    println!("Updated {} to {}", current_exe.display(), latest);
+    Ok(())
+}
+
+fn curl(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "-H", "User-Agent: rats-self-update", url])
+        .output()
+        .map_err(|err| format!("failed to run curl: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "download of {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,49 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::ModifyKind,
+};
+
+/// Notices entries appearing in or leaving one directory (not below it),
+/// so the listing can be refreshed without re-entering it.
+pub struct DirWatcher {
+    pub dir: PathBuf,
+    // Watching stops when this is dropped
+    _watcher: RecommendedWatcher,
+    rx: Receiver<()>,
+}
+
+impl DirWatcher {
+    /// Start watching `dir`, or `None` where that isn't possible (out of
+    /// inotify watches, a filesystem without change events, ...).
+    pub fn new(dir: &Path) -> Option<DirWatcher> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // Writes to a file don't change the listing
+            if let Ok(event) = event
+                && matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+                )
+            {
+                let _ = tx.send(());
+            }
+        })
+        .ok()?;
+        watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+        Some(DirWatcher {
+            dir: dir.to_path_buf(),
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Whether anything came or went since the last call.
+    pub fn changed(&self) -> bool {
+        self.rx.try_iter().count() > 0
+    }
+}
@@ -0,0 +1,806 @@
+use std::time::{Duration, SystemTime};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+use crate::{
+    action::Action,
+    ansi,
+    app::App,
+    config::{SortOrder, Source},
+    fold,
+    grep,
+    mode::Mode,
+    names::safe_filename_to_string,
+    plan::FileOp,
+    preview,
+    tags,
+    thumbnail::{self, Placement},
+};
+
+/// Line style of pane borders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BorderStyle {
+    /// No borders, only titles
+    None,
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl BorderStyle {
+    pub fn from_name(name: &str) -> Option<BorderStyle> {
+        match name {
+            "none" => Some(BorderStyle::None),
+            "plain" => Some(BorderStyle::Plain),
+            "rounded" => Some(BorderStyle::Rounded),
+            "double" => Some(BorderStyle::Double),
+            "thick" => Some(BorderStyle::Thick),
+            _ => None,
+        }
+    }
+
+    fn border_type(self) -> Option<BorderType> {
+        match self {
+            BorderStyle::None => None,
+            BorderStyle::Plain => Some(BorderType::Plain),
+            BorderStyle::Rounded => Some(BorderType::Rounded),
+            BorderStyle::Double => Some(BorderType::Double),
+            BorderStyle::Thick => Some(BorderType::Thick),
+        }
+    }
+}
+
+/// Colors of the parts of the UI that can be changed with `--color`.
+#[derive(Debug, Clone)]
+pub struct Colors {
+    pub header: Color,
+    /// Background of the selected entry
+    pub selection: Color,
+    pub marked: Color,
+    /// Characters of a name that the filter matched
+    pub matched: Color,
+    /// Border of the preview while it has focus
+    pub focus: Color,
+    pub preview: Color,
+}
+
+impl Default for Colors {
+    fn default() -> Colors {
+        Colors {
+            header: Color::Cyan,
+            selection: Color::LightBlue,
+            marked: Color::Yellow,
+            matched: Color::Green,
+            focus: Color::Yellow,
+            preview: Color::White,
+        }
+    }
+}
+
+impl Colors {
+    /// Apply `part=color` pairs separated by commas, like
+    /// `header=red,selection=#334455`. Colors are names, `#rrggbb` or
+    /// 256-color indices.
+    pub fn set(&mut self, spec: &str) -> Result<(), String> {
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (part, color) = pair.split_once('=').ok_or_else(|| format!("expected part=color, got {}", pair))?;
+            let color: Color = color.trim().parse().map_err(|_| format!("unknown color: {}", color))?;
+            match part.trim() {
+                "header" => self.header = color,
+                "selection" => self.selection = color,
+                "marked" => self.marked = color,
+                "matched" => self.matched = color,
+                "focus" => self.focus = color,
+                "preview" => self.preview = color,
+                part => {
+                    return Err(format!("unknown part: {} (expected header, selection, marked, matched, focus or preview)", part));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decoration around the panes.
+#[derive(Debug, Clone)]
+pub struct ChromeConfig {
+    /// No header, borders or titles, and a bare prompt line in place of the
+    /// help bar, leaving every other row for entries
+    pub minimal: bool,
+    pub border: BorderStyle,
+    pub title_alignment: Alignment,
+    pub colors: Colors,
+}
+
+impl Default for ChromeConfig {
+    fn default() -> ChromeConfig {
+        ChromeConfig {
+            minimal: false,
+            border: BorderStyle::Plain,
+            title_alignment: Alignment::Left,
+            colors: Colors::default(),
+        }
+    }
+}
+
+impl ChromeConfig {
+    pub fn alignment_from_name(name: &str) -> Option<Alignment> {
+        match name {
+            "left" => Some(Alignment::Left),
+            "center" => Some(Alignment::Center),
+            "right" => Some(Alignment::Right),
+            _ => None,
+        }
+    }
+
+    /// The frame of a pane titled `title`; nothing at all in minimal mode.
+    pub fn block<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        if self.minimal {
+            return Block::default();
+        }
+        let block = Block::default().title(title).title_alignment(self.title_alignment);
+        self.borders(block)
+    }
+
+    /// `block` with the configured borders, for frames that stay even in
+    /// minimal mode, such as popups and gallery cells. With no border style
+    /// they fall back to plain lines.
+    pub fn framed<'a>(&self, block: Block<'a>) -> Block<'a> {
+        let kind = self.border.border_type().unwrap_or(BorderType::Plain);
+        block.borders(Borders::ALL).border_type(kind).title_alignment(self.title_alignment)
+    }
+
+    /// Rows taken by a one-line bar such as the header: one for the text,
+    /// plus its borders or title.
+    pub fn bar_height(&self) -> u16 {
+        match (self.minimal, self.border) {
+            (true, _) => 1,
+            (false, BorderStyle::None) => 2,
+            (false, _) => 3,
+        }
+    }
+
+    fn borders<'a>(&self, block: Block<'a>) -> Block<'a> {
+        match self.border.border_type() {
+            Some(kind) => block.borders(Borders::ALL).border_type(kind),
+            None => block,
+        }
+    }
+}
+
+/// A part of the screen that draws itself from the app state and handles
+/// the keys meant for it, so layouts can place it wherever they like.
+pub trait Component {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App);
+
+    /// What `key` does here, or None when it isn't one for this component.
+    fn action(&self, _app: &App, _key: KeyCode) -> Option<Action> {
+        None
+    }
+}
+
+/// The filtered entries, with marks, pins, tags, notes and thumbnails.
+pub struct FileList;
+
+impl Component for FileList {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        // With the preview hidden, entries can carry an excerpt instead. Request
+        // them, image thumbnails and modification times for every entry that
+        // could end up on screen.
+        let show_excerpts = app.config.excerpts && !app.show_preview;
+        let show_dates = app.config.sort == SortOrder::Taken || !app.query.taken.is_empty();
+        let show_ages = app.config.age_colors && !app.network;
+        let block = app.config.chrome.block("Files");
+        // Header lines stay put above the entries, which scroll below them
+        let header: Vec<Line> = app
+            .header()
+            .map(|line| Line::styled(line.to_string(), Style::default().fg(app.config.chrome.colors.header)))
+            .collect();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(header.len() as u16), Constraint::Min(0)])
+            .split(block.inner(area));
+        let inner = chunks[1];
+        app.pane_areas.list = inner;
+        if show_excerpts || app.show_thumbnails || show_ages {
+            let height = inner.height as usize;
+            let selected = app.list_state.selected().unwrap_or(0);
+            let offset = app.list_state.offset();
+            let window = selected.saturating_sub(height)..selected + height;
+            for position in window.chain(offset..offset + height) {
+                if let Some(&(i, _, _)) = app.filtered_items.get(position) {
+                    let entry = app.items[i].clone();
+                    if show_excerpts {
+                        app.request_excerpt(&entry);
+                    }
+                    if app.show_thumbnails && thumbnail::is_image(&entry.path) {
+                        app.request_thumbnail(&entry.path);
+                    }
+                    if show_ages && entry.label.is_none() {
+                        app.modified(&entry.path);
+                    }
+                }
+            }
+        }
+
+        // For images with a thumbnail, the icon is left blank and its column
+        // noted so the thumbnail can be drawn over it.
+        let mut thumbnail_columns = Vec::new();
+        let items: Vec<ListItem> = app
+            .filtered_items
+            .iter()
+            .enumerate()
+            .map(|(position, &(i, _score, ref matched))| {
+                let entry = &app.items[i];
+                let name = entry.display_name();
+                let thumbnail = app.thumbnails.get(&entry.path).cloned().flatten();
+
+                let icon = if entry.label.is_some() {
+                    ""
+                } else if name == ".." || entry.is_dir() {
+                    "📁 "
+                } else if thumbnail.is_some() {
+                    "   "
+                } else {
+                    "📄 "
+                };
+                let marked = app.marked.contains(&entry.path);
+                let prefix = match (marked, app.pinned.contains(&i)) {
+                    (true, true) => "✚ 📌 ",
+                    (true, false) => "✚ ",
+                    (false, true) => "📌 ",
+                    (false, false) => "",
+                };
+                // Grouped results start their group with a dimmed header
+                let header = app.group_header(position).map(|group| {
+                    let size = app.group_sizes.get(&group).copied().unwrap_or(0);
+                    let name = match group.as_os_str().is_empty() {
+                        true => "./".to_string(),
+                        false => format!("{}/", group.display()),
+                    };
+                    let text = match app.collapsed.contains(&group) {
+                        true => format!("▸ {} (+{} more)", name, size.saturating_sub(1)),
+                        false => format!("▾ {} ({})", name, size),
+                    };
+                    Line::styled(text, Style::default().add_modifier(Modifier::DIM))
+                });
+                let header_rows = u16::from(header.is_some());
+                thumbnail_columns
+                    .push(thumbnail.map(|thumbnail| (Span::raw(prefix).width() as u16, header_rows, thumbnail)));
+                let style = if marked {
+                    Style::default().fg(app.config.chrome.colors.marked).add_modifier(Modifier::BOLD)
+                } else if show_ages && let Some(Some(modified)) = app.mtimes.get(&entry.path) {
+                    app.config.age_scale.style(*modified)
+                } else {
+                    Style::default()
+                };
+                let matched_style = style.fg(app.config.chrome.colors.matched).add_modifier(Modifier::BOLD);
+                let mut spans = vec![Span::styled(format!("{}{}", prefix, icon), style)];
+                spans.extend(highlight_matched(name, matched, style, matched_style));
+                if show_dates && let Some(Some(date)) = app.capture_dates.get(&entry.path) {
+                    spans.push(Span::styled(
+                        format!("  {}", date),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                for tag in app.tags.get(&entry.path) {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        format!(" {} ", tag),
+                        Style::default().fg(Color::Black).bg(tags::color(tag)),
+                    ));
+                }
+                if let Some(note) = app.notes.get(&entry.path) {
+                    spans.push(Span::styled(
+                        format!("  {}", note),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                let mut lines: Vec<Line> = header.into_iter().chain([Line::from(spans)]).collect();
+                if show_excerpts
+                    && let Some(Some(excerpt)) = app.excerpts.get(&(entry.path.clone(), entry.line))
+                {
+                    lines.push(Line::styled(
+                        format!("      {}", excerpt),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                ListItem::new(lines)
+            })
+            .collect();
+
+        let heights: Vec<usize> = items.iter().map(ListItem::height).collect();
+        let items_list = List::new(items)
+            .highlight_style(Style::default().bg(app.config.chrome.colors.selection).fg(Color::Black))
+            .highlight_symbol(">> ");
+
+        f.render_widget(block, area);
+        f.render_widget(Paragraph::new(header), chunks[0]);
+        f.render_stateful_widget(items_list, inner, &mut app.list_state);
+
+        app.placements.clear();
+        if app.show_thumbnails && app.plan.is_none() {
+            // After the highlight symbol
+            let left = inner.x + if app.list_state.selected().is_some() { 3 } else { 0 };
+            let bottom = inner.y + inner.height;
+            let mut row = inner.y;
+            for position in app.list_state.offset()..thumbnail_columns.len() {
+                if row >= bottom {
+                    break;
+                }
+                if let Some((column, header_rows, thumbnail)) = &thumbnail_columns[position]
+                    && row + header_rows < bottom
+                {
+                    app.placements.push(Placement {
+                        column: left + column,
+                        row: row + header_rows,
+                        width: 2,
+                        height: 1,
+                        thumbnail: thumbnail.clone(),
+                    });
+                }
+                row += heights[position] as u16;
+            }
+        }
+    }
+
+    fn action(&self, app: &App, key: KeyCode) -> Option<Action> {
+        match key {
+            KeyCode::Down | KeyCode::Char('j') => Some(Action::Next),
+            KeyCode::Char('z') if app.grouping() => Some(Action::ToggleGroup),
+            KeyCode::Char('Z') if app.grouping() => Some(Action::ToggleAllGroups),
+            KeyCode::Up | KeyCode::Char('k') => Some(Action::Previous),
+            KeyCode::Char('G') => Some(Action::Bottom),
+            KeyCode::Char(' ') => Some(Action::ToggleMark),
+            KeyCode::Char('P') => Some(Action::TogglePin),
+            KeyCode::Char('H') => Some(Action::Dismiss),
+            KeyCode::Char('y') => Some(Action::Yank),
+            KeyCode::Char('x') => Some(Action::Cut),
+            KeyCode::Char('p') if app.config.source == Source::Browse => Some(Action::Paste),
+            KeyCode::Char('X') => Some(Action::UndismissAll),
+            _ => None,
+        }
+    }
+}
+
+/// `text` split into spans, the characters at `matched` (char indices, as
+/// `fuzzy_match` reports them) in `matched_style` and the rest in `style`.
+fn highlight_matched(text: String, matched: &[usize], style: Style, matched_style: Style) -> Vec<Span<'static>> {
+    if matched.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in text.chars().enumerate() {
+        let is_matched = matched.binary_search(&index).is_ok();
+        if is_matched != run_matched && !run.is_empty() {
+            let style = if run_matched { matched_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    spans.push(Span::styled(run, if run_matched { matched_style } else { style }));
+    spans
+}
+
+/// The selected file's content, scrolled, folded and with the line of
+/// interest highlighted. Keys move the cursor while it has focus and scroll
+/// it otherwise.
+pub struct PreviewPane;
+
+impl Component for PreviewPane {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        let inner = app.config.chrome.block("Preview").inner(area);
+        app.pane_areas.preview = inner;
+        let preview_content = if let Some(ref content) = app.preview_content {
+            // Parse the whole content so styles spanning lines carry over
+            let mut lines = ansi::to_text(content).lines;
+            let visible_height = inner.height as usize;
+            let searching = app.config.source == Source::Grep;
+            if app.center_preview {
+                let focus = app.preview_focus.unwrap_or(1).saturating_sub(1);
+                let above = match app.config.grep_context {
+                    Some(context) if searching => context.min(visible_height.saturating_sub(1)),
+                    _ => visible_height / 2,
+                };
+                app.preview_scroll = focus.saturating_sub(above);
+                app.center_preview = false;
+            }
+            let start_line = app.preview_scroll;
+
+            // Highlight the line of interest (diagnostic or `file:line` target)
+            if let Some(line) = app.preview_focus.and_then(|line| lines.get_mut(line.saturating_sub(1))) {
+                *line = line.clone().style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            // and in content search, the searched text wherever it occurs
+            let mut matched_lines = Vec::new();
+            if searching && !app.filter.is_empty() {
+                let style = Style::default().fg(Color::Black).bg(Color::Yellow);
+                for (index, line) in lines.iter_mut().enumerate() {
+                    let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+                    let ranges = grep::find_all(&text, &app.filter);
+                    if !ranges.is_empty() {
+                        *line = highlight_ranges(line, &ranges, style);
+                        matched_lines.push(index);
+                    }
+                }
+            }
+            let total_lines = lines.len();
+            if app.preview_focused
+                && let Some(line) = lines.get_mut(app.preview_cursor)
+            {
+                *line = line.clone().patch_style(Style::default().bg(Color::DarkGray));
+            }
+
+            // Collapse folded blocks to their first line
+            if !app.folds.is_empty() {
+                lines = fold::visible_lines(&app.preview_levels, &app.folds)
+                    .into_iter()
+                    .filter_map(|(index, hidden)| {
+                        let mut line = lines.get(index)?.clone();
+                        if hidden > 0 {
+                            line.push_span(Span::styled(
+                                format!(" ··· {} lines", hidden),
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                        Some(line)
+                    })
+                    .collect();
+            }
+
+            let visible_lines = if start_line < lines.len() {
+                let end_line = std::cmp::min(start_line + visible_height, lines.len());
+                lines[start_line..end_line].to_vec()
+            } else {
+                Vec::new()
+            };
+
+            // Show scroll indicators
+            let scroll_info = if lines.len() > visible_height {
+                format!(" [{}..{}/{}]", start_line + 1,
+                       std::cmp::min(start_line + visible_height, lines.len()),
+                       lines.len())
+            } else {
+                String::new()
+            };
+
+            let changed = match app.preview_changed {
+                true => " — file changed, press R to reload",
+                false => "",
+            };
+            (Text::from(visible_lines), format!("Preview{}{}", scroll_info, changed), matched_lines, total_lines)
+        } else {
+            (Text::raw("Select a file to preview"), "Preview".to_string(), Vec::new(), 0)
+        };
+
+        let preview_border = if app.preview_focused {
+            Style::default().fg(app.config.chrome.colors.focus)
+        } else {
+            Style::default()
+        };
+        let preview_widget = Paragraph::new(preview_content.0)
+            .block(app.config.chrome.block(preview_content.1).border_style(preview_border))
+            .style(Style::default().fg(app.config.chrome.colors.preview));
+
+        f.render_widget(preview_widget, area);
+        if let Some(placement) = app.preview_placement(inner) {
+            app.placements.push(placement);
+        }
+        if app.config.grep_minimap && !preview_content.2.is_empty() {
+            draw_minimap(f, area, inner, &preview_content.2, preview_content.3);
+        }
+    }
+
+    fn action(&self, app: &App, key: KeyCode) -> Option<Action> {
+        if !app.preview_focused {
+            return match key {
+                KeyCode::Left | KeyCode::Char('h') => Some(Action::ScrollPreview(-5)),
+                KeyCode::Right | KeyCode::Char('l') => Some(Action::ScrollPreview(5)),
+                _ => None,
+            };
+        }
+        match key {
+            KeyCode::Down | KeyCode::Char('j') => Some(Action::MovePreviewCursor(1)),
+            KeyCode::Up | KeyCode::Char('k') => Some(Action::MovePreviewCursor(-1)),
+            KeyCode::Char('z') => Some(Action::ToggleFold),
+            KeyCode::Char('Z') => Some(Action::ToggleAllFolds),
+            KeyCode::Enter => Some(Action::FollowPath),
+            _ => None,
+        }
+    }
+}
+
+/// Restyle the parts of `line` at the byte `ranges` of its text with
+/// `style`, splitting spans where a range starts or ends inside one.
+fn highlight_ranges(line: &Line<'static>, ranges: &[std::ops::Range<usize>], style: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in &line.spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+        let mut cut = offset;
+        for range in ranges.iter().filter(|range| range.start < end && range.end > offset) {
+            let start = range.start.max(offset);
+            if start > cut {
+                spans.push(Span::styled(content[cut - offset..start - offset].to_string(), span.style));
+            }
+            let stop = range.end.min(end);
+            spans.push(Span::styled(content[start - offset..stop - offset].to_string(), span.style.patch(style)));
+            cut = stop;
+        }
+        if cut < end {
+            spans.push(Span::styled(content[cut - offset..].to_string(), span.style));
+        }
+        offset = end;
+    }
+    Line {
+        spans,
+        style: line.style,
+        alignment: line.alignment,
+    }
+}
+
+/// Mark where `matched` lines (0-based, of `total`) are in the file on the
+/// right edge of the preview: its border, or the last column without one.
+fn draw_minimap(f: &mut Frame, area: Rect, inner: Rect, matched: &[usize], total: usize) {
+    let column = if inner.right() < area.right() { area.right() - 1 } else { inner.right().saturating_sub(1) };
+    let height = inner.height as usize;
+    if height == 0 || total == 0 {
+        return;
+    }
+    let buf = f.buffer_mut();
+    for &line in matched {
+        let row = inner.y + (line * height / total) as u16;
+        buf[(column, row)].set_symbol("▐").set_fg(Color::Yellow);
+    }
+}
+
+/// The mode and whatever is being typed in it: the filter, a note, a tag,
+/// a command, a file name or a confirmation.
+pub struct PromptLine;
+
+impl PromptLine {
+    pub fn text(app: &App) -> String {
+        let mode = mode_indicator(&app.mode);
+        match app.mode {
+            Mode::Annotate => format!("-- {} -- | Note: {}", mode, app.note_input),
+            Mode::Command => format!("-- {} -- | :{}", mode, app.command_input),
+            Mode::Confirm => match (&app.pending_purge, &app.plan) {
+                (None, Some(plan)) => {
+                    format!("-- {} -- | Apply {} operations? {}", mode, plan.operations.len(), app.confirm_input)
+                }
+                (target, _) => {
+                    let target = target.as_deref().map(safe_filename_to_string).unwrap_or_default();
+                    format!("-- {} -- | Delete {}? {}", mode, target, app.confirm_input)
+                }
+            },
+            Mode::SaveAs => format!("-- {} -- | Name: {}", mode, app.save_name),
+            Mode::Rename => format!("-- {} -- | Rename to: {}", mode, app.rename_input),
+            Mode::Create if app.creating_dir => format!("-- {} -- | New directory: {}", mode, app.create_input),
+            Mode::Create => format!("-- {} -- | New file: {}", mode, app.create_input),
+            Mode::Plan => format!("-- {} -- | Apply this plan?", mode),
+            Mode::Tag => format!("-- {} -- | Tag: {}", mode, app.tag_input),
+            Mode::Normal | Mode::Insert => {
+                let filter = if app.filter.is_empty() { "<empty>" } else { app.filter.as_str() };
+                let mut text = format!("-- {} -- | Filter: {}", mode, filter);
+                if app.config.source == Source::Browse {
+                    text.push_str(if app.config.hidden { " | Dot-files: shown" } else { " | Dot-files: hidden" });
+                }
+                if let Some(marked) = app.marked_summary() {
+                    text.push_str(&format!(" | {}", marked));
+                }
+                if let Some((copied, total)) = app.paste_progress {
+                    text.push_str(&format!(
+                        " | Pasting {} of {} (Esc to stop)",
+                        preview::human_size(copied),
+                        preview::human_size(total)
+                    ));
+                } else if let Some(register) = &app.register {
+                    let verb = if register.cut { "cut" } else { "yanked" };
+                    text.push_str(&format!(" | {} {}", register.paths.len(), verb));
+                }
+                text
+            }
+        }
+    }
+}
+
+impl Component for PromptLine {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        let prompt = Paragraph::new(PromptLine::text(app)).style(Style::default().fg(mode_color(&app.mode)));
+        f.render_widget(prompt, area);
+    }
+
+    /// Editing keys for the input of the current mode. Enter and Esc are
+    /// left to the caller, as what they do depends on the mode.
+    fn action(&self, app: &App, key: KeyCode) -> Option<Action> {
+        if matches!(app.mode, Mode::Normal | Mode::Plan) {
+            return None;
+        }
+        match key {
+            KeyCode::Backspace => Some(Action::Backspace),
+            KeyCode::Char(c) => Some(Action::Type(c)),
+            _ => None,
+        }
+    }
+}
+
+/// The bottom bar: the prompt line followed by the keys for the current
+/// mode, or a status message in their place.
+pub struct StatusBar;
+
+impl Component for StatusBar {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        let help_text = match app.mode {
+            Mode::Normal if app.preview_focused => "j/k: move cursor | z: fold/unfold | Z: fold/unfold all | Enter: go to path on line | Tab/Esc: back to list | q: quit",
+            Mode::Normal if app.gallery => "←↓↑→/hjkl: move | Enter: open | Space: mark | :gallery: back to the list | i/: insert mode | q: quit",
+            Mode::Normal if app.config.source == Source::Trash => "j/k: navigate | r: restore | D: purge | Enter: open | i/: insert mode | q: quit",
+            Mode::Normal if app.config.source == Source::Mounts => "j/k: navigate | Enter: browse | m: mount | u: unmount | i/: insert mode | q: quit",
+            Mode::Normal if app.config.chooser.as_ref().is_some_and(|request| request.save) => "j/k: navigate | Enter: open directory, or overwrite file | c: save here | i/: insert mode | q: cancel",
+            Mode::Normal if app.config.chooser.as_ref().is_some_and(|request| request.directory) => "j/k: navigate | Enter: open | c: choose selected or current directory | Space: mark | i/: insert mode | q: cancel",
+            Mode::Normal if app.config.chooser.is_some() => "j/k: navigate | Enter: choose | Space: mark | c: choose marked | i/: insert mode | q: cancel",
+            Mode::Normal => "j/k: navigate | h/l: scroll preview | Enter: open | i/: insert mode | gg/G: top/bottom | Tab: preview | v: hide preview | Space: mark | dd: trash | y/x/p: yank/cut/paste | P: pin | H: hide | a: note | t: tag | Ctrl+r: history | q: quit | Esc: clear filter",
+            Mode::Insert => "Type to filter | Enter: open | Esc: normal mode | Backspace: delete char",
+            Mode::Annotate => "Enter: save (empty removes) | Esc: cancel",
+            Mode::Tag => "Enter: add tag, or remove it if present | Esc: cancel",
+            Mode::Command => "trash | delete | move <dir> | copy <dir> | oplog: operation log | history: selection history | Enter: run | Esc: cancel",
+            Mode::SaveAs => "Enter: save in this directory | Esc: cancel",
+            Mode::Rename => "Enter: rename | Esc: cancel",
+            Mode::Create => "Enter: create, with any missing directories on the way | Esc: cancel",
+            Mode::Plan => "y: apply (deletes are permanent) | e: export as JSON | j/k: scroll | Esc/n: cancel",
+            Mode::Confirm if app.pending_purge.is_none() => {
+                "Running as root: type yes and press Enter to apply the plan | Esc: back to the plan"
+            }
+            Mode::Confirm => "Running as root: type yes and press Enter to permanently delete | Esc: cancel",
+        };
+
+        // Minimal chrome drops the keys, keeping only what changes
+        let help_text = match &app.status_message {
+            Some(message) => format!(" | {}", message),
+            None if app.config.timings && app.config.chrome.minimal => format!(" | {}", app.timings.summary()),
+            None if app.config.timings => format!(" | {} | {}", app.timings.summary(), help_text),
+            None if app.config.chrome.minimal => String::new(),
+            None => format!(" | {}", help_text),
+        };
+        let help = Paragraph::new(help_text).style(Style::default().fg(mode_color(&app.mode)));
+
+        let block = app.config.chrome.block("Help").style(Style::default().fg(mode_color(&app.mode)));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let prompt_width = Span::raw(PromptLine::text(app)).width() as u16;
+        let spinner_width = Spinner::text(app).map_or(0, |text| Span::raw(text).width() as u16);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(prompt_width),
+                Constraint::Length(spinner_width),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+        PromptLine.render(f, chunks[0], app);
+        Spinner.render(f, chunks[1], app);
+        f.render_widget(help, chunks[2]);
+    }
+}
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each spinner frame shows.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Turns while a directory is being read or background tasks (previews,
+/// excerpts, thumbnails, sizes, dates) are queued, with what is going on,
+/// so the UI never looks frozen.
+pub struct Spinner;
+
+impl Spinner {
+    /// The frame showing now. The screen needs drawing again when it
+    /// changes.
+    pub fn frame() -> usize {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+        (now.as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER_FRAMES.len()
+    }
+
+    fn text(app: &App) -> Option<String> {
+        let activity = app.activity()?;
+        Some(format!(" | {} {}", SPINNER_FRAMES[Spinner::frame()], activity))
+    }
+}
+
+impl Component for Spinner {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        if let Some(text) = Spinner::text(app) {
+            f.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Magenta)), area);
+        }
+    }
+}
+
+/// A box drawn over whatever is below it: the plan of a batch operation,
+/// with conflicting operations in red.
+pub struct Popup;
+
+impl Component for Popup {
+    fn render(&self, f: &mut Frame, area: Rect, app: &mut App) {
+        let Some(plan) = &app.plan else {
+            return;
+        };
+        let conflicts = app.plan_conflicts.iter().flatten().count();
+        let lines: Vec<Line> = plan
+            .operations
+            .iter()
+            .zip(&app.plan_conflicts)
+            .skip(app.plan_scroll)
+            .map(|(op, conflict)| match conflict {
+                Some(conflict) => Line::from(vec![
+                    Span::styled(format!("✗ {}", op), Style::default().fg(Color::Red)),
+                    Span::styled(format!("  {}", conflict), Style::default().fg(Color::Red).add_modifier(Modifier::DIM)),
+                ]),
+                // Directories go with everything in them
+                None => match op {
+                    FileOp::Delete { path } if path.is_dir() => Line::from(vec![
+                        Span::raw(format!("  {}", op)),
+                        Span::styled("  and everything in it", Style::default().add_modifier(Modifier::DIM)),
+                    ]),
+                    _ => Line::raw(format!("  {}", op)),
+                },
+            })
+            .collect();
+        let title = if conflicts > 0 {
+            format!("Plan: {} operations, {} conflicts", plan.operations.len(), conflicts)
+        } else {
+            format!("Plan: {} operations", plan.operations.len())
+        };
+        let border = if conflicts > 0 { Color::Red } else { Color::Yellow };
+        let plan_widget = Paragraph::new(lines).block(
+            app.config
+                .chrome
+                .framed(Block::default().title(title))
+                .border_style(Style::default().fg(border)),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(plan_widget, area);
+    }
+
+    fn action(&self, _app: &App, key: KeyCode) -> Option<Action> {
+        match key {
+            KeyCode::Down | KeyCode::Char('j') => Some(Action::ScrollPlan(1)),
+            KeyCode::Up | KeyCode::Char('k') => Some(Action::ScrollPlan(-1)),
+            _ => None,
+        }
+    }
+}
+
+fn mode_indicator(mode: &Mode) -> &'static str {
+    match mode {
+        Mode::Normal => "NORMAL",
+        Mode::Insert => "INSERT",
+        Mode::Annotate => "NOTE",
+        Mode::Tag => "TAG",
+        Mode::Confirm => "CONFIRM",
+        Mode::Command => "COMMAND",
+        Mode::Plan => "PLAN",
+        Mode::SaveAs => "SAVE",
+        Mode::Rename => "RENAME",
+        Mode::Create => "NEW",
+    }
+}
+
+fn mode_color(mode: &Mode) -> Color {
+    match mode {
+        Mode::Normal => Color::Cyan,
+        Mode::Insert => Color::Green,
+        Mode::Annotate | Mode::Tag | Mode::Command | Mode::SaveAs | Mode::Rename | Mode::Create => Color::Yellow,
+        Mode::Confirm | Mode::Plan => Color::Red,
+    }
+}
@@ -1,65 +1,110 @@
-mod app;
-mod config;
-mod fuzzy;
-mod mode;
-mod ui;
-
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{self, Write},
+    time::{Duration, Instant},
 };
-use ratatui::{
-    backend::{Backend, CrosstermBackend},
-    Terminal,
+
+use rats_core::{backend::FsBackend, fuzzy, query::Query};
+#[cfg(feature = "termion")]
+use rats_tui::term_termion::TermionFrontend;
+use rats_tui::{
+    action::{Action, Flow},
+    app::{App, Selection},
+    config::{Config, Source, TerminalBackend},
+    crash,
+    frame::{FrameScheduler, TARGET_FPS},
+    history, journal,
+    mode::Mode,
+    plan::Plan,
+    term::{CrosstermFrontend, Frontend},
+    thumbnail::{self, Graphics, Painter},
+    ui::ui,
+    update,
+    widgets::{Component, FileList, Popup, PreviewPane, PromptLine, Spinner},
 };
-use std::{error::Error, io, path::PathBuf};
 
-use app::App;
-use config::Config;
-use mode::Mode;
-use ui::ui;
+/// Exit status when `--timeout` or `--idle-timeout` runs out, as with
+/// coreutils' `timeout`
+const TIMED_OUT_STATUS: i32 = 124;
+
+/// How the UI finished.
+enum Outcome {
+    Done(Option<Selection>),
+    TimedOut,
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Parse configuration
-    let config = Config::from_args();
+    let mut config = Config::from_args();
+
+    if config.self_update {
+        return update::self_update(config.check_only);
+    }
+
+    if let Some(plan) = &config.apply {
+        return Plan::load(plan)?.apply(config.dry_run);
+    }
 
-    // Handle JSON mode
+    if config.resume_crashed {
+        match journal::last_state() {
+            Some(state) => {
+                config.directory = state.cwd;
+                config.query = state.query;
+                config.select = state.selected;
+                journal::clear_crashed();
+            }
+            None => eprintln!("No crashed session to resume"),
+        }
+    }
+
+    if config.crash_report {
+        crash::install(&config);
+    }
+
+    // Print the matches for the query instead of starting the UI
     if config.json_mode {
-        // JSON mode implementation would go here if needed
-        // For now, just print that it's not implemented in this refactor
-        eprintln!("JSON mode not implemented in this version");
+        let query = Query::parse(&config.query);
+        let results = fuzzy::search(&FsBackend, &config.directory, &query.pattern, config.recursive, !config.no_ignore)?;
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &results)?;
+        writeln!(stdout)?;
         return Ok(());
     }
 
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // Create app and run it
+    // Create the app before taking over the terminal, so startup errors are
+    // printed normally
+    let backend = config.backend;
+    let terminator = if config.print0 { b'\0' } else { b'\n' };
     let app = App::new(config)?;
-    let res = run_app(&mut terminal, app);
-
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    let res = match backend {
+        TerminalBackend::Crossterm => run_in(CrosstermFrontend::new()?, app),
+        #[cfg(feature = "termion")]
+        TerminalBackend::Termion => run_in(TermionFrontend::new()?, app),
+    };
+    if res.is_ok() {
+        journal::clear();
+    }
 
     match res {
-        Ok(Some(selected_file)) => {
-            // Print the selected file path for external tools (like Neovim) to capture
-            println!("{}", selected_file.display());
+        Ok(Outcome::Done(Some(selection))) => {
+            // Failing to record history should never lose the selection
+            let _ = history::record(&selection);
+            // Print the selected file path for external tools (like Neovim)
+            // to capture, byte for byte
+            let mut stdout = io::stdout().lock();
+            selection.write_to(&mut stdout, terminator)?;
+            stdout.flush()?;
         }
-        Ok(None) => {
+        Ok(Outcome::Done(None)) => {
             // User quit without selecting anything
         }
+        Ok(Outcome::TimedOut) => {
+            eprintln!("Timed out waiting for input");
+            std::process::exit(TIMED_OUT_STATUS);
+        }
         Err(err) => {
             eprintln!("Error: {:?}", err);
             return Err(err.into());
@@ -69,61 +114,255 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<Option<PathBuf>> {
+/// Run the app on `frontend`, restoring the terminal afterwards whatever
+/// the outcome.
+fn run_in<F: Frontend>(mut frontend: F, app: App) -> io::Result<Outcome> {
+    // Sixels and iTerm images leave with the alternate screen
+    let thumbnails = app.graphics == Some(Graphics::Kitty);
+    let res = run_app(&mut frontend, app);
+    if thumbnails {
+        thumbnail::place(frontend.terminal().backend_mut(), &[])?;
+    }
+    frontend.restore()?;
+    res
+}
+
+fn run_app<F: Frontend>(frontend: &mut F, mut app: App) -> io::Result<Outcome> {
+    let mut frames = FrameScheduler::new(TARGET_FPS);
+    let started = Instant::now();
+    let mut last_input = started;
+    let mut on_start: VecDeque<Action> = std::mem::take(&mut app.config.on_start).into();
+    // Thumbnails currently on screen, redrawn only when they change
+    let mut placed = Vec::new();
+    let mut painter = app.graphics.map(|graphics| Painter::new(graphics, app.magick()));
+    let mut spinner_frame = Spinner::frame();
     loop {
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match app.mode {
-                    Mode::Normal => {
-                        match key.code {
-                            KeyCode::Char('q') => return Ok(None),
-                            KeyCode::Char('i') => app.set_mode(Mode::Insert),
-                            KeyCode::Char('/') => app.set_mode(Mode::Insert),
-                            KeyCode::Down | KeyCode::Char('j') => app.next(),
-                            KeyCode::Up | KeyCode::Char('k') => app.previous(),
-                            KeyCode::Left | KeyCode::Char('h') => app.scroll_preview_up(),
-                            KeyCode::Right | KeyCode::Char('l') => app.scroll_preview_down(),
-                            KeyCode::Char('g') => {
-                                // Handle 'gg' - go to top
-                                if let Event::Key(next_key) = event::read()? {
-                                    if next_key.kind == KeyEventKind::Press {
-                                        if let KeyCode::Char('g') = next_key.code {
-                                            app.go_to_top();
-                                        }
-                                    }
-                                }
-                            },
-                            KeyCode::Char('G') => app.go_to_bottom(),
-                            KeyCode::Enter => {
-                                match app.enter_selected() {
-                                    Ok(Some(path)) => return Ok(Some(path)),
-                                    Ok(None) => {}, // Directory navigation, continue
-                                    Err(_) => {}, // Handle error if needed
-                                }
-                            }
-                            KeyCode::Esc => app.clear_filter(),
-                            _ => {}
-                        }
-                    },
-                    Mode::Insert => {
-                        match key.code {
-                            KeyCode::Esc => app.set_mode(Mode::Normal),
-                            KeyCode::Enter => {
-                                match app.enter_selected() {
-                                    Ok(Some(path)) => return Ok(Some(path)),
-                                    Ok(None) => {}, // Directory navigation, continue
-                                    Err(_) => {}, // Handle error if needed
-                                }
-                            }
-                            KeyCode::Backspace => app.remove_char_from_filter(),
-                            KeyCode::Char(c) => app.add_char_to_filter(c),
-                            _ => {}
-                        }
-                    }
+        if app.config.timeout.is_some_and(|limit| started.elapsed() >= limit)
+            || app.config.idle_timeout.is_some_and(|limit| last_input.elapsed() >= limit)
+        {
+            return Ok(Outcome::TimedOut);
+        }
+        // Each `--on-start` step waits for any listing the one before started
+        while app.loading.is_none()
+            && let Some(action) = on_start.pop_front()
+        {
+            frames.mark_dirty();
+            if let Flow::Exit(selection) = app.dispatch(action)?
+                && let Some(outcome) = finish(&mut app, selection)?
+            {
+                return Ok(outcome);
+            }
+        }
+        if app.poll_background() {
+            frames.mark_dirty();
+        }
+        // Actions bound to what happened since the last time round
+        let fired: Vec<Action> =
+            app.take_events().into_iter().flat_map(|event| app.config.keymap.on(event).to_vec()).collect();
+        for action in fired {
+            frames.mark_dirty();
+            if let Flow::Exit(selection) = app.dispatch(action)?
+                && let Some(outcome) = finish(&mut app, selection)?
+            {
+                return Ok(outcome);
+            }
+        }
+        if app.activity().is_some() && Spinner::frame() != spinner_frame {
+            spinner_frame = Spinner::frame();
+            frames.mark_dirty();
+        }
+        if frames.should_draw(Instant::now()) {
+            frontend.terminal().draw(|f| ui(f, &mut app))?;
+            if let Some(painter) = &mut painter
+                && app.placements != placed
+            {
+                if painter.needs_redraw() {
+                    frontend.terminal().clear()?;
+                    frontend.terminal().draw(|f| ui(f, &mut app))?;
                 }
+                painter.place(frontend.terminal().backend_mut(), &app.placements)?;
+                placed = app.placements.clone();
             }
+            frames.drawn(Instant::now());
         }
+
+        // Handle everything already queued before drawing again, so bursts
+        // of input cost one frame
+        let mut timeout = frames.timeout(Instant::now());
+        while frontend.poll(timeout)? {
+            frames.mark_dirty();
+            last_input = Instant::now();
+            let event = frontend.read()?;
+            if let Flow::Exit(selection) = handle_event(&mut app, frontend, event)?
+                && let Some(outcome) = finish(&mut app, selection)?
+            {
+                return Ok(outcome);
+            }
+            timeout = Duration::ZERO;
+        }
+    }
+}
+
+/// How the UI finishes after an action asked to exit with `selection`, or
+/// `None` if it carries on after all.
+fn finish(app: &mut App, selection: Option<Selection>) -> io::Result<Option<Outcome>> {
+    // A file chooser request is answered instead of printing
+    match selection {
+        Some(selection) if app.config.chooser.is_some() => {
+            Ok(app.answer_chooser(Some(selection.path))?.then_some(Outcome::Done(None)))
+        }
+        selection => Ok(Some(Outcome::Done(selection))),
+    }
+}
+
+fn handle_event<F: Frontend>(app: &mut App, frontend: &mut F, event: Event) -> io::Result<Flow> {
+    let key = match event {
+        Event::Key(key) => key,
+        Event::Mouse(mouse) => {
+            for action in app.mouse_actions(mouse) {
+                if let Flow::Exit(selection) = app.dispatch(action)? {
+                    return Ok(Flow::Exit(selection));
+                }
+            }
+            return Ok(Flow::Continue);
+        }
+        _ => return Ok(Flow::Continue),
+    };
+    if key.kind != KeyEventKind::Press {
+        return Ok(Flow::Continue);
+    }
+    // Status messages are one-shot: dismiss on the next key press
+    app.status_message = None;
+    if key.code != KeyCode::Char('D') && app.mode != Mode::Confirm {
+        app.pending_purge = None;
+    }
+    if key.code != KeyCode::Enter {
+        app.pending_accept = None;
+    }
+
+    match key_action(app, frontend, key)? {
+        Some(action) => app.dispatch(action),
+        None => Ok(Flow::Continue),
     }
-}
\ No newline at end of file
+}
+
+/// What a key press does in the current mode: what the user bound it to,
+/// or else its built-in meaning.
+fn key_action<F: Frontend>(app: &App, frontend: &mut F, key: KeyEvent) -> io::Result<Option<Action>> {
+    // Normal mode bindings are for the list, not the focused preview
+    if !(app.mode == Mode::Normal && app.preview_focused)
+        && let Some(bound) = app.config.keymap.get(&app.mode, &key)
+    {
+        return Ok(bound);
+    }
+    if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Ok(Some(Action::ToggleHistory));
+    }
+    if key.code == KeyCode::Char('g') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Ok(Some(Action::ToggleGrep));
+    }
+    // Most terminals send Ctrl-i as Tab, so forward is on `L` too
+    if app.mode == Mode::Normal && key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('o') => return Ok(Some(Action::Back)),
+            KeyCode::Char('i') => return Ok(Some(Action::Forward)),
+            _ => {}
+        }
+    }
+
+    let action = match app.mode {
+        Mode::Normal if app.preview_focused => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Tab | KeyCode::Esc => Some(Action::TogglePreviewFocus),
+            key => PreviewPane.action(app, key),
+        },
+        Mode::Normal => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Char('i') | KeyCode::Char('/') => Some(Action::SetMode(Mode::Insert)),
+            KeyCode::Down | KeyCode::Char('j') if app.gallery => Some(Action::MoveGallery(app.grid.columns as isize)),
+            KeyCode::Up | KeyCode::Char('k') if app.gallery => Some(Action::MoveGallery(-(app.grid.columns as isize))),
+            KeyCode::Left | KeyCode::Char('h') if app.gallery => Some(Action::MoveGallery(-1)),
+            KeyCode::Right | KeyCode::Char('l') if app.gallery => Some(Action::MoveGallery(1)),
+            KeyCode::Char('g') => {
+                // Handle 'gg' - go to top
+                match frontend.read()? {
+                    Event::Key(next_key)
+                        if next_key.kind == KeyEventKind::Press && next_key.code == KeyCode::Char('g') =>
+                    {
+                        Some(Action::Top)
+                    }
+                    _ => None,
+                }
+            }
+            KeyCode::Char('d')
+                if matches!(app.config.source, Source::Browse | Source::Grep)
+                    && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                // 'dd' moves the entry to the trash, like `:trash`
+                match frontend.read()? {
+                    Event::Key(next_key)
+                        if next_key.kind == KeyEventKind::Press && next_key.code == KeyCode::Char('d') =>
+                    {
+                        Some(Action::MoveToTrash)
+                    }
+                    _ => None,
+                }
+            }
+            KeyCode::Char('m') if app.config.source == Source::Browse => match frontend.read()? {
+                // 'm<letter>' bookmarks the directory, '\'<letter>' goes back to it
+                Event::Key(next_key) if next_key.kind == KeyEventKind::Press => match next_key.code {
+                    KeyCode::Char(letter) if letter.is_alphanumeric() => Some(Action::SetBookmark(letter)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            KeyCode::Char('\'') => match frontend.read()? {
+                Event::Key(next_key) if next_key.kind == KeyEventKind::Press => match next_key.code {
+                    KeyCode::Char(letter) if letter.is_alphanumeric() => Some(Action::JumpToBookmark(letter)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            KeyCode::Char('M') => Some(Action::ToggleBookmarks),
+            KeyCode::Char('L') => Some(Action::Forward),
+            KeyCode::Char('J') => Some(Action::ToggleJump),
+            KeyCode::Char('r') if app.config.source == Source::Trash => Some(Action::Restore),
+            KeyCode::Char('r') if app.config.source == Source::Browse => Some(Action::Rename),
+            KeyCode::Char('n') if app.config.source == Source::Browse => Some(Action::Create(false)),
+            KeyCode::Char('N') if app.config.source == Source::Browse => Some(Action::Create(true)),
+            KeyCode::Char('R') => Some(Action::ReloadPreview),
+            KeyCode::Char('D') if app.config.source == Source::Trash => Some(Action::Purge),
+            KeyCode::Char('D') if matches!(app.config.source, Source::Browse | Source::Grep) => Some(Action::Delete),
+            KeyCode::Char('a') => Some(Action::Annotate),
+            KeyCode::Char('t') => Some(Action::SetMode(Mode::Tag)),
+            KeyCode::Char('E') => Some(Action::ExportTree),
+            KeyCode::Char(':') => Some(Action::SetMode(Mode::Command)),
+            KeyCode::Char('c') if app.config.chooser.is_some() => Some(Action::Choose),
+            KeyCode::Tab => Some(Action::TogglePreviewFocus),
+            KeyCode::Char('v') => Some(Action::TogglePreview),
+            KeyCode::Char('U') => Some(Action::Decrypt),
+            KeyCode::Char('.') => Some(Action::ToggleHidden),
+            KeyCode::Char('I') => Some(Action::ToggleIgnored),
+            KeyCode::Char('o') => Some(Action::OpenContainingDir),
+            KeyCode::Char('m') if app.config.source == Source::Mounts => Some(Action::Mount(true)),
+            KeyCode::Char('u') if app.config.source == Source::Mounts => Some(Action::Mount(false)),
+            KeyCode::Enter => Some(Action::Accept),
+            KeyCode::Esc if app.loading.is_some() || app.paste_progress.is_some() => Some(Action::CancelLoading),
+            KeyCode::Esc => Some(Action::ClearFilter),
+            key => FileList.action(app, key).or_else(|| PreviewPane.action(app, key)),
+        },
+        Mode::Plan => match key.code {
+            KeyCode::Char('y') => Some(Action::ApplyPlan),
+            KeyCode::Char('e') => Some(Action::ExportPlan),
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => Some(Action::CancelPlan),
+            key => Popup.action(app, key),
+        },
+        Mode::Insert if key.code == KeyCode::Esc && app.loading.is_some() => Some(Action::CancelLoading),
+        Mode::Insert | Mode::Annotate | Mode::Command | Mode::SaveAs | Mode::Rename | Mode::Create | Mode::Confirm | Mode::Tag => match key.code {
+            KeyCode::Esc => Some(Action::CancelInput),
+            KeyCode::Enter => Some(Action::Submit),
+            key => PromptLine.action(app, key),
+        },
+    };
+    Ok(action)
+}